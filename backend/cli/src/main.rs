@@ -0,0 +1,151 @@
+// 離線配對工具：讀取 CSV/JSON 名冊，不需資料庫或網頁伺服器即可執行配對
+
+use circlematch_matcher::model::MatchResult;
+use circlematch_matcher::{report, roster};
+use circlematch_matcher::subject::SubjectCompatibility;
+use circlematch_matcher::{find_matches_with_report, MatcherConfig, MatchSortKey, PackingObjective};
+use clap::{Parser, ValueEnum};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum InputFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Csv,
+    Text,
+}
+
+/// 以 CircleMatch 配對引擎處理教師名冊，輸出找到的調動循環
+#[derive(Parser, Debug)]
+#[command(name = "circlematch", version)]
+struct Args {
+    /// 名冊檔案（CSV 或 JSON），使用 - 代表標準輸入
+    input: PathBuf,
+
+    /// 名冊格式，預設依副檔名判斷
+    #[arg(long, value_enum)]
+    input_format: Option<InputFormat>,
+
+    /// 輸出檔案，預設為標準輸出
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// 輸出格式
+    #[arg(short = 'f', long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
+    /// 搜尋的最大循環大小
+    #[arg(long, default_value_t = MatcherConfig::default().max_cycle_size)]
+    max_cycle_size: usize,
+
+    /// 排序方式：none、distance、cycle_size
+    #[arg(long, default_value = "none")]
+    sort: MatchSortKey,
+
+    /// 只輸出互不重疊的循環，並依此目標選取：max_teachers、min_distance
+    #[arg(long)]
+    packing: Option<PackingObjective>,
+
+    /// 自訂科目相容表（JSON），預設使用內建相容表
+    #[arg(long)]
+    compatibility: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("錯誤: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let input = read_input(&args.input)?;
+    let input_format = args.input_format
+        .or_else(|| detect_input_format(&args.input))
+        .ok_or("無法判斷名冊格式，請使用 --input-format 指定")?;
+
+    let mut teachers = match input_format {
+        InputFormat::Csv => roster::read_csv(input.as_bytes())?,
+        InputFormat::Json => roster::read_json(&input)?,
+    };
+    roster::assign_missing_ids(&mut teachers);
+
+    let custom_compatibility = match &args.compatibility {
+        Some(path) => {
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| format!("無法讀取科目相容表 {}: {}", path.display(), e))?;
+            Some(SubjectCompatibility::from_json(&json)
+                .map_err(|e| format!("科目相容表格式錯誤: {}", e))?)
+        }
+        None => None,
+    };
+    let compatibility = custom_compatibility.as_ref().unwrap_or_else(|| SubjectCompatibility::bundled());
+
+    let config = MatcherConfig {
+        max_cycle_size: args.max_cycle_size,
+        sort_by: args.sort,
+        packing: args.packing,
+    };
+
+    eprintln!("讀取 {} 位教師", teachers.len());
+    let finalized = find_matches_with_report(teachers, compatibility, &config);
+    let matches = finalized.matches;
+    eprintln!("找到 {} 組循環", matches.len());
+    if !finalized.exhaustive {
+        eprintln!("警告：循環選取超過搜尋上限，結果為目前找到的最佳組合，不保證調動人數最多");
+    }
+
+    let output = render(&matches, args.format)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, output)
+            .map_err(|e| format!("無法寫入 {}: {}", path.display(), e)),
+        None => std::io::stdout().write_all(output.as_bytes())
+            .map_err(|e| format!("無法輸出結果: {}", e)),
+    }
+}
+
+fn read_input(path: &Path) -> Result<String, String> {
+    if path.as_os_str() == "-" {
+        let mut buffer = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut buffer)
+            .map_err(|e| format!("無法讀取標準輸入: {}", e))?;
+        return Ok(buffer);
+    }
+    std::fs::read_to_string(path).map_err(|e| format!("無法讀取 {}: {}", path.display(), e))
+}
+
+fn detect_input_format(path: &Path) -> Option<InputFormat> {
+    match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "csv" => Some(InputFormat::Csv),
+        "json" => Some(InputFormat::Json),
+        _ => None,
+    }
+}
+
+fn render(matches: &[MatchResult], format: OutputFormat) -> Result<String, String> {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(matches)
+            .map(|json| json + "\n")
+            .map_err(|e| format!("無法輸出 JSON: {}", e)),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in report::participant_rows(matches) {
+                writer.serialize(row).map_err(|e| format!("無法輸出 CSV: {}", e))?;
+            }
+            let bytes = writer.into_inner().map_err(|e| format!("無法輸出 CSV: {}", e))?;
+            String::from_utf8(bytes).map_err(|e| format!("無法輸出 CSV: {}", e))
+        }
+        OutputFormat::Text => Ok(report::text_report(matches)),
+    }
+}
@@ -0,0 +1,66 @@
+// find_matches 的效能基準，名冊由 synthetic::generate_roster 以固定種子產生。
+// 執行：cargo bench -p circlematch-matcher；比較前後版本可先以 --save-baseline 保存，再以 --baseline 比較
+use circlematch_matcher::synthetic::{generate_roster, RosterSpec};
+use circlematch_matcher::model::Teacher;
+use circlematch_matcher::{find_matches, MatcherConfig};
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+
+fn roster(teachers: usize, max_targets: usize) -> Vec<Teacher> {
+    generate_roster(&RosterSpec { teachers, max_targets, ..Default::default() })
+}
+
+fn bench_case(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    id: BenchmarkId,
+    teachers: &[Teacher],
+    max_cycle_size: usize,
+) {
+    let config = MatcherConfig { max_cycle_size, ..Default::default() };
+    group.throughput(Throughput::Elements(teachers.len() as u64));
+    group.bench_with_input(id, teachers, |b, teachers| {
+        b.iter_batched(|| teachers.to_vec(), |teachers| find_matches(teachers, &config), BatchSize::LargeInput)
+    });
+}
+
+// 登記數量：每人最多三個志願，循環最多四人
+fn roster_size(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_matches/roster_size");
+    group.sample_size(10);
+    for teachers in [1_000, 2_000, 5_000] {
+        bench_case(&mut group, BenchmarkId::from_parameter(teachers), &roster(teachers, 3), 4);
+    }
+    group.finish();
+}
+
+// 偏好圖密度：每人的志願數量上限
+fn density(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_matches/max_targets");
+    group.sample_size(10);
+    for max_targets in [1, 3, 6] {
+        bench_case(&mut group, BenchmarkId::from_parameter(max_targets), &roster(2_000, max_targets), 6);
+    }
+    group.finish();
+}
+
+// 最大循環大小，預設值為 10
+fn cycle_size(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_matches/max_cycle_size");
+    let teachers = roster(1_000, 3);
+    for max_cycle_size in [2, 4, 6, 10] {
+        bench_case(&mut group, BenchmarkId::from_parameter(max_cycle_size), &teachers, max_cycle_size);
+    }
+    group.finish();
+}
+
+// 全國規模：兩萬筆登記、每人一個志願
+fn national_scale(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_matches/national_scale");
+    group.sample_size(10).measurement_time(Duration::from_secs(30));
+    bench_case(&mut group, BenchmarkId::from_parameter(20_000), &roster(20_000, 1), 4);
+    group.finish();
+}
+
+criterion_group!(benches, roster_size, density, cycle_size, national_scale);
+criterion_main!(benches);
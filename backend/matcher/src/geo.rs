@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+// 地球平均半徑（公里）
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+#[derive(Deserialize)]
+struct CountyCentroids {
+    name: String,
+    districts: Vec<DistrictCentroid>,
+}
+
+#[derive(Deserialize)]
+struct DistrictCentroid {
+    name: String,
+    lat: f64,
+    lng: f64,
+}
+
+// 各區域中心點座標快取：(縣市, 區域) -> (緯度, 經度)
+static DISTRICT_CENTROIDS: Lazy<HashMap<(String, String), (f64, f64)>> = Lazy::new(|| {
+    let json_data = include_str!("../../data/taiwan_district_centroids.json");
+    let counties: Vec<CountyCentroids> = serde_json::from_str(json_data).unwrap_or_else(|e| {
+        tracing::error!("區域中心點資料解析失敗: {}", e);
+        Vec::new()
+    });
+
+    counties.into_iter()
+        .flat_map(|county| {
+            let county_name = county.name;
+            county.districts.into_iter()
+                .map(move |d| ((county_name.clone(), d.name), (d.lat, d.lng)))
+        })
+        .collect()
+});
+
+// 取得區域中心點座標（緯度, 經度）
+pub fn district_centroid(county: &str, district: &str) -> Option<(f64, f64)> {
+    DISTRICT_CENTROIDS
+        .get(&(county.trim().to_string(), district.trim().to_string()))
+        .copied()
+}
+
+// 兩點間的大圓距離（公里）
+pub fn haversine_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lng1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lng2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lng2 - lng1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// 兩個區域中心點之間的距離，任一區域沒有座標時返回 None
+pub fn district_distance_km(
+    from_county: &str,
+    from_district: &str,
+    to_county: &str,
+    to_district: &str,
+) -> Option<f64> {
+    let from = district_centroid(from_county, from_district)?;
+    let to = district_centroid(to_county, to_district)?;
+    Some(haversine_km(from, to))
+}
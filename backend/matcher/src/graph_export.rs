@@ -0,0 +1,149 @@
+use crate::matcher::PreferenceGraph;
+use crate::model::Teacher;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+// 支援的偏好圖匯出格式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    Dot,
+    Graphml,
+    #[default]
+    Json,
+}
+
+impl GraphFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "text/vnd.graphviz; charset=utf-8",
+            GraphFormat::Graphml => "application/graphml+xml; charset=utf-8",
+            GraphFormat::Json => "application/json",
+        }
+    }
+}
+
+pub fn export_graph(graph: &PreferenceGraph, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => to_dot(graph),
+        GraphFormat::Graphml => to_graphml(graph),
+        GraphFormat::Json => to_node_link_json(graph).to_string(),
+    }
+}
+
+fn node_display_id(teacher: &Teacher, index: usize) -> String {
+    teacher.display_id.clone().unwrap_or_else(|| format!("#{}", index))
+}
+
+fn node_location(teacher: &Teacher) -> String {
+    format!("{}{}", teacher.current_county, teacher.current_district)
+}
+
+// Graphviz DOT 格式，循環中的邊以紅色粗線標示
+pub fn to_dot(graph: &PreferenceGraph) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let cycle_edges = graph.cycle_edges();
+    let mut out = String::from("digraph preference {\n    rankdir=LR;\n    node [shape=box];\n");
+
+    for (i, teacher) in graph.teachers.iter().enumerate() {
+        let in_cycle = cycle_edges.iter().any(|&(from, to)| from == i || to == i);
+        out.push_str(&format!(
+            "    n{} [label=\"{}\\n{}\"{}];\n",
+            i,
+            escape(&node_display_id(teacher, i)),
+            escape(&node_location(teacher)),
+            if in_cycle { ", color=red" } else { "" },
+        ));
+    }
+
+    for &(from, to) in &graph.edges {
+        let style = if cycle_edges.contains(&(from, to)) {
+            " [color=red, penwidth=2]"
+        } else {
+            ""
+        };
+        out.push_str(&format!("    n{} -> n{}{};\n", from, to, style));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+// GraphML 格式
+pub fn to_graphml(graph: &PreferenceGraph) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let cycle_edges = graph.cycle_edges();
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"teacher_id\" for=\"node\" attr.name=\"teacher_id\" attr.type=\"int\"/>\n",
+        "  <key id=\"county\" for=\"node\" attr.name=\"county\" attr.type=\"string\"/>\n",
+        "  <key id=\"district\" for=\"node\" attr.name=\"district\" attr.type=\"string\"/>\n",
+        "  <key id=\"subject\" for=\"node\" attr.name=\"subject\" attr.type=\"string\"/>\n",
+        "  <key id=\"in_cycle\" for=\"edge\" attr.name=\"in_cycle\" attr.type=\"boolean\"/>\n",
+        "  <graph id=\"preference\" edgedefault=\"directed\">\n",
+    ));
+
+    for (i, teacher) in graph.teachers.iter().enumerate() {
+        out.push_str(&format!("    <node id=\"n{}\">\n", i));
+        out.push_str(&format!("      <data key=\"label\">{}</data>\n", escape(&node_display_id(teacher, i))));
+        if let Some(id) = teacher.id {
+            out.push_str(&format!("      <data key=\"teacher_id\">{}</data>\n", id));
+        }
+        out.push_str(&format!("      <data key=\"county\">{}</data>\n", escape(&teacher.current_county)));
+        out.push_str(&format!("      <data key=\"district\">{}</data>\n", escape(&teacher.current_district)));
+        out.push_str(&format!("      <data key=\"subject\">{}</data>\n", escape(&teacher.subject)));
+        out.push_str("    </node>\n");
+    }
+
+    for (k, &(from, to)) in graph.edges.iter().enumerate() {
+        out.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">\n      <data key=\"in_cycle\">{}</data>\n    </edge>\n",
+            k, from, to, cycle_edges.contains(&(from, to)),
+        ));
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+// node-link JSON 格式（與 networkx 的 node_link_data 相容）
+pub fn to_node_link_json(graph: &PreferenceGraph) -> Value {
+    let cycle_edges = graph.cycle_edges();
+
+    let nodes: Vec<Value> = graph.teachers.iter().enumerate()
+        .map(|(i, teacher)| json!({
+            "id": i,
+            "teacher_id": teacher.id,
+            "label": node_display_id(teacher, i),
+            "display_id": teacher.display_id,
+            "county": teacher.current_county,
+            "district": teacher.current_district,
+            "location": node_location(teacher),
+            "subject": teacher.subject,
+        }))
+        .collect();
+
+    let links: Vec<Value> = graph.edges.iter()
+        .map(|&(from, to)| json!({
+            "source": from,
+            "target": to,
+            "in_cycle": cycle_edges.contains(&(from, to)),
+        }))
+        .collect();
+
+    json!({
+        "directed": true,
+        "multigraph": false,
+        "graph": {},
+        "nodes": nodes,
+        "links": links,
+        "cycles": graph.cycles,
+    })
+}
@@ -0,0 +1,13 @@
+// CircleMatch 配對引擎：教師介聘的循環偵測、距離計算與報表輸出
+// 不依賴資料庫或網頁伺服器，可供 API 與離線命令列工具共用
+
+pub mod model;
+pub mod subject;
+pub mod geo;
+pub mod matcher;
+pub mod graph_export;
+pub mod report;
+pub mod roster;
+pub mod synthetic;
+
+pub use matcher::{find_matches, find_matches_with, find_matches_with_report, FinalizedMatches, MatcherConfig, MatchSortKey, PackingObjective, PartitionKey};
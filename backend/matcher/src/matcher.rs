@@ -0,0 +1,704 @@
+use crate::model::{Teacher, MatchResult, CycleMove};
+use crate::subject::SubjectCompatibility;
+use crate::geo;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use itertools::Itertools;
+
+// 配對結果的排序方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchSortKey {
+    // 不排序，保持搜尋順序
+    #[default]
+    None,
+    // 依總調動距離由近到遠
+    Distance,
+    // 依循環大小由小到大，同大小再依距離
+    CycleSize,
+}
+
+// 選出互不重疊的循環時要優先的目標
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PackingObjective {
+    // 讓最多教師調動成功，同數量時選距離較近的。以分支定界搜尋，
+    // 重疊群組過大而超過搜尋節點上限時改用目前找到的最佳組合，結果會標示為未窮舉
+    MaxTeachers,
+    // 依平均距離由近到遠貪婪選取，不保證整體平均距離最短
+    MinDistance,
+}
+
+impl FromStr for MatchSortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().replace('-', "_").as_str() {
+            "none" => Ok(MatchSortKey::None),
+            "distance" => Ok(MatchSortKey::Distance),
+            "cycle_size" => Ok(MatchSortKey::CycleSize),
+            other => Err(format!("未知的排序方式: {}（可用 none/distance/cycle_size）", other)),
+        }
+    }
+}
+
+impl FromStr for PackingObjective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().replace('-', "_").as_str() {
+            "max_teachers" => Ok(PackingObjective::MaxTeachers),
+            "min_distance" => Ok(PackingObjective::MinDistance),
+            other => Err(format!("未知的選取目標: {}（可用 max_teachers/min_distance）", other)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MatcherConfig {
+    // 搜尋的最大循環大小
+    pub max_cycle_size: usize,
+    pub sort_by: MatchSortKey,
+    // 設定時只返回互不重疊的循環（每位教師最多出現一次）
+    pub packing: Option<PackingObjective>,
+}
+
+impl Default for MatcherConfig {
+    fn default() -> Self {
+        MatcherConfig {
+            max_cycle_size: 10,
+            sort_by: MatchSortKey::None,
+            packing: None,
+        }
+    }
+}
+
+pub fn find_matches(teachers: Vec<Teacher>, config: &MatcherConfig) -> Vec<MatchResult> {
+    find_matches_with(teachers, SubjectCompatibility::bundled(), config)
+}
+
+// 使用指定的科目相容表進行配對
+pub fn find_matches_with(
+    teachers: Vec<Teacher>,
+    compatibility: &SubjectCompatibility,
+    config: &MatcherConfig,
+) -> Vec<MatchResult> {
+    find_matches_with_report(teachers, compatibility, config).matches
+}
+
+// 與 find_matches_with 相同，另外返回循環選取是否完整搜尋
+pub fn find_matches_with_report(
+    teachers: Vec<Teacher>,
+    compatibility: &SubjectCompatibility,
+    config: &MatcherConfig,
+) -> FinalizedMatches {
+    let results = group_by_partition(&teachers, compatibility)
+        .into_values()
+        .flat_map(|partition| partition_cycles(&partition, compatibility, config.max_cycle_size))
+        .collect();
+
+    finalize_matches_with_report(results, config)
+}
+
+// 配對分區：只有同年度、同學制且科目屬於同一相容群組的教師之間才可能形成循環，
+// 因此各分區可以獨立計算，教師資料變動時也只需重算所在的分區
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PartitionKey {
+    pub year: i32,
+    pub school_level: String,
+    pub subject_group: String,
+}
+
+impl PartitionKey {
+    pub fn of(teacher: &Teacher, compatibility: &SubjectCompatibility) -> Self {
+        PartitionKey {
+            year: teacher.year,
+            school_level: teacher.school_level.trim().to_string(),
+            subject_group: compatibility.group_of(&teacher.subject),
+        }
+    }
+}
+
+// 將教師依配對分區分組
+pub fn group_by_partition(
+    teachers: &[Teacher],
+    compatibility: &SubjectCompatibility,
+) -> HashMap<PartitionKey, Vec<Teacher>> {
+    let mut groups: HashMap<PartitionKey, Vec<Teacher>> = HashMap::new();
+    for teacher in teachers {
+        groups.entry(PartitionKey::of(teacher, compatibility))
+            .or_default()
+            .push(teacher.clone());
+    }
+    groups
+}
+
+// 列出單一分區內的所有循環，尚未去除重複排列、選取或排序
+// 同一位教師只會屬於一個分區，所以各分區的結果可以直接合併後交給 finalize_matches
+pub fn partition_cycles(
+    teachers: &[Teacher],
+    compatibility: &SubjectCompatibility,
+    max_cycle_size: usize,
+) -> Vec<MatchResult> {
+    let preference_graph = build_preference_graph(teachers, compatibility);
+
+    find_all_cycles(teachers, &preference_graph, max_cycle_size)
+        .into_iter()
+        .map(|cycle| cycle_result(cycle.iter().map(|&idx| teachers[idx].clone()).collect()))
+        .collect()
+}
+
+// 整理後的配對結果
+#[derive(Debug, Clone)]
+pub struct FinalizedMatches {
+    pub matches: Vec<MatchResult>,
+    // MaxTeachers 選取時有任一重疊群組超過搜尋節點上限則為 false，
+    // 此時的組合不保證調動人數最多（至少與貪婪法相同）
+    pub exhaustive: bool,
+}
+
+// 將各分區的循環整理成最終結果：去除重複、依設定選取互不重疊的循環並排序
+pub fn finalize_matches(results: Vec<MatchResult>, config: &MatcherConfig) -> Vec<MatchResult> {
+    finalize_matches_with_report(results, config).matches
+}
+
+// 與 finalize_matches 相同，另外返回循環選取是否完整搜尋
+pub fn finalize_matches_with_report(results: Vec<MatchResult>, config: &MatcherConfig) -> FinalizedMatches {
+    // 同一個循環可能由不同來源重複提供（例如多次合併快取的分區結果）
+    let mut results = remove_duplicate_cycles(results);
+    let mut exhaustive = true;
+
+    if let Some(objective) = config.packing {
+        (results, exhaustive) = pack_cycles(results, objective);
+    }
+
+    sort_matches(&mut results, config.sort_by);
+
+    FinalizedMatches { matches: results, exhaustive }
+}
+
+// 單一分區（年度、學制、科目相容群組）的偏好圖，供除錯與匯出使用
+#[derive(Debug, Clone)]
+pub struct PreferenceGraph {
+    pub teachers: Vec<Teacher>,
+    // (調動者索引, 目標職缺持有者索引)
+    pub edges: Vec<(usize, usize)>,
+    // 在此分區中偵測到的循環（教師索引）
+    pub cycles: Vec<Vec<usize>>,
+}
+
+impl PreferenceGraph {
+    // 屬於任一循環的邊
+    pub fn cycle_edges(&self) -> HashSet<(usize, usize)> {
+        self.cycles.iter()
+            .flat_map(|cycle| {
+                (0..cycle.len()).map(move |i| (cycle[i], cycle[(i + 1) % cycle.len()]))
+            })
+            .collect()
+    }
+}
+
+// 取得指定年度、學制及科目所在分區的偏好圖
+pub fn partition_graph(
+    teachers: &[Teacher],
+    year: i32,
+    school_level: &str,
+    subject: &str,
+    compatibility: &SubjectCompatibility,
+    config: &MatcherConfig,
+) -> PreferenceGraph {
+    let group = compatibility.group_of(subject);
+    let partition: Vec<Teacher> = teachers.iter()
+        .filter(|t| t.year == year && t.school_level.trim() == school_level.trim())
+        .filter(|t| compatibility.group_of(&t.subject) == group)
+        .cloned()
+        .collect();
+
+    let graph = build_preference_graph(&partition, compatibility);
+    let edges = graph.iter()
+        .flat_map(|(&from, targets)| targets.iter().map(move |&to| (from, to)))
+        .sorted()
+        .collect();
+    let cycles = find_all_cycles(&partition, &graph, config.max_cycle_size);
+
+    PreferenceGraph { teachers: partition, edges, cycles }
+}
+
+// 構建教師偏好的有向圖
+// 返回的是一個映射：教師索引 -> 他期望調去的教師索引列表
+// 只有當調動者的科目可以填補對方職缺時才會建立邊
+fn build_preference_graph(
+    teachers: &[Teacher],
+    compatibility: &SubjectCompatibility,
+) -> HashMap<usize, Vec<usize>> {
+    let mut graph = HashMap::new();
+    
+    for (i, from_teacher) in teachers.iter().enumerate() {
+        let mut preferences = Vec::new();
+        
+        for (j, to_teacher) in teachers.iter().enumerate() {
+            if i != j
+                && wants_location(from_teacher, to_teacher)
+                && can_fill_post(from_teacher, to_teacher, compatibility)
+            {
+                preferences.push(j);
+            }
+        }
+        
+        graph.insert(i, preferences);
+    }
+    
+    graph
+}
+
+// 檢查先前找到的循環在教師資料變更後是否仍然成立
+// teachers 依循環順序排列，成立時返回 None，否則返回第一個不成立的原因
+pub fn cycle_break_reason(teachers: &[Teacher], compatibility: &SubjectCompatibility) -> Option<String> {
+    let first = teachers.first()?;
+    let label = |t: &Teacher| t.display_id.clone().unwrap_or_else(|| format!("#{}", t.id.unwrap_or(0)));
+
+    // 學制與 PartitionKey 一樣忽略前後空白，否則仍在同一分區的循環會被誤判為失效
+    for teacher in teachers {
+        if teacher.year != first.year || teacher.school_level.trim() != first.school_level.trim() {
+            return Some(format!("{} 的年度或學制已變更", label(teacher)));
+        }
+    }
+
+    for (i, teacher) in teachers.iter().enumerate() {
+        if teachers[..i].iter().any(|other| same_person(other, teacher)) {
+            return Some(format!("{} 與循環中另一筆登記屬於同一個帳號", label(teacher)));
+        }
+    }
+
+    for (i, from) in teachers.iter().enumerate() {
+        let to = &teachers[(i + 1) % teachers.len()];
+        if !wants_location(from, to) {
+            return Some(format!(
+                "{} 的志願已不包含{}{}",
+                label(from), to.current_county, to.current_district
+            ));
+        }
+        if !can_fill_post(from, to, compatibility) {
+            return Some(format!(
+                "{} 的科目（{}）已無法填補{}的職缺（{}）",
+                label(from), from.subject, label(to), to.subject
+            ));
+        }
+    }
+
+    None
+}
+
+// 同一個帳號（google_id）可以有多筆登記，這些登記屬於同一個人
+// 沒有 google_id 的登記各自視為不同的人
+fn same_person(a: &Teacher, b: &Teacher) -> bool {
+    match (a.google_id.as_deref(), b.google_id.as_deref()) {
+        (Some(a), Some(b)) => !a.is_empty() && a == b,
+        _ => false,
+    }
+}
+
+// 教師所屬的人，選取循環時同一個人的登記互斥
+fn person_key(teacher: &Teacher) -> String {
+    match teacher.google_id.as_deref() {
+        Some(google_id) if !google_id.is_empty() => format!("google:{}", google_id),
+        _ => format!("teacher:{}", teacher.id.unwrap_or(0)),
+    }
+}
+
+// 檢查調動者的科目是否可以填補對方目前的職缺
+fn can_fill_post(mover: &Teacher, post_holder: &Teacher, compatibility: &SubjectCompatibility) -> bool {
+    compatibility.accepts(&post_holder.current_county, &post_holder.subject, &mover.subject)
+}
+
+// 由循環中的教師建立配對結果，並計算每次調動的距離
+// teachers[i] 調往 teachers[(i + 1) % n] 目前的位置
+fn cycle_result(teachers: Vec<Teacher>) -> MatchResult {
+    let cycle_size = teachers.len();
+    let moves: Vec<CycleMove> = (0..cycle_size)
+        .map(|i| {
+            let from = &teachers[i];
+            let to = &teachers[(i + 1) % cycle_size];
+            CycleMove {
+                teacher_id: from.id,
+                from_county: from.current_county.clone(),
+                from_district: from.current_district.clone(),
+                to_county: to.current_county.clone(),
+                to_district: to.current_district.clone(),
+                distance_km: geo::district_distance_km(
+                    &from.current_county,
+                    &from.current_district,
+                    &to.current_county,
+                    &to.current_district,
+                ),
+            }
+        })
+        .collect();
+    let total_distance_km = moves.iter().map(|m| m.distance_km).sum();
+
+    MatchResult {
+        match_type: if cycle_size == 2 { 
+            "direct_swap".to_string() 
+        } else if cycle_size == 3 {
+            "triangle_swap".to_string()
+        } else {
+            format!("{}_swap", cycle_size)
+        },
+        teachers,
+        moves,
+        total_distance_km,
+    }
+}
+
+// 比較兩個可能缺少座標的距離，缺少座標的排在最後
+fn compare_distance(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn sort_matches(results: &mut [MatchResult], sort_by: MatchSortKey) {
+    match sort_by {
+        MatchSortKey::None => {}
+        MatchSortKey::Distance => {
+            results.sort_by(|a, b| compare_distance(a.total_distance_km, b.total_distance_km));
+        }
+        MatchSortKey::CycleSize => {
+            results.sort_by(|a, b| {
+                a.teachers.len().cmp(&b.teachers.len())
+                    .then_with(|| compare_distance(a.total_distance_km, b.total_distance_km))
+            });
+        }
+    }
+}
+
+// 選出互不重疊的循環（以帳號判斷重疊，同一個人的多筆登記最多只有一筆能被選入）
+// MaxTeachers 以分支定界求出調動人數最多的組合；MinDistance 依平均距離貪婪選取
+// 第二個值表示搜尋是否完整，只有 MaxTeachers 超過搜尋節點上限時為 false
+fn pack_cycles(results: Vec<MatchResult>, objective: PackingObjective) -> (Vec<MatchResult>, bool) {
+    let mut candidates = results;
+    let average_distance = |m: &MatchResult| m.total_distance_km.map(|d| d / m.teachers.len() as f64);
+
+    match objective {
+        PackingObjective::MaxTeachers => {
+            candidates.sort_by(|a, b| {
+                b.teachers.len().cmp(&a.teachers.len())
+                    .then_with(|| compare_distance(a.total_distance_km, b.total_distance_km))
+            });
+            pack_max_teachers(candidates)
+        }
+        PackingObjective::MinDistance => {
+            candidates.sort_by(|a, b| {
+                compare_distance(average_distance(a), average_distance(b))
+                    .then_with(|| b.teachers.len().cmp(&a.teachers.len()))
+            });
+            let people: Vec<Vec<String>> = candidates.iter()
+                .map(|c| c.teachers.iter().map(person_key).collect())
+                .collect();
+            let selected = greedy_packing(&people, &(0..candidates.len()).collect::<Vec<_>>());
+            (take_selected(candidates, &selected), true)
+        }
+    }
+}
+
+// 單一重疊群組的搜尋節點上限；超過時使用目前找到的最佳組合（至少與貪婪法相同）
+const PACKING_SEARCH_LIMIT: usize = 200_000;
+
+// 依序選取不與已選循環共用帳號的循環，order 為候選的優先順序
+fn greedy_packing(people: &[Vec<String>], order: &[usize]) -> Vec<usize> {
+    let mut used = HashSet::new();
+    let mut selected = Vec::new();
+    for &index in order {
+        if people[index].iter().any(|person| used.contains(person)) {
+            continue;
+        }
+        used.extend(people[index].iter().cloned());
+        selected.push(index);
+    }
+    selected
+}
+
+// 依 selected 的索引取出循環，保持 candidates 原本的順序
+fn take_selected(candidates: Vec<MatchResult>, selected: &[usize]) -> Vec<MatchResult> {
+    let selected: HashSet<usize> = selected.iter().copied().collect();
+    candidates.into_iter()
+        .enumerate()
+        .filter(|(index, _)| selected.contains(index))
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+// 調動人數最多的組合；人數相同時缺少距離的循環較少者優先，再比較總距離
+// 互相沒有共用帳號的循環群組彼此獨立，分別求解。candidates 已依循環大小由大到小排序
+// 第二個值為 false 表示有群組超過搜尋節點上限
+fn pack_max_teachers(candidates: Vec<MatchResult>) -> (Vec<MatchResult>, bool) {
+    let people: Vec<Vec<String>> = candidates.iter()
+        .map(|c| c.teachers.iter().map(person_key).collect())
+        .collect();
+
+    // 以共用帳號將循環分成互相重疊的群組
+    let mut parent: Vec<usize> = (0..candidates.len()).collect();
+    fn root(parent: &mut [usize], mut index: usize) -> usize {
+        while parent[index] != index {
+            parent[index] = parent[parent[index]];
+            index = parent[index];
+        }
+        index
+    }
+    let mut owner: HashMap<&str, usize> = HashMap::new();
+    for (index, cycle_people) in people.iter().enumerate() {
+        for person in cycle_people {
+            let other = *owner.entry(person.as_str()).or_insert(index);
+            let (a, b) = (root(&mut parent, index), root(&mut parent, other));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+    let mut components: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..candidates.len() {
+        components.entry(root(&mut parent, index)).or_default().push(index);
+    }
+
+    let mut exhaustive = true;
+    let selected: Vec<usize> = components.values()
+        .flat_map(|component| {
+            let cycles: Vec<PackingCycle> = component.iter()
+                .map(|&index| PackingCycle {
+                    index,
+                    size: candidates[index].teachers.len(),
+                    distance: candidates[index].total_distance_km,
+                })
+                .collect();
+            let mut search = PackingSearch::new(&people, cycles);
+            exhaustive &= search.run();
+            search.best_selection
+        })
+        .collect();
+
+    (take_selected(candidates, &selected), exhaustive)
+}
+
+struct PackingCycle {
+    index: usize,
+    size: usize,
+    distance: Option<f64>,
+}
+
+// 組合的評分：調動人數、缺少距離的循環數、已知距離總和
+#[derive(Clone, Copy, Default)]
+struct PackingScore {
+    teachers: usize,
+    missing_distances: usize,
+    distance: f64,
+}
+
+impl PackingScore {
+    fn add(self, cycle: &PackingCycle) -> Self {
+        PackingScore {
+            teachers: self.teachers + cycle.size,
+            missing_distances: self.missing_distances + usize::from(cycle.distance.is_none()),
+            distance: self.distance + cycle.distance.unwrap_or(0.0),
+        }
+    }
+
+    fn is_better_than(&self, other: &PackingScore) -> bool {
+        self.teachers.cmp(&other.teachers)
+            .then_with(|| other.missing_distances.cmp(&self.missing_distances))
+            .then_with(|| other.distance.total_cmp(&self.distance))
+            == Ordering::Greater
+    }
+}
+
+struct PackingSearch<'a> {
+    people: &'a [Vec<String>],
+    cycles: Vec<PackingCycle>,
+    // remaining[i]：第 i 個之後（含）所有循環的人數總和，作為上界
+    remaining: Vec<usize>,
+    used: HashSet<&'a str>,
+    chosen: Vec<usize>,
+    nodes: usize,
+    best_score: PackingScore,
+    best_selection: Vec<usize>,
+}
+
+impl<'a> PackingSearch<'a> {
+    fn new(people: &'a [Vec<String>], cycles: Vec<PackingCycle>) -> Self {
+        let mut remaining = vec![0; cycles.len() + 1];
+        for i in (0..cycles.len()).rev() {
+            remaining[i] = remaining[i + 1] + cycles[i].size;
+        }
+
+        // 以貪婪法的結果作為初始解
+        let order: Vec<usize> = cycles.iter().map(|c| c.index).collect();
+        let best_selection = greedy_packing(people, &order);
+        let best_score = cycles.iter()
+            .filter(|c| best_selection.contains(&c.index))
+            .fold(PackingScore::default(), PackingScore::add);
+
+        PackingSearch {
+            people,
+            cycles,
+            remaining,
+            used: HashSet::new(),
+            chosen: Vec::new(),
+            nodes: 0,
+            best_score,
+            best_selection,
+        }
+    }
+
+    // 返回搜尋是否完整
+    fn run(&mut self) -> bool {
+        self.search(0, PackingScore::default());
+        let exhaustive = self.nodes <= PACKING_SEARCH_LIMIT;
+        if !exhaustive {
+            tracing::warn!("循環選取超過搜尋上限（{} 個循環），使用目前的最佳組合", self.cycles.len());
+        }
+        exhaustive
+    }
+
+    fn search(&mut self, position: usize, score: PackingScore) {
+        self.nodes += 1;
+        if self.nodes > PACKING_SEARCH_LIMIT {
+            return;
+        }
+        if score.is_better_than(&self.best_score) {
+            self.best_score = score;
+            self.best_selection = self.chosen.clone();
+        }
+        if position == self.cycles.len() || score.teachers + self.remaining[position] < self.best_score.teachers {
+            return;
+        }
+
+        let people = &self.people[self.cycles[position].index];
+        if people.iter().all(|person| !self.used.contains(person.as_str())) {
+            self.used.extend(people.iter().map(String::as_str));
+            self.chosen.push(self.cycles[position].index);
+            let next = score.add(&self.cycles[position]);
+            self.search(position + 1, next);
+            self.chosen.pop();
+            for person in people {
+                self.used.remove(person.as_str());
+            }
+        }
+        self.search(position + 1, score);
+    }
+}
+
+// 檢查教師是否希望調往特定縣市和區域
+fn wants_location(from_teacher: &Teacher, to_teacher: &Teacher) -> bool {
+    // 檢查縣市是否相同，相同縣市不允許匹配
+    if from_teacher.current_county == to_teacher.current_county {
+        return false;
+    }
+    
+    // 檢查縣市與區域是否符合教師的調動志願
+    for (i, county) in from_teacher.target_counties.iter().enumerate() {
+        if county == &to_teacher.current_county && 
+           i < from_teacher.target_districts.len() && 
+           from_teacher.target_districts[i] == to_teacher.current_district {
+            return true;
+        }
+    }
+    
+    false
+}
+
+// 查找 2 到 max_cycle_size 的所有循環
+fn find_all_cycles(
+    teachers: &[Teacher],
+    graph: &HashMap<usize, Vec<usize>>,
+    max_cycle_size: usize,
+) -> Vec<Vec<usize>> {
+    let mut cycles = Vec::new();
+    for cycle_size in 2..=max_cycle_size {
+        find_cycles(teachers, graph, cycle_size, &mut cycles);
+    }
+    cycles
+}
+
+// 查找指定大小的循環，結果為教師索引的路徑
+// 每個循環只從索引最小的教師開始搜尋一次，因此同一個循環的不同旋轉不會重複出現；
+// 方向不同的循環（A→B→C 與 A→C→B）是不同的調動安排，兩者都會返回
+fn find_cycles(
+    teachers: &[Teacher],
+    graph: &HashMap<usize, Vec<usize>>,
+    cycle_size: usize,
+    results: &mut Vec<Vec<usize>>
+) {
+    if teachers.len() < cycle_size {
+        return;
+    }
+
+    let mut visited = vec![false; teachers.len()];
+    let mut path = Vec::with_capacity(cycle_size);
+
+    for start in 0..teachers.len() {
+        dfs_find_cycle(graph, teachers, start, start, cycle_size, &mut visited, &mut path, results);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dfs_find_cycle(
+    graph: &HashMap<usize, Vec<usize>>,
+    teachers: &[Teacher],
+    start: usize,
+    current: usize,
+    cycle_size: usize,
+    visited: &mut [bool],
+    path: &mut Vec<usize>,
+    results: &mut Vec<Vec<usize>>,
+) {
+    path.push(current);
+    visited[current] = true;
+
+    let neighbors = graph.get(&current).map(Vec::as_slice).unwrap_or_default();
+    if path.len() == cycle_size {
+        if neighbors.contains(&start) {
+            results.push(path.clone());
+        }
+    } else {
+        for &next in neighbors {
+            // 路徑上只允許索引大於起點的教師，同一個人的另一筆登記也不能再出現在同一個循環中
+            if next > start
+                && !visited[next]
+                && !path.iter().any(|&idx| same_person(&teachers[idx], &teachers[next]))
+            {
+                dfs_find_cycle(graph, teachers, start, next, cycle_size, visited, path, results);
+            }
+        }
+    }
+
+    path.pop();
+    visited[current] = false;
+}
+
+// 移除重複的循環
+fn remove_duplicate_cycles(results: Vec<MatchResult>) -> Vec<MatchResult> {
+    let mut unique_results = Vec::new();
+    let mut seen_cycles = HashSet::new();
+    
+    for result in results {
+        let cycle_key = create_cycle_key(&result.teachers);
+        
+        if !seen_cycles.contains(&cycle_key) {
+            unique_results.push(result);
+            seen_cycles.insert(cycle_key);
+        }
+    }
+    
+    unique_results
+}
+
+// 循環的唯一標識：依循環順序排列的教師編號，從編號最小的教師開始。
+// 只有旋轉視為同一個循環，方向不同的循環有不同的標識
+fn create_cycle_key(teachers: &[Teacher]) -> Vec<i32> {
+    let ids: Vec<i32> = teachers.iter().map(|t| t.id.unwrap_or(0)).collect();
+    let first = ids.iter().position_min().unwrap_or(0);
+    ids[first..].iter().chain(&ids[..first]).copied().collect()
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use chrono::{Utc, DateTime};
+
+// 學制
+pub const SCHOOL_LEVEL_ELEMENTARY: &str = "國小";
+pub const SCHOOL_LEVEL_JUNIOR_HIGH: &str = "國中";
+pub const SCHOOL_LEVEL_SENIOR_HIGH: &str = "高中";
+pub const SCHOOL_LEVELS: [&str; 3] = [
+    SCHOOL_LEVEL_ELEMENTARY,
+    SCHOOL_LEVEL_JUNIOR_HIGH,
+    SCHOOL_LEVEL_SENIOR_HIGH,
+];
+
+fn default_school_level() -> String {
+    SCHOOL_LEVEL_ELEMENTARY.to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Teacher {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub display_id: Option<String>,
+    pub email: String,
+    pub google_id: Option<String>,
+    pub year: i32,
+    #[serde(default = "default_school_level")]
+    pub school_level: String,  // 國小/國中/高中，舊資料預設為國小
+    pub subject: String,  // Added subject field
+    pub current_county: String,
+    pub current_district: String,
+    pub current_school: String,
+    #[serde(default)]
+    pub school_code: Option<String>,  // 學校名錄中的學校代碼
+    pub target_counties: Vec<String>,
+    pub target_districts: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    // 每次更新加一，用於偵測同時編輯（If-Match）
+    #[serde(default)]
+    pub version: i32,
+    // 登記所屬的帳號（users 表），由伺服器在登入或新增時連結
+    #[serde(default)]
+    pub user_id: Option<i32>,
+    // 管理者隱藏的登記不參與配對，也不出現在公開列表中
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MatchResult {
+    pub match_type: String,
+    pub teachers: Vec<Teacher>,
+    // 循環中每位教師的調動，順序與 teachers 相同
+    #[serde(default)]
+    pub moves: Vec<CycleMove>,
+    // 所有調動距離的總和，任一調動缺少座標時為 None
+    #[serde(default)]
+    pub total_distance_km: Option<f64>,
+}
+
+// 循環中的一次調動：教師從目前位置調往下一位教師的位置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CycleMove {
+    pub teacher_id: Option<i32>,
+    pub from_county: String,
+    pub from_district: String,
+    pub to_county: String,
+    pub to_district: String,
+    pub distance_km: Option<f64>,
+}
\ No newline at end of file
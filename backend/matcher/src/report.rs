@@ -0,0 +1,174 @@
+use crate::model::{MatchResult, Teacher};
+use crate::roster::LIST_SEPARATOR;
+use serde::Serialize;
+
+// 循環的穩定識別碼：以最小教師 ID 為起點旋轉，保留調動方向
+// 例如 7 -> 3 -> 12 與 3 -> 12 -> 7 都會得到 "3-12-7"
+pub fn cycle_key(result: &MatchResult) -> String {
+    let ids: Vec<i32> = result.teachers.iter().map(|t| t.id.unwrap_or(0)).collect();
+    let start = ids.iter()
+        .enumerate()
+        .min_by_key(|&(_, id)| *id)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    (0..ids.len())
+        .map(|i| ids[(start + i) % ids.len()].to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// 攤平後的循環參與者，一位教師一列，供 CSV/試算表輸出
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ParticipantRow {
+    // 在本次輸出中的循環編號（從 1 開始）
+    pub cycle_no: usize,
+    pub cycle_key: String,
+    pub match_type: String,
+    pub cycle_size: usize,
+    // 在循環中的位置（從 1 開始）
+    pub position: usize,
+    pub teacher_id: Option<i32>,
+    pub display_id: String,
+    pub name: String,
+    pub email: String,
+    pub year: i32,
+    pub school_level: String,
+    pub subject: String,
+    pub from_county: String,
+    pub from_district: String,
+    pub from_school: String,
+    pub to_county: String,
+    pub to_district: String,
+    pub to_school: String,
+    pub distance_km: Option<f64>,
+}
+
+pub fn participant_rows(matches: &[MatchResult]) -> Vec<ParticipantRow> {
+    matches.iter()
+        .enumerate()
+        .flat_map(|(cycle_index, result)| cycle_participant_rows(cycle_index, result))
+        .collect()
+}
+
+// 單一循環的參與者列，cycle_index 從 0 開始
+pub fn cycle_participant_rows(cycle_index: usize, result: &MatchResult) -> Vec<ParticipantRow> {
+    let key = cycle_key(result);
+    let size = result.teachers.len();
+
+    result.teachers.iter()
+        .enumerate()
+        .map(|(position, teacher)| {
+            // teachers[i] 調往 teachers[(i + 1) % n] 目前的位置
+            let target = &result.teachers[(position + 1) % size];
+            ParticipantRow {
+                cycle_no: cycle_index + 1,
+                cycle_key: key.clone(),
+                match_type: result.match_type.clone(),
+                cycle_size: size,
+                position: position + 1,
+                teacher_id: teacher.id,
+                display_id: teacher.display_id.clone().unwrap_or_default(),
+                name: teacher.name.clone().unwrap_or_default(),
+                email: teacher.email.clone(),
+                year: teacher.year,
+                school_level: teacher.school_level.clone(),
+                subject: teacher.subject.clone(),
+                from_county: teacher.current_county.clone(),
+                from_district: teacher.current_district.clone(),
+                from_school: teacher.current_school.clone(),
+                to_county: target.current_county.clone(),
+                to_district: target.current_district.clone(),
+                to_school: target.current_school.clone(),
+                distance_km: result.moves.get(position).and_then(|m| m.distance_km),
+            }
+        })
+        .collect()
+}
+
+// 教師登記的一列，欄位與 CSV 名冊相同，匯出的檔案可直接再匯入
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RegistrationRow {
+    pub id: Option<i32>,
+    pub display_id: String,
+    pub name: String,
+    pub email: String,
+    pub google_id: String,
+    pub year: i32,
+    pub school_level: String,
+    pub subject: String,
+    pub current_county: String,
+    pub current_district: String,
+    pub current_school: String,
+    pub school_code: String,
+    pub target_counties: String,
+    pub target_districts: String,
+}
+
+pub fn registration_rows(teachers: &[Teacher]) -> Vec<RegistrationRow> {
+    teachers.iter().map(registration_row).collect()
+}
+
+pub fn registration_row(teacher: &Teacher) -> RegistrationRow {
+    let separator = LIST_SEPARATOR.to_string();
+
+    RegistrationRow {
+        id: teacher.id,
+        display_id: teacher.display_id.clone().unwrap_or_default(),
+        name: teacher.name.clone().unwrap_or_default(),
+        email: teacher.email.clone(),
+        google_id: teacher.google_id.clone().unwrap_or_default(),
+        year: teacher.year,
+        school_level: teacher.school_level.clone(),
+        subject: teacher.subject.clone(),
+        current_county: teacher.current_county.clone(),
+        current_district: teacher.current_district.clone(),
+        current_school: teacher.current_school.clone(),
+        school_code: teacher.school_code.clone().unwrap_or_default(),
+        target_counties: teacher.target_counties.join(&separator),
+        target_districts: teacher.target_districts.join(&separator),
+    }
+}
+
+fn format_distance(distance_km: Option<f64>) -> String {
+    match distance_km {
+        Some(d) => format!("{:.1} 公里", d),
+        None => "距離未知".to_string(),
+    }
+}
+
+// 可列印的文字報表
+pub fn text_report(matches: &[MatchResult]) -> String {
+    let mut out = format!("配對結果：共 {} 組循環\n", matches.len());
+
+    for (cycle_index, result) in matches.iter().enumerate() {
+        out.push_str(&format!(
+            "\n== 循環 {}：{}（{} 人，總距離 {}）==\n",
+            cycle_index + 1,
+            result.match_type,
+            result.teachers.len(),
+            format_distance(result.total_distance_km),
+        ));
+
+        let size = result.teachers.len();
+        for (position, teacher) in result.teachers.iter().enumerate() {
+            let target = &result.teachers[(position + 1) % size];
+            out.push_str(&format!(
+                "  {}. {} {}（{}）{}{} {} → {}{} {}（{}）\n",
+                position + 1,
+                teacher.display_id.clone().unwrap_or_else(|| format!("#{}", teacher.id.unwrap_or(0))),
+                teacher.name.as_deref().unwrap_or(""),
+                teacher.subject,
+                teacher.current_county,
+                teacher.current_district,
+                teacher.current_school,
+                target.current_county,
+                target.current_district,
+                target.current_school,
+                format_distance(result.moves.get(position).and_then(|m| m.distance_km)),
+            ));
+        }
+    }
+
+    out
+}
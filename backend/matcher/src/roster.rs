@@ -0,0 +1,124 @@
+use crate::model::{Teacher, SCHOOL_LEVEL_ELEMENTARY};
+use serde::Deserialize;
+
+// 名冊中多個志願以分號分隔，縣市與區域依序對應
+pub(crate) const LIST_SEPARATOR: char = ';';
+
+// CSV 名冊的一列，欄位名稱與 Teacher 相同
+#[derive(Deserialize, Debug)]
+struct RosterRow {
+    id: Option<i32>,
+    name: Option<String>,
+    display_id: Option<String>,
+    #[serde(default)]
+    email: String,
+    google_id: Option<String>,
+    year: i32,
+    school_level: Option<String>,
+    subject: String,
+    current_county: String,
+    current_district: String,
+    #[serde(default)]
+    current_school: String,
+    school_code: Option<String>,
+    #[serde(default)]
+    target_counties: String,
+    #[serde(default)]
+    target_districts: String,
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl RosterRow {
+    fn into_teacher(self) -> Teacher {
+        Teacher {
+            id: self.id,
+            name: non_empty(self.name),
+            display_id: non_empty(self.display_id),
+            email: self.email.trim().to_string(),
+            google_id: non_empty(self.google_id),
+            year: self.year,
+            school_level: non_empty(self.school_level)
+                .unwrap_or_else(|| SCHOOL_LEVEL_ELEMENTARY.to_string()),
+            subject: self.subject.trim().to_string(),
+            current_county: self.current_county.trim().to_string(),
+            current_district: self.current_district.trim().to_string(),
+            current_school: self.current_school.trim().to_string(),
+            school_code: non_empty(self.school_code),
+            target_counties: split_list(&self.target_counties),
+            target_districts: split_list(&self.target_districts),
+            created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
+        }
+    }
+}
+
+// 配對引擎以教師 ID 判斷重複循環，名冊沒有 ID 時依列號補上
+pub fn assign_missing_ids(teachers: &mut [Teacher]) {
+    let mut next_id = teachers.iter().filter_map(|t| t.id).max().unwrap_or(0);
+    for teacher in teachers.iter_mut().filter(|t| t.id.is_none()) {
+        next_id += 1;
+        teacher.id = Some(next_id);
+    }
+}
+
+// CSV 名冊中的一列解析結果
+#[derive(Debug)]
+pub struct RosterRecord {
+    // 該筆資料在檔案中開始的列號，標題為第 1 列
+    pub line: usize,
+    pub teacher: Result<Teacher, String>,
+}
+
+// 逐列解析 CSV 名冊，單列格式錯誤不會中斷其他列；標題錯誤時返回 Err
+pub fn parse_csv(input: impl std::io::Read) -> Result<Vec<RosterRecord>, String> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(input);
+    let headers = reader.headers().map_err(|e| format!("名冊標題列格式錯誤: {}", e))?.clone();
+
+    // 列號取自 CSV 讀取器的位置：引號內含換行的欄位會跨越多列，不能以資料筆數推算
+    let mut records = Vec::new();
+    let mut last_line = 1;
+    for record in reader.records() {
+        let (line, teacher) = match record {
+            Ok(record) => (
+                record.position().map_or(last_line + 1, |position| position.line() as usize),
+                record.deserialize::<RosterRow>(Some(&headers))
+                    .map(RosterRow::into_teacher)
+                    .map_err(|e| format!("格式錯誤: {}", e)),
+            ),
+            Err(e) => (
+                e.position().map_or(last_line + 1, |position| position.line() as usize),
+                Err(format!("格式錯誤: {}", e)),
+            ),
+        };
+        last_line = line;
+        records.push(RosterRecord { line, teacher });
+    }
+    Ok(records)
+}
+
+// 解析 CSV 名冊，任一列格式錯誤即返回 Err
+pub fn read_csv(input: impl std::io::Read) -> Result<Vec<Teacher>, String> {
+    parse_csv(input)?
+        .into_iter()
+        .map(|record| record.teacher.map_err(|e| format!("名冊第 {} 列{}", record.line, e)))
+        .collect()
+}
+
+// JSON 名冊為 Teacher 陣列，與 GET /api/teachers 的輸出相同
+pub fn read_json(input: &str) -> Result<Vec<Teacher>, String> {
+    serde_json::from_str(input).map_err(|e| format!("JSON 名冊格式錯誤: {}", e))
+}
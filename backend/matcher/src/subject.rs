@@ -0,0 +1,134 @@
+use serde::Deserialize;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+// 未填寫科目時使用的分組標識
+pub const UNSPECIFIED_SUBJECT: &str = "未指定";
+
+// 科目相容表快取
+static SUBJECT_COMPATIBILITY: Lazy<SubjectCompatibility> = Lazy::new(|| {
+    let json_data = include_str!("../../data/taiwan_subject_compatibility.json");
+    SubjectCompatibility::from_json(json_data).unwrap_or_else(|e| {
+        tracing::error!("科目相容表解析失敗，僅允許同科目調動: {}", e);
+        SubjectCompatibility::default()
+    })
+});
+
+// 使用小寫並去除空白，增加匹配機會
+pub fn normalize_subject(subject: &str) -> String {
+    let normalized = subject.trim().to_lowercase();
+    if normalized.is_empty() {
+        UNSPECIFIED_SUBJECT.to_string()
+    } else {
+        normalized
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct CompatibilityFile {
+    #[serde(default)]
+    default: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    counties: HashMap<String, HashMap<String, Vec<String>>>,
+}
+
+// 科目相容表：記錄某科目的職缺除了同科目教師之外，還能由哪些科目的教師填補。
+// 縣市規則會整個取代該科目的預設規則。
+#[derive(Debug, Clone, Default)]
+pub struct SubjectCompatibility {
+    default: HashMap<String, Vec<String>>,
+    counties: HashMap<String, HashMap<String, Vec<String>>>,
+    // 科目 -> 所屬相容群組的代表科目
+    groups: HashMap<String, String>,
+}
+
+impl SubjectCompatibility {
+    // 從 JSON 載入相容表，格式為
+    // { "default": { 職缺科目: [可填補科目...] }, "counties": { 縣市: { 職缺科目: [...] } } }
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let file: CompatibilityFile = serde_json::from_str(json)?;
+
+        let normalize_rules = |rules: HashMap<String, Vec<String>>| {
+            rules.into_iter()
+                .map(|(post, movers)| {
+                    (normalize_subject(&post), movers.iter().map(|s| normalize_subject(s)).collect())
+                })
+                .collect::<HashMap<String, Vec<String>>>()
+        };
+
+        let default = normalize_rules(file.default);
+        let counties = file.counties.into_iter()
+            .map(|(county, rules)| (county, normalize_rules(rules)))
+            .collect::<HashMap<_, _>>();
+
+        let groups = build_groups(std::iter::once(&default).chain(counties.values()));
+
+        Ok(SubjectCompatibility { default, counties, groups })
+    }
+
+    // 隨程式打包的相容表
+    pub fn bundled() -> &'static SubjectCompatibility {
+        &SUBJECT_COMPATIBILITY
+    }
+
+    // 檢查 mover_subject 的教師能否填補位於 county 的 post_subject 職缺
+    pub fn accepts(&self, county: &str, post_subject: &str, mover_subject: &str) -> bool {
+        let post = normalize_subject(post_subject);
+        let mover = normalize_subject(mover_subject);
+        if post == mover {
+            return true;
+        }
+
+        let rules = self.counties.get(county)
+            .and_then(|rules| rules.get(&post))
+            .or_else(|| self.default.get(&post));
+
+        rules.is_some_and(|movers| movers.contains(&mover))
+    }
+
+    // 取得科目所屬的相容群組；只有同群組的教師之間才可能互相調動
+    pub fn group_of(&self, subject: &str) -> String {
+        let normalized = normalize_subject(subject);
+        self.groups.get(&normalized).cloned().unwrap_or(normalized)
+    }
+}
+
+// 將所有規則中出現的科目依相容關係合併成群組，以字典序最小的科目作為代表
+fn build_groups<'a>(
+    rule_sets: impl Iterator<Item = &'a HashMap<String, Vec<String>>>,
+) -> HashMap<String, String> {
+    let mut parent: HashMap<String, String> = HashMap::new();
+
+    fn find(parent: &mut HashMap<String, String>, subject: &str) -> String {
+        let next = parent.entry(subject.to_string())
+            .or_insert_with(|| subject.to_string())
+            .clone();
+        if next == subject {
+            return next;
+        }
+        let root = find(parent, &next);
+        parent.insert(subject.to_string(), root.clone());
+        root
+    }
+
+    for rules in rule_sets {
+        for (post, movers) in rules {
+            for mover in movers {
+                let a = find(&mut parent, post);
+                let b = find(&mut parent, mover);
+                if a != b {
+                    let (root, child) = if a < b { (a, b) } else { (b, a) };
+                    parent.insert(child, root);
+                }
+            }
+        }
+    }
+
+    let subjects: Vec<String> = parent.keys().cloned().collect();
+    subjects.into_iter()
+        .map(|subject| {
+            let root = find(&mut parent, &subject);
+            (subject, root)
+        })
+        .collect()
+}
@@ -0,0 +1,134 @@
+use crate::model::{Teacher, SCHOOL_LEVELS};
+use once_cell::sync::Lazy;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+// 直轄市，產生登記時依 big_city_weight 提高這些縣市的比重
+pub const BIG_CITIES: [&str; 6] = ["臺北市", "新北市", "桃園市", "臺中市", "臺南市", "高雄市"];
+
+// 各學制的登記比例，順序與 SCHOOL_LEVELS 相同
+const SCHOOL_LEVEL_WEIGHTS: [u32; 3] = [6, 3, 2];
+
+#[derive(Deserialize)]
+struct County {
+    name: String,
+    districts: Vec<District>,
+}
+
+#[derive(Deserialize)]
+struct District {
+    name: String,
+}
+
+static COUNTIES: Lazy<Vec<County>> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../../data/taiwan_districts.json")).expect("縣市區域資料格式錯誤")
+});
+
+// 各學制的科目，順序與 SCHOOL_LEVELS 相同
+static SUBJECTS: Lazy<[Vec<String>; 3]> = Lazy::new(|| {
+    let parse = |json: &str| serde_json::from_str::<Vec<String>>(json).expect("科目資料格式錯誤");
+    [
+        parse(include_str!("../../data/taiwan_elementary_subject.json")),
+        parse(include_str!("../../data/taiwan_junior_high_subject.json")),
+        parse(include_str!("../../data/taiwan_senior_high_subject.json")),
+    ]
+});
+
+// 合成名冊的參數；相同參數與種子產生相同的名冊
+#[derive(Debug, Clone)]
+pub struct RosterSpec {
+    pub teachers: usize,
+    pub year: i32,
+    // 每位教師的志願數量上限（至少一個），數值越大偏好圖越密
+    pub max_targets: usize,
+    // 直轄市相對於其他縣市的比重倍數，1.0 表示只依區域數量分配
+    pub big_city_weight: f64,
+    pub seed: u64,
+}
+
+impl Default for RosterSpec {
+    fn default() -> Self {
+        RosterSpec {
+            teachers: 1000,
+            year: 114,
+            max_targets: 3,
+            big_city_weight: 3.0,
+            seed: 0,
+        }
+    }
+}
+
+// 依實際的縣市、區域與各學制科目產生教師登記。
+// 目前位置與志願都依縣市比重抽樣（區域越多的縣市比重越高，直轄市再乘上 big_city_weight），
+// 科目依資料檔中的順序遞減（第 n 個科目的比重為 1/n），志願不包含目前所在的縣市
+pub fn generate_roster(spec: &RosterSpec) -> Vec<Teacher> {
+    let mut rng = StdRng::seed_from_u64(spec.seed);
+    let counties = &*COUNTIES;
+
+    let county_weights = counties.iter().map(|county| {
+        let weight = county.districts.len() as f64;
+        if BIG_CITIES.contains(&county.name.as_str()) {
+            weight * spec.big_city_weight.max(0.0)
+        } else {
+            weight
+        }
+    });
+    let county_index = WeightedIndex::new(county_weights).expect("縣市比重必須為正數");
+    let level_index = WeightedIndex::new(SCHOOL_LEVEL_WEIGHTS).unwrap();
+    let subject_indexes: Vec<WeightedIndex<f64>> = SUBJECTS.iter()
+        .map(|subjects| WeightedIndex::new((1..=subjects.len()).map(|rank| 1.0 / rank as f64)).unwrap())
+        .collect();
+
+    let pick_location = |rng: &mut StdRng| {
+        let county = &counties[county_index.sample(rng)];
+        let district = county.districts.choose(rng).expect("縣市沒有區域");
+        (county.name.as_str(), district.name.as_str())
+    };
+
+    (0..spec.teachers)
+        .map(|i| {
+            let id = i as i32 + 1;
+            let level = level_index.sample(&mut rng);
+            let subject = &SUBJECTS[level][subject_indexes[level].sample(&mut rng)];
+            let (county, district) = pick_location(&mut rng);
+
+            // 志願依相同比重抽樣，略過目前所在的縣市與重複的志願
+            let wanted = rng.gen_range(1..=spec.max_targets.max(1));
+            let mut targets: Vec<(&str, &str)> = Vec::with_capacity(wanted);
+            for _ in 0..wanted * 4 {
+                if targets.len() == wanted {
+                    break;
+                }
+                let target = pick_location(&mut rng);
+                if target.0 != county && !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+
+            Teacher {
+                id: Some(id),
+                name: Some(format!("Synthetic-{}", id)),
+                display_id: None,
+                email: format!("teacher{}@example.com", id),
+                google_id: Some(format!("teacher{}@example.com", id)),
+                year: spec.year,
+                school_level: SCHOOL_LEVELS[level].to_string(),
+                subject: subject.clone(),
+                current_county: county.to_string(),
+                current_district: district.to_string(),
+                current_school: format!("{}{}", district, SCHOOL_LEVELS[level]),
+                school_code: None,
+                target_counties: targets.iter().map(|(county, _)| county.to_string()).collect(),
+                target_districts: targets.iter().map(|(_, district)| district.to_string()).collect(),
+                created_at: None,
+                updated_at: None,
+                version: 1,
+                user_id: None,
+                hidden: false,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,297 @@
+// 以隨機產生的教師登記比對配對引擎與窮舉所有排列的參考實作。
+// 參考實作直接依規則檢查每個排列，不共用配對引擎的圖或搜尋程式碼
+use circlematch_matcher::matcher::{cycle_break_reason, finalize_matches};
+use circlematch_matcher::model::{MatchResult, Teacher};
+use circlematch_matcher::subject::SubjectCompatibility;
+use circlematch_matcher::{find_matches_with, find_matches_with_report, MatcherConfig, PackingObjective, PartitionKey};
+use itertools::Itertools;
+use proptest::prelude::*;
+use std::collections::{BTreeSet, HashSet};
+
+// 位置不多，隨機產生的志願才容易形成循環（包含方向相反的循環）
+const LOCATIONS: [(&str, &str); 5] = [
+    ("臺北市", "大安區"),
+    ("臺北市", "信義區"),
+    ("新北市", "板橋區"),
+    ("桃園市", "中壢區"),
+    ("臺中市", "西屯區"),
+];
+// 一般、特教、英文在相容表中屬於同一群組，體育自成一組
+const SUBJECTS: [&str; 4] = ["一般", "特教", "英文", "體育"];
+const SCHOOL_LEVELS: [&str; 2] = ["國小", "國中"];
+const ACCOUNTS: [&str; 3] = ["a", "b", "c"];
+
+fn teacher(id: i32, level: &str, subject: &str, location: usize, targets: &[usize], account: Option<&str>) -> Teacher {
+    let (county, district) = LOCATIONS[location];
+    Teacher {
+        id: Some(id),
+        name: None,
+        display_id: None,
+        email: format!("{}@example.com", id),
+        google_id: account.map(str::to_string),
+        year: 114,
+        school_level: level.to_string(),
+        subject: subject.to_string(),
+        current_county: county.to_string(),
+        current_district: district.to_string(),
+        current_school: format!("{}國小", district),
+        school_code: None,
+        target_counties: targets.iter().map(|&i| LOCATIONS[i].0.to_string()).collect(),
+        target_districts: targets.iter().map(|&i| LOCATIONS[i].1.to_string()).collect(),
+        created_at: None,
+        updated_at: None,
+        version: 1,
+        user_id: None,
+        hidden: false,
+    }
+}
+
+// 教師編號依序為 1..=n；少數登記共用帳號，用來檢查同一個人不會在循環中出現兩次
+fn roster() -> impl Strategy<Value = Vec<Teacher>> {
+    let entry = (
+        prop_oneof![4 => Just(0), 1 => Just(1)],
+        prop_oneof![4 => Just(0), 1 => 1..SUBJECTS.len()],
+        0..LOCATIONS.len(),
+        proptest::collection::vec(0..LOCATIONS.len(), 0..=4),
+        proptest::option::weighted(0.2, 0..ACCOUNTS.len()),
+    );
+    proptest::collection::vec(entry, 0..=8).prop_map(|entries| {
+        entries.into_iter()
+            .enumerate()
+            .map(|(i, (level, subject, location, targets, account))| {
+                teacher(
+                    i as i32 + 1,
+                    SCHOOL_LEVELS[level],
+                    SUBJECTS[subject],
+                    location,
+                    &targets,
+                    account.map(|a| ACCOUNTS[a]),
+                )
+            })
+            .collect()
+    })
+}
+
+// 調動規則：同一分區、不同縣市、目標位置在志願中，且科目可以填補對方的職缺
+fn can_move(from: &Teacher, to: &Teacher, compatibility: &SubjectCompatibility) -> bool {
+    PartitionKey::of(from, compatibility) == PartitionKey::of(to, compatibility)
+        && from.current_county != to.current_county
+        && from.target_counties.iter()
+            .zip(&from.target_districts)
+            .any(|(county, district)| *county == to.current_county && *district == to.current_district)
+        && compatibility.accepts(&to.current_county, &to.subject, &from.subject)
+}
+
+fn same_account(a: &Teacher, b: &Teacher) -> bool {
+    a.google_id.is_some() && a.google_id == b.google_id
+}
+
+// 循環的標識：從編號最小的教師開始，保留方向
+fn cycle_key(teachers: &[Teacher]) -> Vec<i32> {
+    let ids: Vec<i32> = teachers.iter().map(|t| t.id.unwrap()).collect();
+    let first = ids.iter().position_min().unwrap();
+    ids[first..].iter().chain(&ids[..first]).copied().collect()
+}
+
+// 窮舉所有長度 2..=max_cycle_size 的排列，第一位為最小編號的排列代表一個循環
+fn oracle(teachers: &[Teacher], max_cycle_size: usize, compatibility: &SubjectCompatibility) -> BTreeSet<Vec<i32>> {
+    let mut cycles = BTreeSet::new();
+    for size in 2..=max_cycle_size.min(teachers.len()) {
+        for order in (0..teachers.len()).permutations(size) {
+            if order.iter().skip(1).any(|&i| i < order[0]) {
+                continue;
+            }
+            let cycle: Vec<&Teacher> = order.iter().map(|&i| &teachers[i]).collect();
+            let moves_ok = (0..size).all(|i| can_move(cycle[i], cycle[(i + 1) % size], compatibility));
+            let distinct_people = cycle.iter().tuple_combinations().all(|(a, b)| !same_account(a, b));
+            if moves_ok && distinct_people {
+                cycles.insert(cycle.iter().map(|t| t.id.unwrap()).collect());
+            }
+        }
+    }
+    cycles
+}
+
+fn check_result(result: &MatchResult, max_cycle_size: usize, compatibility: &SubjectCompatibility) -> Result<(), TestCaseError> {
+    let teachers = &result.teachers;
+    let size = teachers.len();
+    prop_assert!((2..=max_cycle_size).contains(&size));
+    prop_assert_eq!(result.moves.len(), size);
+
+    for (i, from) in teachers.iter().enumerate() {
+        let to = &teachers[(i + 1) % size];
+        prop_assert!(can_move(from, to, compatibility), "{:?} -> {:?}", from.id, to.id);
+
+        let step = &result.moves[i];
+        prop_assert_eq!(step.teacher_id, from.id);
+        prop_assert_eq!((&step.from_county, &step.from_district), (&from.current_county, &from.current_district));
+        prop_assert_eq!((&step.to_county, &step.to_district), (&to.current_county, &to.current_district));
+    }
+    prop_assert!(teachers.iter().tuple_combinations().all(|(a, b)| !same_account(a, b)));
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn matches_agree_with_exhaustive_search(teachers in roster(), max_cycle_size in 2usize..=5) {
+        let compatibility = SubjectCompatibility::bundled();
+        let config = MatcherConfig { max_cycle_size, ..Default::default() };
+        let results = find_matches_with(teachers.clone(), compatibility, &config);
+
+        for result in &results {
+            check_result(result, max_cycle_size, compatibility)?;
+        }
+
+        let keys: Vec<Vec<i32>> = results.iter().map(|r| cycle_key(&r.teachers)).collect();
+        let found: BTreeSet<Vec<i32>> = keys.iter().cloned().collect();
+        prop_assert_eq!(found.len(), keys.len(), "重複的循環: {:?}", keys);
+        prop_assert_eq!(found, oracle(&teachers, max_cycle_size, compatibility));
+    }
+
+    #[test]
+    fn finalizing_merged_results_is_idempotent(teachers in roster()) {
+        let config = MatcherConfig::default();
+        let results = find_matches_with(teachers, SubjectCompatibility::bundled(), &config);
+        let expected: BTreeSet<Vec<i32>> = results.iter().map(|r| cycle_key(&r.teachers)).collect();
+
+        // 快取合併分區結果時會再次整理，重複提供或旋轉過的循環只保留一份
+        let rotated = results.iter().map(|r| {
+            let mut r = r.clone();
+            r.teachers.rotate_left(1);
+            r
+        });
+        let merged = results.iter().cloned().chain(rotated).collect();
+        let refinalized = finalize_matches(merged, &config);
+
+        prop_assert_eq!(refinalized.len(), expected.len());
+        let keys: BTreeSet<Vec<i32>> = refinalized.iter().map(|r| cycle_key(&r.teachers)).collect();
+        prop_assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn packing_selects_a_maximal_set_of_disjoint_cycles(
+        teachers in roster(),
+        objective in prop_oneof![Just(PackingObjective::MaxTeachers), Just(PackingObjective::MinDistance)],
+    ) {
+        let compatibility = SubjectCompatibility::bundled();
+        let all = find_matches_with(teachers.clone(), compatibility, &MatcherConfig::default());
+        let config = MatcherConfig { packing: Some(objective), ..Default::default() };
+        let packed = find_matches_with(teachers, compatibility, &config);
+
+        let person = |t: &Teacher| t.google_id.clone().unwrap_or_else(|| format!("#{}", t.id.unwrap()));
+        let mut used = HashSet::new();
+        for result in &packed {
+            for teacher in &result.teachers {
+                prop_assert!(used.insert(person(teacher)), "{} 出現在兩個循環中", person(teacher));
+            }
+        }
+
+        // 未選入的循環都與已選入的循環重疊
+        let packed_keys: HashSet<Vec<i32>> = packed.iter().map(|r| cycle_key(&r.teachers)).collect();
+        for result in all.iter().filter(|r| !packed_keys.contains(&cycle_key(&r.teachers))) {
+            prop_assert!(result.teachers.iter().any(|t| used.contains(&person(t))));
+        }
+
+        // MaxTeachers 的調動人數等於窮舉所有互不重疊組合的最大值
+        if objective == PackingObjective::MaxTeachers && all.len() <= 16 {
+            let people: Vec<HashSet<String>> = all.iter().map(|r| r.teachers.iter().map(person).collect()).collect();
+            let best = (0u32..1 << all.len())
+                .filter(|mask| {
+                    let chosen: Vec<usize> = (0..all.len()).filter(|i| mask & (1 << i) != 0).collect();
+                    chosen.iter().tuple_combinations().all(|(&a, &b)| people[a].is_disjoint(&people[b]))
+                })
+                .map(|mask| (0..all.len()).filter(|i| mask & (1 << i) != 0).map(|i| all[i].teachers.len()).sum::<usize>())
+                .max()
+                .unwrap_or(0);
+            prop_assert_eq!(used.len(), best);
+        }
+    }
+}
+
+#[test]
+fn max_teachers_prefers_two_swaps_over_one_larger_cycle() {
+    // 1→2→3→1 會擋住 1↔4 與 2↔3 兩組互換；互換可以讓四位教師調動
+    let teachers = vec![
+        teacher(1, "國小", "一般", 0, &[2, 4], None),
+        teacher(2, "國小", "一般", 2, &[3], None),
+        teacher(3, "國小", "一般", 3, &[0, 2], None),
+        teacher(4, "國小", "一般", 4, &[0], None),
+    ];
+    let compatibility = SubjectCompatibility::bundled();
+    let all = find_matches_with(teachers.clone(), compatibility, &MatcherConfig::default());
+    let keys: BTreeSet<Vec<i32>> = all.iter().map(|r| cycle_key(&r.teachers)).collect();
+    assert_eq!(keys, [vec![1, 2, 3], vec![1, 4], vec![2, 3]].into());
+
+    let config = MatcherConfig { packing: Some(PackingObjective::MaxTeachers), ..Default::default() };
+    let packed = find_matches_with_report(teachers, compatibility, &config);
+    assert!(packed.exhaustive);
+    let keys: BTreeSet<Vec<i32>> = packed.matches.iter().map(|r| cycle_key(&r.teachers)).collect();
+    assert_eq!(keys, [vec![1, 4], vec![2, 3]].into());
+}
+
+#[test]
+fn max_teachers_reports_when_the_search_limit_is_reached() {
+    // 兩地各 15 位教師互相想調往對方，共 225 組互換彼此重疊，無法在節點上限內窮舉
+    let teachers: Vec<Teacher> = (0..30)
+        .map(|id| if id % 2 == 0 {
+            teacher(id, "國小", "一般", 0, &[2], None)
+        } else {
+            teacher(id, "國小", "一般", 2, &[0], None)
+        })
+        .collect();
+    let config = MatcherConfig {
+        max_cycle_size: 2,
+        packing: Some(PackingObjective::MaxTeachers),
+        ..Default::default()
+    };
+    let packed = find_matches_with_report(teachers, SubjectCompatibility::bundled(), &config);
+    assert!(!packed.exhaustive);
+    // 仍返回互不重疊的組合，至少與貪婪法相同
+    assert_eq!(packed.matches.len(), 15);
+}
+
+#[test]
+fn keeps_both_directions_of_a_cycle() {
+    // 三位教師互相接受對方的位置：A→B→C 與 A→C→B 是不同的調動安排
+    let teachers = vec![
+        teacher(1, "國小", "一般", 0, &[2, 4], None),
+        teacher(2, "國小", "一般", 2, &[0, 4], None),
+        teacher(3, "國小", "一般", 4, &[0, 2], None),
+    ];
+    let config = MatcherConfig { max_cycle_size: 3, ..Default::default() };
+    let results = find_matches_with(teachers, SubjectCompatibility::bundled(), &config);
+
+    let keys: BTreeSet<Vec<i32>> = results.iter().map(|r| cycle_key(&r.teachers)).collect();
+    let expected: BTreeSet<Vec<i32>> = [vec![1, 2], vec![1, 3], vec![2, 3], vec![1, 2, 3], vec![1, 3, 2]].into();
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn same_location_different_teachers_are_distinct_cycles() {
+    // 兩位教師在同一區，各自與第三位教師互換
+    let teachers = vec![
+        teacher(1, "國小", "一般", 0, &[2], None),
+        teacher(2, "國小", "一般", 0, &[2], None),
+        teacher(3, "國小", "一般", 2, &[0], None),
+    ];
+    let results = find_matches_with(teachers, SubjectCompatibility::bundled(), &MatcherConfig::default());
+
+    let keys: BTreeSet<Vec<i32>> = results.iter().map(|r| cycle_key(&r.teachers)).collect();
+    assert_eq!(keys, [vec![1, 3], vec![2, 3]].into());
+}
+
+#[test]
+fn surrounding_whitespace_in_school_level_does_not_break_a_cycle() {
+    // PartitionKey 忽略學制前後的空白，檢查已保存的循環時也必須一致
+    let teachers = vec![
+        teacher(1, "國小", "一般", 0, &[2], None),
+        teacher(2, " 國小 ", "一般", 2, &[0], None),
+    ];
+    assert_eq!(cycle_break_reason(&teachers, SubjectCompatibility::bundled()), None);
+
+    let teachers = vec![
+        teacher(1, "國小", "一般", 0, &[2], None),
+        teacher(2, "國中", "一般", 2, &[0], None),
+    ];
+    assert!(cycle_break_reason(&teachers, SubjectCompatibility::bundled()).is_some());
+}
@@ -0,0 +1,37 @@
+use circlematch_matcher::model::MatchResult;
+use circlematch_matcher::report::text_report;
+use circlematch_matcher::roster::{parse_csv, read_csv};
+
+const HEADER: &str = "id,name,year,subject,current_county,current_district,current_school,target_counties,target_districts\n";
+
+#[test]
+fn line_numbers_follow_multiline_fields() {
+    // 第 2 筆的校名含換行，占第 3、4 列；第 3 筆從第 5 列開始且年度格式錯誤
+    let csv = format!(
+        "{}1,甲,114,一般,臺北市,大安區,大安國小,新北市,板橋區\n2,乙,114,一般,新北市,板橋區,\"板橋\n國小\",臺北市,大安區\n3,丙,abc,一般,臺北市,信義區,信義國小,新北市,中和區\n",
+        HEADER
+    );
+    let records = parse_csv(csv.as_bytes()).unwrap();
+
+    let lines: Vec<usize> = records.iter().map(|record| record.line).collect();
+    assert_eq!(lines, [2, 3, 5]);
+    assert!(records[1].teacher.is_ok());
+    assert!(records[2].teacher.is_err());
+
+    let error = read_csv(csv.as_bytes()).unwrap_err();
+    assert!(error.contains("第 5 列"), "{}", error);
+}
+
+#[test]
+fn text_report_labels_teachers_without_display_id_by_id() {
+    let csv = format!(
+        "{}7,甲,114,一般,臺北市,大安區,大安國小,新北市,板橋區\n8,乙,114,一般,新北市,板橋區,板橋國小,臺北市,大安區\n",
+        HEADER
+    );
+    let teachers = read_csv(csv.as_bytes()).unwrap();
+    let matches: Vec<MatchResult> = circlematch_matcher::find_matches(teachers, &Default::default());
+    let report = text_report(&matches);
+
+    assert!(report.contains("1. #7 甲"), "{}", report);
+    assert!(report.contains("2. #8 乙"), "{}", report);
+}
@@ -0,0 +1,41 @@
+use circlematch_matcher::geo::district_centroid;
+use circlematch_matcher::synthetic::{generate_roster, RosterSpec, BIG_CITIES};
+
+fn big_city_share(spec: &RosterSpec) -> f64 {
+    let roster = generate_roster(spec);
+    let big = roster.iter().filter(|t| BIG_CITIES.contains(&t.current_county.as_str())).count();
+    big as f64 / roster.len() as f64
+}
+
+#[test]
+fn same_seed_produces_the_same_roster() {
+    let spec = RosterSpec { teachers: 200, seed: 7, ..Default::default() };
+    let first = serde_json::to_string(&generate_roster(&spec)).unwrap();
+    let second = serde_json::to_string(&generate_roster(&spec)).unwrap();
+    assert_eq!(first, second);
+
+    let other = serde_json::to_string(&generate_roster(&RosterSpec { seed: 8, ..spec })).unwrap();
+    assert_ne!(first, other);
+}
+
+#[test]
+fn registrations_use_real_districts_and_other_counties_as_targets() {
+    let spec = RosterSpec { teachers: 500, max_targets: 4, ..Default::default() };
+    for teacher in generate_roster(&spec) {
+        assert!(district_centroid(&teacher.current_county, &teacher.current_district).is_some());
+        assert!(teacher.target_counties.len() <= 4);
+        assert_eq!(teacher.target_counties.len(), teacher.target_districts.len());
+        for (county, district) in teacher.target_counties.iter().zip(&teacher.target_districts) {
+            assert_ne!(county, &teacher.current_county);
+            assert!(district_centroid(county, district).is_some());
+        }
+    }
+}
+
+#[test]
+fn big_city_weight_skews_locations() {
+    let spec = RosterSpec { teachers: 2_000, ..Default::default() };
+    let even = big_city_share(&RosterSpec { big_city_weight: 1.0, ..spec.clone() });
+    let skewed = big_city_share(&RosterSpec { big_city_weight: 5.0, ..spec });
+    assert!(skewed > even + 0.1, "{} vs {}", skewed, even);
+}
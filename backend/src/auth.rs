@@ -0,0 +1,328 @@
+use crate::state::AppState;
+use crate::model::{Role, Teacher, User};
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::{header, request::Parts, StatusCode};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GoogleTokenInfo {
+    // Google 帳號不會變動的識別碼
+    pub sub: String,
+    pub email: String,
+    pub name: String,
+    pub picture: String,
+}
+
+pub async fn verify_google_token(token: &str, client_id: &str) -> Result<GoogleTokenInfo, String> {
+    let client = Client::new();
+    let google_api_url = format!(
+        "https://oauth2.googleapis.com/tokeninfo?id_token={}",
+        token
+    );
+
+    let response = client.get(&google_api_url).send().await.map_err(|err| {
+        format!("無法連接到 Google API: {}", err)
+    })?;
+
+    if !response.status().is_success() {
+        return Err("Google Token 驗證失敗".to_string());
+    }
+
+    let token_info: Value = response.json().await.map_err(|err| {
+        format!("解析 Google 回應失敗: {}", err)
+    })?;
+
+    if token_info["aud"].as_str() != Some(client_id) {
+        return Err("Token 的 audience 不匹配".to_string());
+    }
+
+    let sub = token_info["sub"].as_str()
+        .filter(|sub| !sub.is_empty())
+        .ok_or("Token 缺少帳號識別碼")?
+        .to_string();
+
+    Ok(GoogleTokenInfo {
+        sub,
+        email: token_info["email"].as_str().unwrap_or("未知使用者").to_string(),
+        name: token_info["name"].as_str().unwrap_or("未知使用者").to_string(),
+        picture: token_info["picture"].as_str().unwrap_or("").to_string(),
+    })
+}
+
+// 驗證登入 token 並取得帳號資料；正式環境使用 Google，測試可改用 StaticTokenVerifier
+#[async_trait]
+pub trait TokenVerifier: Send + Sync {
+    async fn verify(&self, token: &str) -> Result<GoogleTokenInfo, String>;
+}
+
+pub struct GoogleTokenVerifier {
+    client_id: String,
+}
+
+impl GoogleTokenVerifier {
+    pub fn new(client_id: impl Into<String>) -> Self {
+        GoogleTokenVerifier { client_id: client_id.into() }
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for GoogleTokenVerifier {
+    async fn verify(&self, token: &str) -> Result<GoogleTokenInfo, String> {
+        verify_google_token(token, &self.client_id).await
+    }
+}
+
+// 只接受事先登記的 token，不連線到 Google
+#[derive(Default)]
+pub struct StaticTokenVerifier {
+    tokens: HashMap<String, GoogleTokenInfo>,
+}
+
+impl StaticTokenVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>, info: GoogleTokenInfo) -> Self {
+        self.tokens.insert(token.into(), info);
+        self
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for StaticTokenVerifier {
+    async fn verify(&self, token: &str) -> Result<GoogleTokenInfo, String> {
+        self.tokens.get(token).cloned().ok_or_else(|| "無效的 token".to_string())
+    }
+}
+
+// 取得目前設定的驗證方式，未設定 GOOGLE_CLIENT_ID 時無法登入
+pub fn token_verifier(state: &AppState) -> Result<&dyn TokenVerifier, (StatusCode, String)> {
+    state.verifier.as_deref().ok_or_else(|| {
+        tracing::error!("GOOGLE_CLIENT_ID 未設定，無法驗證登入");
+        (StatusCode::SERVICE_UNAVAILABLE, "伺服器未設定登入驗證".to_string())
+    })
+}
+
+// 已登入的使用者，google_id 即 Google 帳號的 email，sub 對應 users.google_sub，
+// user_id 為 sub 對應的 users.id，尚未透過 /api/google-login 登入過時為 None
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub google_id: String,
+    pub sub: String,
+    pub user_id: Option<i32>,
+}
+
+impl AuthUser {
+    // 已連結帳號的登記只屬於該 users.id；email 可能由其他 Google 帳號沿用，
+    // 只有尚未連結帳號（user_id 為 NULL）的舊登記才以 email 比對，並在修改時連結到目前帳號
+    pub fn owns(&self, teacher: &Teacher) -> bool {
+        match teacher.user_id {
+            Some(owner) => self.user_id == Some(owner),
+            None => teacher.google_id.as_deref() == Some(self.google_id.as_str()),
+        }
+    }
+
+    pub fn ensure_owns(&self, teacher: &Teacher) -> Result<(), (StatusCode, String)> {
+        if self.owns(teacher) {
+            Ok(())
+        } else {
+            Err((StatusCode::FORBIDDEN, "只能存取自己帳號的登記".to_string()))
+        }
+    }
+
+    // 登記所屬的帳號，或可管理登記所在縣市的管理者；以管理者身分存取時返回 AdminUser
+    pub async fn ensure_owns_or_admin(&self, state: &AppState, teacher: &Teacher) -> Result<Option<AdminUser>, (StatusCode, String)> {
+        if self.owns(teacher) {
+            return Ok(None);
+        }
+
+        let user = state.users.get_by_sub(&self.sub)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("讀取帳號資料失敗: {}", e)))?;
+        match user.map(AdminUser::from_account) {
+            Some(Ok(admin)) => {
+                admin.ensure_county(&teacher.current_county)?;
+                Ok(Some(admin))
+            }
+            _ => Err((StatusCode::FORBIDDEN, "只能存取自己帳號的登記".to_string())),
+        }
+    }
+}
+
+// 從 Authorization: Bearer 標頭取得 Google ID token；不接受網址參數，以免 token 出現在存取紀錄中
+fn request_token(parts: &Parts) -> Option<String> {
+    parts.headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let token = request_token(parts)
+            .ok_or((StatusCode::UNAUTHORIZED, "請先登入".to_string()))?;
+
+        let info = token_verifier(state)?.verify(&token).await.map_err(|e| {
+            tracing::warn!("登入驗證失敗: {}", e);
+            (StatusCode::UNAUTHORIZED, "驗證失敗".to_string())
+        })?;
+
+        let user_id = state.users.get_by_sub(&info.sub)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("讀取帳號資料失敗: {}", e)))?
+            .map(|user| user.id);
+
+        Ok(AuthUser { google_id: info.email, sub: info.sub, user_id })
+    }
+}
+
+// SSE 票證的有效時間
+pub const EVENT_TICKET_TTL: Duration = Duration::from_secs(60);
+
+// SSE 用的短期票證：EventSource 無法設定標頭，先以 Bearer token 換取票證再放在網址中。
+// 票證只能使用一次且很快過期，也只有 /api/events 接受，網址被記錄下來也無法再使用
+#[derive(Clone, Default)]
+pub struct EventTickets {
+    tickets: Arc<Mutex<HashMap<String, (AuthUser, Instant)>>>,
+}
+
+impl EventTickets {
+    pub fn issue(&self, user: AuthUser) -> String {
+        let ticket = uuid::Uuid::new_v4().simple().to_string();
+        let now = Instant::now();
+        let mut tickets = self.tickets.lock().unwrap_or_else(|e| e.into_inner());
+        tickets.retain(|_, (_, issued_at)| now.duration_since(*issued_at) < EVENT_TICKET_TTL);
+        tickets.insert(ticket.clone(), (user, now));
+        ticket
+    }
+
+    // 取出票證對應的帳號，票證使用後即失效
+    pub fn redeem(&self, ticket: &str) -> Option<AuthUser> {
+        let (user, issued_at) = self.tickets.lock().unwrap_or_else(|e| e.into_inner()).remove(ticket)?;
+        (issued_at.elapsed() < EVENT_TICKET_TTL).then_some(user)
+    }
+}
+
+#[derive(Deserialize)]
+struct TicketQuery {
+    ticket: Option<String>,
+}
+
+// SSE 的使用者：有 Authorization 標頭時與 AuthUser 相同，否則以 ?ticket= 的票證驗證
+#[derive(Debug, Clone)]
+pub struct EventStreamUser(pub AuthUser);
+
+#[async_trait]
+impl FromRequestParts<AppState> for EventStreamUser {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if parts.headers.contains_key(header::AUTHORIZATION) {
+            return AuthUser::from_request_parts(parts, state).await.map(EventStreamUser);
+        }
+
+        let ticket = Query::<TicketQuery>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|query| query.0.ticket)
+            .ok_or((StatusCode::UNAUTHORIZED, "請先登入".to_string()))?;
+        state.event_tickets.redeem(&ticket)
+            .map(EventStreamUser)
+            .ok_or((StatusCode::UNAUTHORIZED, "票證無效或已過期".to_string()))
+    }
+}
+
+// 具管理權限的帳號，/api/admin 下的路由都以此驗證
+#[derive(Debug, Clone)]
+pub struct AdminUser {
+    pub user: User,
+}
+
+impl AdminUser {
+    // 帳號具管理權限時才成為 AdminUser
+    fn from_account(user: User) -> Result<Self, (StatusCode, String)> {
+        match user.role {
+            Role::User => Err((StatusCode::FORBIDDEN, "需要管理者權限".to_string())),
+            Role::CountyAdmin if user.admin_county.is_none() => {
+                Err((StatusCode::FORBIDDEN, "縣市管理者尚未設定管理的縣市".to_string()))
+            }
+            _ => Ok(AdminUser { user }),
+        }
+    }
+
+    // county_admin 只能存取所屬縣市，super_admin 不限（None）
+    pub fn county_scope(&self) -> Option<&str> {
+        match self.user.role {
+            Role::SuperAdmin => None,
+            _ => self.user.admin_county.as_deref(),
+        }
+    }
+
+    pub fn can_access_county(&self, county: &str) -> bool {
+        self.county_scope().is_none_or(|scope| scope == county)
+    }
+
+    // 套用縣市範圍：county_admin 未指定縣市時使用所屬縣市，指定其他縣市時拒絕
+    pub fn scoped_county<'a>(&'a self, requested: Option<&'a str>) -> Result<Option<&'a str>, (StatusCode, String)> {
+        match (self.county_scope(), requested) {
+            (None, requested) => Ok(requested),
+            (Some(scope), None) => Ok(Some(scope)),
+            (Some(scope), Some(requested)) if scope == requested => Ok(Some(scope)),
+            (Some(scope), Some(requested)) => Err((
+                StatusCode::FORBIDDEN,
+                format!("只能存取 {} 的資料，無法存取 {}", scope, requested),
+            )),
+        }
+    }
+
+    pub fn ensure_county(&self, county: &str) -> Result<(), (StatusCode, String)> {
+        if self.can_access_county(county) {
+            Ok(())
+        } else {
+            Err((StatusCode::FORBIDDEN, format!("沒有管理 {} 登記的權限", county)))
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = AuthUser::from_request_parts(parts, state).await?;
+        let user = state.users.get_by_sub(&auth.sub)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("讀取帳號資料失敗: {}", e)))?
+            .ok_or((StatusCode::FORBIDDEN, "需要管理者權限".to_string()))?;
+
+        AdminUser::from_account(user)
+    }
+}
+
+// 只有系統管理者可以使用的操作，例如輪次、匯入與帳號權限管理
+#[derive(Debug, Clone)]
+pub struct SuperAdmin(pub AdminUser);
+
+#[async_trait]
+impl FromRequestParts<AppState> for SuperAdmin {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let admin = AdminUser::from_request_parts(parts, state).await?;
+        if admin.user.role != Role::SuperAdmin {
+            return Err((StatusCode::FORBIDDEN, "需要系統管理者權限".to_string()));
+        }
+        Ok(SuperAdmin(admin))
+    }
+}
@@ -0,0 +1,394 @@
+use crate::mailer::SmtpSecurity;
+use axum::http::HeaderValue;
+use circlematch_matcher::matcher::{MatchSortKey, MatcherConfig, PackingObjective};
+use serde::Deserialize;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// 未指定 CONFIG_FILE 時，若工作目錄有此檔案就會讀取
+const DEFAULT_CONFIG_FILE: &str = "circlematch.toml";
+
+// 伺服器設定：先讀取 TOML 設定檔（可省略），再以環境變數覆寫，啟動時驗證一次後放入 axum state
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub matcher: MatcherLimits,
+    pub auth: AuthConfig,
+    pub data: DataConfig,
+    pub mail: MailConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addr: SocketAddr,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    // 必填，沒有預設值以免把密碼寫在程式中
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    // 允許的來源，例如 https://circlematch.tw；只有 "*" 時允許任何來源
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatcherLimits {
+    // 搜尋的最大循環人數
+    pub max_cycle_size: usize,
+    // 教師登記變動後，等待這段時間合併連續變動再重新配對
+    pub rematch_debounce_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    // 未設定時 Google 登入與需要登入的功能都無法使用
+    pub google_client_id: Option<String>,
+    // 這些帳號登入時自動成為系統管理者，用於建立第一個管理者
+    pub super_admin_emails: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataConfig {
+    // 教育部各級學校名錄（CSV 匯出檔或 JSON）；未設定時使用不含學校代碼的內建範例名錄
+    pub school_directory_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    // 未設定時不寄送 Email，通知仍保留在佇列中
+    pub smtp_host: Option<String>,
+    // 未設定時依加密方式使用 1025（none）、587（starttls）或 465（tls）
+    pub smtp_port: Option<u16>,
+    pub smtp_security: SmtpSecurity,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub from: String,
+    // 信件中的網站連結
+    pub app_url: Option<String>,
+    pub poll_seconds: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig { bind_addr: SocketAddr::from(([0, 0, 0, 0], 8000)) }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig { url: None, max_connections: 5, acquire_timeout_secs: 30 }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig { allowed_origins: vec!["http://localhost:3000".to_string()] }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            smtp_host: None,
+            smtp_port: None,
+            smtp_security: SmtpSecurity::None,
+            smtp_username: None,
+            smtp_password: None,
+            from: "CircleMatch <noreply@circlematch.local>".to_string(),
+            app_url: None,
+            poll_seconds: 15,
+        }
+    }
+}
+
+impl Default for MatcherLimits {
+    fn default() -> Self {
+        MatcherLimits {
+            max_cycle_size: MatcherConfig::default().max_cycle_size,
+            rematch_debounce_ms: 500,
+        }
+    }
+}
+
+// 設定錯誤，列出所有問題以便一次修正
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "設定錯誤：")?;
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // 讀取 CONFIG_FILE（或工作目錄的 circlematch.toml）與環境變數並驗證
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match std::env::var("CONFIG_FILE").ok().filter(|path| !path.is_empty()) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+
+        let mut config = match &file {
+            Some(path) => Config::from_file(path).map_err(|problem| ConfigError { problems: vec![problem] })?,
+            None => Config::default(),
+        };
+
+        config = config.with_env(|name| std::env::var(name).ok())?;
+
+        match &file {
+            Some(path) => tracing::info!("已載入設定檔 {}", path.display()),
+            None => tracing::info!("未使用設定檔，設定皆來自環境變數與預設值"),
+        }
+        Ok(config)
+    }
+
+    // 以 var 提供的環境變數覆寫設定後驗證，列出所有問題
+    pub fn with_env(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut problems = self.apply_env(var);
+        problems.extend(self.validate());
+        if problems.is_empty() {
+            Ok(self)
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("無法讀取設定檔 {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("設定檔 {} 格式錯誤: {}", path.display(), e))
+    }
+
+    // 以環境變數覆寫設定，空字串視為未設定；返回無法解析的變數
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let var = |name: &str| var(name).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        let mut problems = Vec::new();
+
+        fn parse<T: std::str::FromStr>(name: &str, value: &str, problems: &mut Vec<String>) -> Option<T>
+        where
+            T::Err: fmt::Display,
+        {
+            value.parse()
+                .map_err(|e| problems.push(format!("{} 無法解析（{}）: {}", name, value, e)))
+                .ok()
+        }
+
+        fn list(value: &str) -> Vec<String> {
+            value.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect()
+        }
+
+        if let Some(value) = var("BIND_ADDR") {
+            if let Some(addr) = parse("BIND_ADDR", &value, &mut problems) {
+                self.server.bind_addr = addr;
+            }
+        }
+        if let Some(value) = var("DATABASE_URL") {
+            self.database.url = Some(value);
+        }
+        if let Some(value) = var("DATABASE_MAX_CONNECTIONS") {
+            if let Some(max) = parse("DATABASE_MAX_CONNECTIONS", &value, &mut problems) {
+                self.database.max_connections = max;
+            }
+        }
+        if let Some(value) = var("DATABASE_ACQUIRE_TIMEOUT_SECS") {
+            if let Some(secs) = parse("DATABASE_ACQUIRE_TIMEOUT_SECS", &value, &mut problems) {
+                self.database.acquire_timeout_secs = secs;
+            }
+        }
+        if let Some(value) = var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = list(&value);
+        }
+        if let Some(value) = var("MATCHER_MAX_CYCLE_SIZE") {
+            if let Some(size) = parse("MATCHER_MAX_CYCLE_SIZE", &value, &mut problems) {
+                self.matcher.max_cycle_size = size;
+            }
+        }
+        if let Some(value) = var("MATCHER_REMATCH_DEBOUNCE_MS") {
+            if let Some(ms) = parse("MATCHER_REMATCH_DEBOUNCE_MS", &value, &mut problems) {
+                self.matcher.rematch_debounce_ms = ms;
+            }
+        }
+        if let Some(value) = var("GOOGLE_CLIENT_ID") {
+            self.auth.google_client_id = Some(value);
+        }
+        if let Some(value) = var("SUPER_ADMIN_EMAILS") {
+            self.auth.super_admin_emails = list(&value);
+        }
+        if let Some(value) = var("SCHOOL_DIRECTORY_FILE") {
+            self.data.school_directory_file = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("SMTP_HOST") {
+            self.mail.smtp_host = Some(value);
+        }
+        if let Some(value) = var("SMTP_PORT") {
+            if let Some(port) = parse("SMTP_PORT", &value, &mut problems) {
+                self.mail.smtp_port = Some(port);
+            }
+        }
+        if let Some(value) = var("SMTP_SECURITY") {
+            if let Some(security) = parse("SMTP_SECURITY", &value, &mut problems) {
+                self.mail.smtp_security = security;
+            }
+        }
+        if let Some(value) = var("SMTP_USERNAME") {
+            self.mail.smtp_username = Some(value);
+        }
+        if let Some(value) = var("SMTP_PASSWORD") {
+            self.mail.smtp_password = Some(value);
+        }
+        if let Some(value) = var("SMTP_FROM") {
+            self.mail.from = value;
+        }
+        if let Some(value) = var("APP_URL") {
+            self.mail.app_url = Some(value);
+        }
+        if let Some(value) = var("EMAIL_POLL_SECONDS") {
+            if let Some(secs) = parse("EMAIL_POLL_SECONDS", &value, &mut problems) {
+                self.mail.poll_seconds = secs;
+            }
+        }
+
+        problems
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        match self.database.url.as_deref() {
+            None => problems.push("未設定 DATABASE_URL（或設定檔的 database.url）".to_string()),
+            Some(url) if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) => {
+                problems.push("DATABASE_URL 必須以 postgres:// 或 postgresql:// 開頭".to_string());
+            }
+            Some(_) => {}
+        }
+        if self.database.max_connections == 0 {
+            problems.push("資料庫連線數 database.max_connections 至少為 1".to_string());
+        }
+        if self.database.acquire_timeout_secs == 0 {
+            problems.push("database.acquire_timeout_secs 至少為 1 秒".to_string());
+        }
+
+        let origins = &self.cors.allowed_origins;
+        if origins.is_empty() {
+            problems.push("CORS 允許來源不可為空，允許任何來源請設為 *".to_string());
+        } else if origins.iter().any(|origin| origin == "*") {
+            if origins.len() > 1 {
+                problems.push("CORS 允許來源為 * 時不可再列出其他來源".to_string());
+            }
+        } else {
+            for origin in origins {
+                let valid_scheme = origin.starts_with("http://") || origin.starts_with("https://");
+                if !valid_scheme || origin.ends_with('/') || HeaderValue::from_str(origin).is_err() {
+                    problems.push(format!("CORS 來源 {} 格式錯誤，應為 https://example.com 形式", origin));
+                }
+            }
+        }
+
+        if !(2..=20).contains(&self.matcher.max_cycle_size) {
+            problems.push(format!("matcher.max_cycle_size 必須介於 2 到 20，目前為 {}", self.matcher.max_cycle_size));
+        }
+
+        if self.auth.google_client_id.as_deref().is_some_and(|id| id.trim().is_empty()) {
+            problems.push("auth.google_client_id 不可為空字串".to_string());
+        }
+        for email in &self.auth.super_admin_emails {
+            if !email.contains('@') {
+                problems.push(format!("SUPER_ADMIN_EMAILS 中的 {} 不是有效的 email", email));
+            }
+        }
+
+        if self.mail.smtp_host.is_some() {
+            if self.mail.from.parse::<lettre::message::Mailbox>().is_err() {
+                problems.push(format!("SMTP_FROM 格式錯誤: {}", self.mail.from));
+            }
+            let credentials = self.mail.smtp_username.is_some() || self.mail.smtp_password.is_some();
+            if credentials && self.mail.smtp_security == SmtpSecurity::None {
+                problems.push("設定了 SMTP 帳號密碼時 SMTP_SECURITY 必須為 starttls 或 tls，避免以明文傳送密碼".to_string());
+            }
+        }
+        if self.mail.smtp_port == Some(0) {
+            problems.push("SMTP_PORT 不可為 0".to_string());
+        }
+        if self.mail.poll_seconds == 0 {
+            problems.push("EMAIL_POLL_SECONDS 至少為 1 秒".to_string());
+        }
+
+        if let Some(path) = &self.data.school_directory_file {
+            if !path.is_file() {
+                problems.push(format!("找不到學校名錄 SCHOOL_DIRECTORY_FILE: {}", path.display()));
+            }
+        }
+
+        problems
+    }
+
+    pub fn database_url(&self) -> &str {
+        // load() 已驗證 url 存在
+        self.database.url.as_deref().unwrap_or_default()
+    }
+
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.database.acquire_timeout_secs)
+    }
+
+    // 依設定的搜尋上限建立單次查詢的配對參數
+    pub fn matcher_config(&self, sort_by: Option<MatchSortKey>, packing: Option<PackingObjective>) -> MatcherConfig {
+        MatcherConfig {
+            max_cycle_size: self.matcher.max_cycle_size,
+            sort_by: sort_by.unwrap_or_default(),
+            packing,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn allows_any(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+
+    pub fn origins(&self) -> Vec<HeaderValue> {
+        self.allowed_origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok()).collect()
+    }
+}
+
+impl MatcherLimits {
+    pub fn rematch_debounce(&self) -> Duration {
+        Duration::from_millis(self.rematch_debounce_ms)
+    }
+}
+
+impl AuthConfig {
+    pub fn is_bootstrap_super_admin(&self, email: &str) -> bool {
+        self.super_admin_emails.iter().any(|admin| admin.eq_ignore_ascii_case(email))
+    }
+}
@@ -1,9 +1,11 @@
-use sqlx::{Pool, Postgres, Row};
-use crate::model::Teacher;
+use sqlx::{Pool, Postgres, Row, postgres::PgRow, PgExecutor};
+use crate::model::{Announcement, AnnouncementInput, AnnouncementSeverity, Teacher, TeacherHistoryEntry, User, NewUser, Role, RoleUpdate, MatchResult, MatchCycle, Notification, NewNotification, NotificationPreferences, NOTIFICATION_MATCH_ACCEPTED, CYCLE_STATUS_ACTIVE, CYCLE_STATUS_ACCEPTED, CYCLE_STATUS_BROKEN, School, Round, RoundState, NewRound, RoundUpdate, SCHOOL_LEVEL_ELEMENTARY, SCHOOL_LEVEL_JUNIOR_HIGH, SCHOOL_LEVEL_SENIOR_HIGH};
 use chrono::Utc;
 use uuid::Uuid;
+use circlematch_matcher::report;
 use serde_json::{Value, json};
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+use std::path::Path;
 use std::sync::Arc;
 
 // 台灣縣市區域資料快取
@@ -32,16 +34,259 @@ static ELEMENTARY_SUBJECTS: Lazy<Arc<Vec<String>>> = Lazy::new(|| {
     ]))
 });
 
+static JUNIOR_HIGH_SUBJECTS: Lazy<Arc<Vec<String>>> = Lazy::new(|| {
+    let json_data = include_str!("../data/taiwan_junior_high_subject.json");
+    Arc::new(serde_json::from_str(json_data).unwrap_or_else(|_| vec![
+        "國文".to_string(),
+        "英語".to_string(),
+        "數學".to_string()
+    ]))
+});
+
+static SENIOR_HIGH_SUBJECTS: Lazy<Arc<Vec<String>>> = Lazy::new(|| {
+    let json_data = include_str!("../data/taiwan_senior_high_subject.json");
+    Arc::new(serde_json::from_str(json_data).unwrap_or_else(|_| vec![
+        "國文".to_string(),
+        "英文".to_string(),
+        "數學".to_string()
+    ]))
+});
+
+// 學校名錄：啟動時由 load_school_directory 載入設定的名錄檔；
+// 未載入時使用內建的範例名錄（不含學校代碼，見檔案中的 coverage 與 note），只供搜尋與自動完成
+static SCHOOL_DIRECTORY: OnceCell<Vec<School>> = OnceCell::new();
+
+fn school_directory() -> &'static [School] {
+    SCHOOL_DIRECTORY.get_or_init(|| {
+        tracing::warn!("使用內建的範例學校名錄，不核對學校代碼");
+        parse_school_directory(include_str!("../data/sample_schools.json"), false).unwrap_or_else(|e| {
+            tracing::error!("內建學校名錄解析失敗: {}", e);
+            Vec::new()
+        })
+    })
+}
+
+// 載入設定的學校名錄檔（副檔名 .csv 視為教育部名錄的 CSV 匯出檔），返回學校數量。
+// 必須在第一次查詢名錄之前呼叫；檔案無法讀取、解析失敗或沒有任何學校時返回錯誤
+pub fn load_school_directory(path: &Path) -> Result<usize, String> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|e| format!("無法讀取學校名錄 {}: {}", path.display(), e))?;
+    let csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let schools = parse_school_directory(&data, csv)
+        .map_err(|e| format!("學校名錄 {} 解析失敗: {}", path.display(), e))?;
+    if schools.is_empty() {
+        return Err(format!("學校名錄 {} 沒有任何可用的學校", path.display()));
+    }
+
+    let count = schools.len();
+    SCHOOL_DIRECTORY.set(schools)
+        .map_err(|_| "學校名錄已經載入，無法再次載入".to_string())?;
+    Ok(count)
+}
+
+// 內建名錄的格式：{ "coverage": ..., "note": ..., "schools": [...] }，也接受只有學校陣列的 JSON
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SchoolDirectoryFile {
+    Annotated { schools: Vec<School> },
+    Plain(Vec<School>),
+}
+
+// 解析學校名錄；csv 為 true 時視為教育部名錄的 CSV 匯出檔
+pub fn parse_school_directory(data: &str, csv: bool) -> Result<Vec<School>, String> {
+    if csv {
+        return parse_moe_school_csv(data);
+    }
+    match serde_json::from_str(data).map_err(|e| e.to_string())? {
+        SchoolDirectoryFile::Annotated { schools } | SchoolDirectoryFile::Plain(schools) => Ok(schools),
+    }
+}
+
+// 教育部各級學校名錄 CSV：使用「代碼」「學校名稱」「縣市名稱」「地址」欄位。
+// 縣市名稱與地址可能帶有 [63]、[100] 之類的代碼前綴；區域由地址比對縣市區域資料取得，
+// 學制由校名判斷，國民中小學會同時列為國小與國中。
+// 無法判斷區域或學制（例如大專校院）的列會略過並逐列記錄
+fn parse_moe_school_csv(data: &str) -> Result<Vec<School>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(data.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let column = |name: &str| {
+        headers.iter()
+            .position(|h| h.trim() == name)
+            .ok_or_else(|| format!("學校名錄缺少「{}」欄位", name))
+    };
+    let (code, name, county, address) = (column("代碼")?, column("學校名稱")?, column("縣市名稱")?, column("地址")?);
+
+    let strip_prefix = |value: &str| -> String {
+        let value = value.trim();
+        match value.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((_, rest)) => rest.trim().to_string(),
+            None => value.to_string(),
+        }
+    };
+
+    let mut schools = Vec::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |index: usize| record.get(index).unwrap_or_default();
+
+        let school_code = field(code).trim().to_string();
+        let school_name = field(name).trim().to_string();
+        let county_name = strip_prefix(field(county));
+        let address = strip_prefix(field(address));
+        let district = address.strip_prefix(county_name.as_str()).and_then(|rest| district_prefix(&county_name, rest));
+        let levels = school_levels_from_name(&school_name);
+
+        let Some(district) = district.filter(|_| !levels.is_empty()) else {
+            let reason = if levels.is_empty() { "學制" } else { "區域" };
+            tracing::info!("略過學校名錄 {} {}：無法判斷{}", school_code, school_name, reason);
+            skipped += 1;
+            continue;
+        };
+        for level in levels {
+            schools.push(School {
+                code: Some(school_code.clone()).filter(|code| !code.is_empty()),
+                name: school_name.clone(),
+                county: county_name.clone(),
+                district: district.clone(),
+                level: level.to_string(),
+            });
+        }
+    }
+
+    if skipped > 0 {
+        tracing::info!("學校名錄中有 {} 筆無法判斷區域或學制，已略過", skipped);
+    }
+    Ok(schools)
+}
+
+// 取出地址開頭符合縣市區域資料的區域名稱
+fn district_prefix(county: &str, address: &str) -> Option<String> {
+    TAIWAN_DISTRICTS.as_array()?
+        .iter()
+        .filter(|c| c["name"].as_str() == Some(county))
+        .filter_map(|c| c["districts"].as_array())
+        .flatten()
+        .filter_map(|d| d["name"].as_str())
+        .find(|district| address.starts_with(district))
+        .map(str::to_string)
+}
+
+// 由校名判斷學制；國民中小學同時有國小與國中，無法判斷時返回空陣列
+fn school_levels_from_name(name: &str) -> &'static [&'static str] {
+    if name.contains("國民中小學") || name.ends_with("國中小") {
+        &[SCHOOL_LEVEL_ELEMENTARY, SCHOOL_LEVEL_JUNIOR_HIGH]
+    } else if name.contains("國民小學") || name.ends_with("國小") {
+        &[SCHOOL_LEVEL_ELEMENTARY]
+    } else if name.contains("國民中學") || name.ends_with("國中") {
+        &[SCHOOL_LEVEL_JUNIOR_HIGH]
+    } else if name.contains("高級中學") || name.contains("高級中等學校") || name.contains("高中") || name.contains("完全中學") {
+        &[SCHOOL_LEVEL_SENIOR_HIGH]
+    } else {
+        &[]
+    }
+}
+
 // 取得縣市區域資料
 pub fn get_taiwan_districts() -> Arc<Value> {
     TAIWAN_DISTRICTS.clone()
 }
 
+// 檢查縣市區域資料中是否有該縣市與區域
+pub fn district_exists(county: &str, district: &str) -> bool {
+    TAIWAN_DISTRICTS.as_array()
+        .into_iter()
+        .flatten()
+        .filter(|c| c["name"].as_str() == Some(county.trim()))
+        .filter_map(|c| c["districts"].as_array())
+        .flatten()
+        .any(|d| d["name"].as_str() == Some(district.trim()))
+}
+
 // 取得國小教師科目資料
 pub fn get_elementary_subjects() -> Arc<Vec<String>> {
     ELEMENTARY_SUBJECTS.clone()
 }
 
+// 依學制取得教師科目資料，未知學制返回 None
+pub fn get_subjects_by_level(level: &str) -> Option<Arc<Vec<String>>> {
+    match level {
+        SCHOOL_LEVEL_ELEMENTARY => Some(get_elementary_subjects()),
+        SCHOOL_LEVEL_JUNIOR_HIGH => Some(JUNIOR_HIGH_SUBJECTS.clone()),
+        SCHOOL_LEVEL_SENIOR_HIGH => Some(SENIOR_HIGH_SUBJECTS.clone()),
+        _ => None,
+    }
+}
+
+// 依學校代碼查詢學校；國民中小學的國小部與國中部共用同一個代碼
+pub fn get_schools_by_code(code: &str) -> Vec<School> {
+    let code = code.trim();
+    school_directory().iter()
+        .filter(|school| school.code.as_deref() == Some(code))
+        .cloned()
+        .collect()
+}
+
+// 名錄是否帶有學校代碼；內建的範例名錄沒有代碼，此時不核對登記填寫的代碼
+pub fn school_codes_available() -> bool {
+    school_directory().iter().any(|school| school.code.is_some())
+}
+
+// 檢查隨程式載入的參考資料，返回沒有資料的項目，供 /readyz 使用
+pub fn missing_reference_data() -> Vec<&'static str> {
+    let datasets = [
+        ("taiwan_districts", TAIWAN_DISTRICTS.as_array().is_some_and(|counties| !counties.is_empty())),
+        ("elementary_subjects", !ELEMENTARY_SUBJECTS.is_empty()),
+        ("junior_high_subjects", !JUNIOR_HIGH_SUBJECTS.is_empty()),
+        ("senior_high_subjects", !SENIOR_HIGH_SUBJECTS.is_empty()),
+        ("school_directory", !school_directory().is_empty()),
+    ];
+
+    datasets.into_iter()
+        .filter(|(_, loaded)| !loaded)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+// 統一學校名稱的常見寫法，讓「大安國小」可以找到「臺北市大安區大安國民小學」
+pub fn normalize_school_name(name: &str) -> String {
+    name.trim()
+        .replace('臺', "台")
+        .replace("國民小學", "國小")
+        .replace("國民中學", "國中")
+        .replace("高級中等學校", "高中")
+        .replace("高級中學", "高中")
+        .replace("市立", "")
+        .replace("縣立", "")
+        .replace(' ', "")
+}
+
+// 學校名錄搜尋，供前端自動完成使用
+pub fn search_schools(
+    keyword: &str,
+    county: Option<&str>,
+    district: Option<&str>,
+    level: Option<&str>,
+    limit: usize,
+) -> Vec<School> {
+    let keyword = normalize_school_name(keyword);
+
+    school_directory().iter()
+        .filter(|school| county.is_none_or(|county| school.county == county))
+        .filter(|school| district.is_none_or(|district| school.district == district))
+        .filter(|school| level.is_none_or(|level| school.level == level))
+        .filter(|school| {
+            keyword.is_empty()
+                || school.code.as_deref().is_some_and(|code| code.starts_with(&keyword))
+                || normalize_school_name(&school.name).contains(&keyword)
+        })
+        .take(limit)
+        .cloned()
+        .collect()
+}
+
 // 用於生成顯示ID的函數
 pub fn generate_display_id(county: &str, district: &str) -> String {
     // 取得當前時間戳作為唯一性保證
@@ -53,57 +298,149 @@ pub fn generate_display_id(county: &str, district: &str) -> String {
     format!("{}{}#{:03}", county, district, hash)
 }
 
+// teachers 表查詢時共用的欄位列表
+const TEACHER_COLUMNS: &str = r#"
+    id,
+    name,
+    display_id,
+    email,
+    google_id,
+    year,
+    school_level,
+    subject,
+    current_county,
+    current_district,
+    current_school,
+    school_code,
+    target_counties,
+    target_districts,
+    created_at,
+    updated_at,
+    version,
+    user_id,
+    hidden
+"#;
+
+// 將查詢結果轉換為 Teacher 結構
+fn teacher_from_row(row: &PgRow) -> Teacher {
+    Teacher {
+        id: row.get("id"),
+        name: row.get("name"),
+        display_id: row.get("display_id"),
+        email: row.get("email"),
+        google_id: row.get("google_id"),
+        year: row.get("year"),
+        school_level: row.get("school_level"),
+        subject: row.get("subject"),
+        current_county: row.get("current_county"),
+        current_district: row.get("current_district"),
+        current_school: row.get("current_school"),
+        school_code: row.get("school_code"),
+        target_counties: row.get("target_counties"),
+        target_districts: row.get("target_districts"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        version: row.get("version"),
+        user_id: row.get("user_id"),
+        hidden: row.get("hidden"),
+    }
+}
+
 pub async fn get_all_teachers(pool: &Pool<Postgres>) -> Result<Vec<Teacher>, sqlx::Error> {
-    let rows = sqlx::query(
-        r#"
-        SELECT 
-            id, 
-            name,
-            display_id,
-            email,
-            google_id,
-            year,
-            subject, 
-            current_county,
-            current_district,
-            current_school,
-            target_counties,
-            target_districts,
-            created_at
-        FROM teachers
-        "#
-    )
+    let rows = sqlx::query(&format!("SELECT {} FROM teachers WHERE deleted_at IS NULL AND NOT hidden", TEACHER_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(teacher_from_row).collect())
+}
+
+// 依年度與目前縣市篩選參與配對的教師（不含隱藏的登記），未指定的條件不篩選
+pub async fn get_teachers_filtered(
+    pool: &Pool<Postgres>,
+    year: Option<i32>,
+    county: Option<&str>,
+) -> Result<Vec<Teacher>, sqlx::Error> {
+    get_teachers_for_admin(pool, year, county, false).await
+}
+
+// 管理介面使用的列表，include_hidden 為 true 時包含被隱藏的登記
+pub async fn get_teachers_for_admin(
+    pool: &Pool<Postgres>,
+    year: Option<i32>,
+    county: Option<&str>,
+    include_hidden: bool,
+) -> Result<Vec<Teacher>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM teachers
+         WHERE deleted_at IS NULL
+           AND ($1::INTEGER IS NULL OR year = $1)
+           AND ($2::TEXT IS NULL OR current_county = $2)
+           AND ($3 OR NOT hidden)
+         ORDER BY year, current_county, current_district, id",
+        TEACHER_COLUMNS
+    ))
+    .bind(year)
+    .bind(county)
+    .bind(include_hidden)
     .fetch_all(pool)
     .await?;
 
-    let teachers = rows.into_iter().map(|row| {
-        Teacher {
-            id: row.get("id"),
-            name: row.get("name"),
-            display_id: row.get("display_id"),
-            email: row.get("email"),
-            google_id: row.get("google_id"),
-            year: row.get("year"),
-            subject: row.get("subject"),
-            current_county: row.get("current_county"),
-            current_district: row.get("current_district"),
-            current_school: row.get("current_school"),
-            target_counties: row.get("target_counties"),
-            target_districts: row.get("target_districts"),
-            created_at: row.get("created_at"),
-        }
-    }).collect();
+    Ok(rows.iter().map(teacher_from_row).collect())
+}
+
+pub async fn get_teacher_by_id(pool: &Pool<Postgres>, teacher_id: i32) -> Result<Option<Teacher>, sqlx::Error> {
+    let row = sqlx::query(&format!("SELECT {} FROM teachers WHERE id = $1 AND deleted_at IS NULL", TEACHER_COLUMNS))
+        .bind(teacher_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.as_ref().map(teacher_from_row))
+}
+
+// 依 ID 取得未刪除且未隱藏的教師，順序不保證與 ids 相同
+pub async fn get_teachers_by_ids(pool: &Pool<Postgres>, ids: &[i32]) -> Result<Vec<Teacher>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM teachers WHERE id = ANY($1) AND deleted_at IS NULL AND NOT hidden",
+        TEACHER_COLUMNS
+    ))
+    .bind(ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(teacher_from_row).collect())
+}
+
+pub async fn create_teacher(pool: &Pool<Postgres>, teacher: Teacher, actor: &str) -> Result<Teacher, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let created = insert_teacher(&mut *tx, teacher).await?;
+    record_history(&mut *tx, &created, HistoryAction::Create, actor).await?;
+    tx.commit().await?;
+
+    Ok(created)
+}
+
+// 在單一交易中新增多位教師，任一筆失敗則全部不寫入
+pub async fn create_teachers(pool: &Pool<Postgres>, teachers: Vec<Teacher>, actor: &str) -> Result<Vec<Teacher>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let mut created = Vec::with_capacity(teachers.len());
+    for teacher in teachers {
+        let teacher = insert_teacher(&mut *tx, teacher).await?;
+        record_history(&mut *tx, &teacher, HistoryAction::Create, actor).await?;
+        created.push(teacher);
+    }
 
-    Ok(teachers)
+    tx.commit().await?;
+    Ok(created)
 }
 
-pub async fn create_teacher(pool: &Pool<Postgres>, teacher: Teacher) -> Result<Teacher, sqlx::Error> {
+async fn insert_teacher<'e>(executor: impl PgExecutor<'e>, teacher: Teacher) -> Result<Teacher, sqlx::Error> {
     let name = teacher.name.clone().unwrap_or_else(|| format!("User-{}", Uuid::new_v4()));
     let display_id = teacher.display_id.clone().unwrap_or_else(|| 
         generate_display_id(&teacher.current_county, &teacher.current_district)
     );
 
-    let row = sqlx::query(
+    let row = sqlx::query(&format!(
         r#"
         INSERT INTO teachers (
             name,
@@ -111,168 +448,198 @@ pub async fn create_teacher(pool: &Pool<Postgres>, teacher: Teacher) -> Result<T
             email,
             google_id,
             year,
+            school_level,
             subject,
             current_county, 
             current_district, 
             current_school, 
+            school_code,
             target_counties,
             target_districts,
-            created_at
+            created_at,
+            updated_at,
+            user_id
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
-        RETURNING 
-            id, 
-            name,
-            display_id,
-            email,
-            google_id,
-            year,
-            subject,
-            current_county, 
-            current_district, 
-            current_school, 
-            target_counties,
-            target_districts,
-            created_at
-        "#
-    )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $14, $15)
+        RETURNING {}
+        "#,
+        TEACHER_COLUMNS
+    ))
     .bind(&name)
     .bind(&display_id)
     .bind(&teacher.email)
     .bind(&teacher.google_id)
-    .bind(&teacher.year)
+    .bind(teacher.year)
+    .bind(&teacher.school_level)
     .bind(&teacher.subject)
     .bind(&teacher.current_county)
     .bind(&teacher.current_district)
     .bind(&teacher.current_school)
+    .bind(&teacher.school_code)
     .bind(&teacher.target_counties)
     .bind(&teacher.target_districts)
     .bind(Utc::now())
-    .fetch_one(pool)
+    .bind(teacher.user_id)
+    .fetch_one(executor)
     .await?;
 
-    Ok(Teacher {
-        id: row.get("id"),
-        name: Some(row.get("name")),
-        display_id: Some(row.get("display_id")),
-        email: row.get("email"),
-        google_id: row.get("google_id"),
-        year: row.get("year"),
-        subject: row.get("subject"),
-        current_county: row.get("current_county"),
-        current_district: row.get("current_district"),
-        current_school: row.get("current_school"),
-        target_counties: row.get("target_counties"),
-        target_districts: row.get("target_districts"),
-        created_at: row.get("created_at"),
-    })
+    Ok(teacher_from_row(&row))
 }
 
-pub async fn get_teacher_by_google_id(
+pub async fn get_teachers_by_google_id(
     pool: &Pool<Postgres>,
     google_id: &str,
-) -> Result<Teacher, sqlx::Error> {
+) -> Result<Vec<Teacher>, sqlx::Error> {
+    // 返回所有與指定 Google ID 關聯的教師記錄
     // 使用 query 而非 query_as! 來避免編譯時需要連接資料庫
-    let row = sqlx::query(
-        r#"
-        SELECT 
-            id, 
-            name,
-            display_id,
-            email,
-            google_id,
-            year,
-            subject,
-            current_county,
-            current_district,
-            current_school,
-            target_counties,
-            target_districts,
-            created_at
-        FROM teachers 
-        WHERE google_id = $1
-        "#
-    )
-    .bind(google_id)
-    .fetch_one(pool)
+    let rows = sqlx::query(&format!("SELECT {} FROM teachers WHERE google_id = $1 AND deleted_at IS NULL", TEACHER_COLUMNS))
+        .bind(google_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(teacher_from_row).collect())
+}
+
+// 帳號下所有未刪除的登記
+pub async fn get_teachers_by_user_id(pool: &Pool<Postgres>, user_id: i32) -> Result<Vec<Teacher>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM teachers WHERE user_id = $1 AND deleted_at IS NULL ORDER BY id",
+        TEACHER_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
     .await?;
 
-    // 手動將查詢結果轉換為 Teacher 結構
-    Ok(Teacher {
+    Ok(rows.iter().map(teacher_from_row).collect())
+}
+
+const USER_COLUMNS: &str = "id, google_sub, email, name, picture, role, admin_county, created_at, last_login_at";
+
+fn user_from_row(row: &PgRow) -> User {
+    let role: String = row.get("role");
+    User {
         id: row.get("id"),
-        name: row.get("name"),
-        display_id: row.get("display_id"),
+        google_sub: row.get("google_sub"),
         email: row.get("email"),
-        google_id: row.get("google_id"),
-        year: row.get("year"),
-        subject: row.get("subject"),
-        current_county: row.get("current_county"),
-        current_district: row.get("current_district"),
-        current_school: row.get("current_school"),
-        target_counties: row.get("target_counties"),
-        target_districts: row.get("target_districts"),
+        name: row.get("name"),
+        picture: row.get("picture"),
+        role: Role::from_db(&role).unwrap_or(Role::User),
+        admin_county: row.get("admin_county"),
         created_at: row.get("created_at"),
-    })
+        last_login_at: row.get("last_login_at"),
+    }
 }
 
-pub async fn get_teachers_by_google_id(
-    pool: &Pool<Postgres>,
-    google_id: &str,
-) -> Result<Vec<Teacher>, sqlx::Error> {
-    // 返回所有與指定 Google ID 關聯的教師記錄
-    let rows = sqlx::query(
+// 登入時建立或更新帳號，並將同一 email（google_id）下尚未連結的登記歸入此帳號
+pub async fn upsert_user(pool: &Pool<Postgres>, login: &NewUser) -> Result<User, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let now = Utc::now();
+
+    let row = sqlx::query(&format!(
         r#"
-        SELECT 
-            id, 
-            name,
-            display_id,
-            email,
-            google_id,
-            year,
-            subject,
-            current_county,
-            current_district,
-            current_school,
-            target_counties,
-            target_districts,
-            created_at
-        FROM teachers 
-        WHERE google_id = $1
-        "#
-    )
-    .bind(google_id)
-    .fetch_all(pool)
+        INSERT INTO users (google_sub, email, name, picture, created_at, last_login_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        ON CONFLICT (google_sub) DO UPDATE
+        SET email = EXCLUDED.email,
+            name = EXCLUDED.name,
+            picture = EXCLUDED.picture,
+            last_login_at = EXCLUDED.last_login_at
+        RETURNING {}
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(&login.google_sub)
+    .bind(&login.email)
+    .bind(&login.name)
+    .bind(&login.picture)
+    .bind(now)
+    .fetch_one(&mut *tx)
     .await?;
+    let user = user_from_row(&row);
 
-    // 將查詢結果轉換為 Teacher 結構的向量
-    let teachers = rows.into_iter().map(|row| {
-        Teacher {
-            id: row.get("id"),
-            name: row.get("name"),
-            display_id: row.get("display_id"),
-            email: row.get("email"),
-            google_id: row.get("google_id"),
-            year: row.get("year"),
-            subject: row.get("subject"),
-            current_county: row.get("current_county"),
-            current_district: row.get("current_district"),
-            current_school: row.get("current_school"),
-            target_counties: row.get("target_counties"),
-            target_districts: row.get("target_districts"),
-            created_at: row.get("created_at"),
-        }
-    }).collect();
+    sqlx::query("UPDATE teachers SET user_id = $1 WHERE google_id = $2 AND user_id IS NULL")
+        .bind(user.id)
+        .bind(&user.email)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(user)
+}
+
+pub async fn get_user_by_sub(pool: &Pool<Postgres>, google_sub: &str) -> Result<Option<User>, sqlx::Error> {
+    let row = sqlx::query(&format!("SELECT {} FROM users WHERE google_sub = $1", USER_COLUMNS))
+        .bind(google_sub)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.as_ref().map(user_from_row))
+}
+
+pub async fn get_users(pool: &Pool<Postgres>) -> Result<Vec<User>, sqlx::Error> {
+    let rows = sqlx::query(&format!("SELECT {} FROM users ORDER BY id", USER_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(user_from_row).collect())
+}
+
+// 修改帳號權限，找不到帳號時返回 None
+pub async fn update_user_role(pool: &Pool<Postgres>, user_id: i32, update: &RoleUpdate) -> Result<Option<User>, sqlx::Error> {
+    let admin_county = (update.role == Role::CountyAdmin).then_some(update.admin_county.as_deref()).flatten();
+    let row = sqlx::query(&format!(
+        "UPDATE users SET role = $1, admin_county = $2 WHERE id = $3 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(update.role.as_str())
+    .bind(admin_county)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
 
-    Ok(teachers)
+    Ok(row.as_ref().map(user_from_row))
 }
 
 pub async fn init_db(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
-    // 先刪除舊表（如果存在）
-    sqlx::query("DROP TABLE IF EXISTS teachers")
+    // 舊版的異動歷史以外鍵參照 teachers；歷史不設外鍵，登記的 ID 不會被重複使用
+    sqlx::query("ALTER TABLE IF EXISTS teacher_history DROP CONSTRAINT IF EXISTS teacher_history_teacher_id_fkey")
+        .execute(pool)
+        .await?;
+
+    // 使用者帳號保留，不隨伺服器重啟刪除；google_sub 為 Google 帳號不會變動的識別碼
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+            id SERIAL PRIMARY KEY,
+            google_sub TEXT NOT NULL UNIQUE,
+            email TEXT NOT NULL,
+            name TEXT,
+            picture TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_login_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // 權限欄位，既有的帳號預設為一般使用者
+    sqlx::query(
+        r#"
+        ALTER TABLE users
+            ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'user'
+                CHECK (role IN ('user', 'county_admin', 'super_admin')),
+            ADD COLUMN IF NOT EXISTS admin_county TEXT
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS users_email_idx ON users (email)")
         .execute(pool)
         .await?;
 
-    // 創建新表，與當前模型匹配
+    // 教師登記保留，不隨伺服器重啟刪除
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS teachers (
@@ -282,74 +649,262 @@ pub async fn init_db(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
             email TEXT NOT NULL,
             google_id TEXT,
             year INTEGER NOT NULL,
+            school_level TEXT NOT NULL DEFAULT '國小',
             subject TEXT,
             current_county TEXT NOT NULL,
             current_district TEXT NOT NULL,
             current_school TEXT NOT NULL,
+            school_code TEXT,
             target_counties TEXT[] NOT NULL,
             target_districts TEXT[] NOT NULL,
-            created_at TIMESTAMPTZ NOT NULL
+            created_at TIMESTAMPTZ NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            version INTEGER NOT NULL DEFAULT 1,
+            notification_preferences JSONB NOT NULL DEFAULT '{}',
+            deleted_at TIMESTAMPTZ,
+            user_id INTEGER REFERENCES users(id),
+            hidden BOOLEAN NOT NULL DEFAULT FALSE
         )
         "#
     )
     .execute(pool)
     .await?;
 
-    // 添加測試數據
-    add_test_data(pool).await?;
-
-    tracing::info!("資料庫初始化完成：teachers 表已創建並添加測試數據");
-    Ok(())
-}
+    // 舊版每次啟動都重建 teachers，沿用舊資料表時補上之後新增的欄位
+    sqlx::query(
+        r#"
+        ALTER TABLE teachers
+            ADD COLUMN IF NOT EXISTS school_level TEXT NOT NULL DEFAULT '國小',
+            ADD COLUMN IF NOT EXISTS school_code TEXT,
+            ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1,
+            ADD COLUMN IF NOT EXISTS notification_preferences JSONB NOT NULL DEFAULT '{}',
+            ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+            ADD COLUMN IF NOT EXISTS user_id INTEGER REFERENCES users(id),
+            ADD COLUMN IF NOT EXISTS hidden BOOLEAN NOT NULL DEFAULT FALSE
+        "#
+    )
+    .execute(pool)
+    .await?;
 
-// 添加測試數據的函數
-async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
-    // 測試數據：台北市教師
-    let taipei_teachers = [
+    // 每次新增、修改、刪除時的完整快照。刪除登記後歷史仍要保留，因此不設外鍵
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS teacher_history (
+            id SERIAL PRIMARY KEY,
+            teacher_id INTEGER NOT NULL,
+            action TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+            snapshot JSONB NOT NULL,
+            actor TEXT NOT NULL,
+            changed_at TIMESTAMPTZ NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS teachers_user_id_idx ON teachers (user_id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS teacher_history_teacher_id_idx ON teacher_history (teacher_id)")
+        .execute(pool)
+        .await?;
+
+    // 舊版重建 teachers 後序號會從頭開始；新登記從現有登記與歷史中最大的 ID 之後編號，不會沿用已有歷史的 ID
+    sqlx::query(
+        r#"
+        SELECT setval(
+            pg_get_serial_sequence('teachers', 'id'),
+            GREATEST(
+                (SELECT COALESCE(MAX(id), 0) FROM teachers),
+                (SELECT COALESCE(MAX(teacher_id), 0) FROM teacher_history)
+            ) + 1,
+            false
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // 配對結果中出現過的循環，參與者修改或刪除登記後標記為 broken
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS match_cycles (
+            id SERIAL PRIMARY KEY,
+            cycle_key TEXT NOT NULL UNIQUE,
+            year INTEGER NOT NULL,
+            school_level TEXT NOT NULL,
+            teacher_ids INTEGER[] NOT NULL,
+            accepted_teacher_ids INTEGER[] NOT NULL DEFAULT '{}',
+            status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'accepted', 'broken')),
+            found_at TIMESTAMPTZ NOT NULL,
+            broken_at TIMESTAMPTZ,
+            broken_reason TEXT
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS notifications (
+            id SERIAL PRIMARY KEY,
+            teacher_id INTEGER NOT NULL REFERENCES teachers(id),
+            google_id TEXT,
+            kind TEXT NOT NULL,
+            cycle_id INTEGER REFERENCES match_cycles(id),
+            message TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            read_at TIMESTAMPTZ,
+            -- 寄送佇列：pending 的通知由背景工作依 email_next_attempt_at 重試
+            email_status TEXT NOT NULL DEFAULT 'skipped'
+                CHECK (email_status IN ('pending', 'sent', 'failed', 'skipped')),
+            email_attempts INTEGER NOT NULL DEFAULT 0,
+            email_next_attempt_at TIMESTAMPTZ,
+            email_sent_at TIMESTAMPTZ,
+            email_last_error TEXT
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS notifications_google_id_idx ON notifications (google_id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS notifications_email_queue_idx ON notifications (email_next_attempt_at) WHERE email_status = 'pending'"
+    )
+    .execute(pool)
+    .await?;
+
+    // 輪次資料保留，不隨伺服器重啟刪除
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS rounds (
+            year INTEGER PRIMARY KEY,
+            state TEXT NOT NULL DEFAULT 'draft'
+                CHECK (state IN ('draft', 'open', 'closed', 'archived')),
+            registration_opens_at TIMESTAMPTZ,
+            registration_closes_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // 網站公告保留，不隨伺服器重啟刪除
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS announcements (
+            id SERIAL PRIMARY KEY,
+            title TEXT,
+            body TEXT NOT NULL,
+            severity TEXT NOT NULL DEFAULT 'info'
+                CHECK (severity IN ('info', 'warning', 'critical')),
+            publish_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expire_at TIMESTAMPTZ,
+            created_by TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            CHECK (expire_at IS NULL OR expire_at > publish_at)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS announcements_publish_at_idx ON announcements (publish_at)")
+        .execute(pool)
+        .await?;
+
+    // 添加測試數據，只在尚未有任何登記（包含已刪除的）時加入，重啟後不會重複
+    let seeded: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM teachers)")
+        .fetch_one(pool)
+        .await?;
+    if !seeded {
+        add_test_data(pool).await?;
+    }
+
+    // 測試數據屬於 114 年度，沒有該輪次時建立為開放狀態
+    sqlx::query("INSERT INTO rounds (year, state) VALUES (114, 'open') ON CONFLICT (year) DO NOTHING")
+        .execute(pool)
+        .await?;
+
+    tracing::info!("資料庫初始化完成");
+    Ok(())
+}
+
+// 添加測試數據的函數
+async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
+    // 測試數據：台北市教師
+    let taipei_teachers = [
         Teacher {
             id: None,
             name: Some("測試教師1".to_string()),
             display_id: Some("臺北市大安區#001".to_string()),
-            email: format!("test1@example.com"),
+            email: "test1@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_JUNIOR_HIGH.to_string(),
             subject: "數學".to_string(),
             current_county: "臺北市".to_string(),
             current_district: "大安區".to_string(),
-            current_school: "大安國小".to_string(),
+            current_school: "大安國中".to_string(),
+            school_code: None,
             target_counties: vec!["新北市".to_string()],
             target_districts: vec!["板橋區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師2".to_string()),
             display_id: Some("臺北市信義區#002".to_string()),
-            email: format!("test2@example.com"),
+            email: "test2@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "英文".to_string(),
             current_county: "臺北市".to_string(),
             current_district: "信義區".to_string(),
             current_school: "信義國小".to_string(),
+            school_code: None,
             target_counties: vec!["臺北市".to_string(), "臺中市".to_string()],
             target_districts: vec!["大安區".to_string(), "西區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師3".to_string()),
             display_id: Some("臺北市中正區#003".to_string()),
-            email: format!("test3@example.com"),
+            email: "test3@example.com".to_string(),
             google_id: None,
             year: 114,
-            subject: "自然".to_string(),
+            school_level: SCHOOL_LEVEL_JUNIOR_HIGH.to_string(),
+            subject: "理化".to_string(),
             current_county: "臺北市".to_string(),
             current_district: "中正區".to_string(),
-            current_school: "中正國小".to_string(),
+            current_school: "中正國中".to_string(),
+            school_code: None,
             target_counties: vec!["新北市".to_string(), "臺中市".to_string()],
             target_districts: vec!["中和區".to_string(), "北區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
     ];
 
@@ -359,46 +914,64 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
             id: None,
             name: Some("測試教師4".to_string()),
             display_id: Some("新北市板橋區#004".to_string()),
-            email: format!("test4@example.com"),
+            email: "test4@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_JUNIOR_HIGH.to_string(),
             subject: "數學".to_string(),
             current_county: "新北市".to_string(),
             current_district: "板橋區".to_string(),
-            current_school: "板橋國小".to_string(),
+            current_school: "板橋國中".to_string(),
+            school_code: None,
             target_counties: vec!["臺北市".to_string()],
             target_districts: vec!["大安區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師5".to_string()),
             display_id: Some("新北市中和區#005".to_string()),
-            email: format!("test5@example.com"),
+            email: "test5@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "英文".to_string(),
             current_county: "新北市".to_string(),
             current_district: "中和區".to_string(),
             current_school: "中和國小".to_string(),
+            school_code: None,
             target_counties: vec!["臺北市".to_string(), "桃園市".to_string()],
             target_districts: vec!["中正區".to_string(), "中壢區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師6".to_string()),
             display_id: Some("新北市三重區#006".to_string()),
-            email: format!("test6@example.com"),
+            email: "test6@example.com".to_string(),
             google_id: None,
             year: 114,
-            subject: "自然".to_string(),
+            school_level: SCHOOL_LEVEL_JUNIOR_HIGH.to_string(),
+            subject: "理化".to_string(),
             current_county: "新北市".to_string(),
             current_district: "三重區".to_string(),
-            current_school: "三重國小".to_string(),
+            current_school: "三重國中".to_string(),
+            school_code: None,
             target_counties: vec!["臺北市".to_string(), "臺中市".to_string()],
             target_districts: vec!["信義區".to_string(), "西區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
     ];
 
@@ -408,31 +981,43 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
             id: None,
             name: Some("測試教師7".to_string()),
             display_id: Some("臺中市西區#007".to_string()),
-            email: format!("test7@example.com"),
+            email: "test7@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_JUNIOR_HIGH.to_string(),
             subject: "數學".to_string(),
             current_county: "臺中市".to_string(),
             current_district: "西區".to_string(),
-            current_school: "西區國小".to_string(),
+            current_school: "西區國中".to_string(),
+            school_code: None,
             target_counties: vec!["新北市".to_string()],
             target_districts: vec!["板橋區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師8".to_string()),
             display_id: Some("臺中市北區#008".to_string()),
-            email: format!("test8@example.com"),
+            email: "test8@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "英文".to_string(),
             current_county: "臺中市".to_string(),
             current_district: "北區".to_string(),
             current_school: "北區國小".to_string(),
+            school_code: None,
             target_counties: vec!["臺北市".to_string(), "新北市".to_string()],
             target_districts: vec!["大安區".to_string(), "三重區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
     ];
     
@@ -442,31 +1027,43 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
             id: None,
             name: Some("測試教師9".to_string()),
             display_id: Some("桃園市中壢區#009".to_string()),
-            email: format!("test9@example.com"),
+            email: "test9@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_JUNIOR_HIGH.to_string(),
             subject: "數學".to_string(),
             current_county: "桃園市".to_string(),
             current_district: "中壢區".to_string(),
-            current_school: "中壢國小".to_string(),
+            current_school: "中壢國中".to_string(),
+            school_code: None,
             target_counties: vec!["新北市".to_string(), "臺北市".to_string()],
             target_districts: vec!["中和區".to_string(), "大安區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師10".to_string()),
             display_id: Some("桃園市桃園區#010".to_string()),
-            email: format!("test10@example.com"),
+            email: "test10@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "英文".to_string(),
             current_county: "桃園市".to_string(),
             current_district: "桃園區".to_string(),
             current_school: "桃園國小".to_string(),
+            school_code: None,
             target_counties: vec!["臺中市".to_string(), "新北市".to_string()],
             target_districts: vec!["西區".to_string(), "板橋區".to_string()],
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
     ];
 
@@ -476,76 +1073,106 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
             id: None,
             name: Some("測試教師11".to_string()),
             display_id: Some("臺北市士林區#011".to_string()),
-            email: format!("test11@example.com"),
+            email: "test11@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "一般".to_string(),
             current_county: "臺北市".to_string(),
             current_district: "士林區".to_string(),
             current_school: "士林國小".to_string(),
+            school_code: None,
             target_counties: vec!["新北市".to_string()],
             target_districts: vec!["永和區".to_string()], // 指向測試教師12
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師12".to_string()),
             display_id: Some("新北市永和區#012".to_string()),
-            email: format!("test12@example.com"),
+            email: "test12@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "一般".to_string(),
             current_county: "新北市".to_string(),
             current_district: "永和區".to_string(),
             current_school: "永和國小".to_string(),
+            school_code: None,
             target_counties: vec!["桃園市".to_string()],
             target_districts: vec!["平鎮區".to_string()], // 指向測試教師13
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師13".to_string()),
             display_id: Some("桃園市平鎮區#013".to_string()),
-            email: format!("test13@example.com"),
+            email: "test13@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "一般".to_string(),
             current_county: "桃園市".to_string(),
             current_district: "平鎮區".to_string(),
             current_school: "平鎮國小".to_string(),
+            school_code: None,
             target_counties: vec!["臺中市".to_string()],
             target_districts: vec!["南屯區".to_string()], // 指向測試教師14
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師14".to_string()),
             display_id: Some("臺中市南屯區#014".to_string()),
-            email: format!("test14@example.com"),
+            email: "test14@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "一般".to_string(),
             current_county: "臺中市".to_string(),
             current_district: "南屯區".to_string(),
             current_school: "南屯國小".to_string(),
+            school_code: None,
             target_counties: vec!["高雄市".to_string()],
             target_districts: vec!["三民區".to_string()], // 指向測試教師15
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
         Teacher {
             id: None,
             name: Some("測試教師15".to_string()),
             display_id: Some("高雄市三民區#015".to_string()),
-            email: format!("test15@example.com"),
+            email: "test15@example.com".to_string(),
             google_id: None,
             year: 114,
+            school_level: SCHOOL_LEVEL_ELEMENTARY.to_string(),
             subject: "一般".to_string(),
             current_county: "高雄市".to_string(),
             current_district: "三民區".to_string(),
             current_school: "三民國小".to_string(),
+            school_code: None,
             target_counties: vec!["臺北市".to_string()],
             target_districts: vec!["士林區".to_string()], // 指向測試教師11，形成5角調
             created_at: None,
+            updated_at: None,
+            version: 0,
+            user_id: None,
+            hidden: false,
         },
     ];
 
@@ -567,6 +1194,7 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
                 display_id,
                 email,
                 year,
+                school_level,
                 subject,
                 current_county, 
                 current_district, 
@@ -575,13 +1203,14 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
                 target_districts,
                 created_at
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             "#
         )
         .bind(&teacher.name)
         .bind(&teacher.display_id)
         .bind(&teacher.email)
-        .bind(&teacher.year)
+        .bind(teacher.year)
+        .bind(&teacher.school_level)
         .bind(&teacher.subject)
         .bind(&teacher.current_county)
         .bind(&teacher.current_district)
@@ -596,11 +1225,56 @@ async fn add_test_data(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
+// 隱藏或恢復登記，視為一次修改並寫入歷史紀錄；找不到教師時返回 None
+pub async fn set_teacher_hidden(
+    pool: &Pool<Postgres>,
+    teacher_id: i32,
+    hidden: bool,
+    actor: &str,
+) -> Result<Option<Teacher>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE teachers
+        SET hidden = $1, updated_at = $2, version = version + 1
+        WHERE id = $3 AND deleted_at IS NULL
+        RETURNING {}
+        "#,
+        TEACHER_COLUMNS
+    ))
+    .bind(hidden)
+    .bind(Utc::now())
+    .bind(teacher_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let teacher = teacher_from_row(&row);
+    record_history(&mut *tx, &teacher, HistoryAction::Update, actor).await?;
+    tx.commit().await?;
+
+    Ok(Some(teacher))
+}
+
+// 更新結果：版本不符時不寫入，並返回資料庫中目前的內容
+#[derive(Debug)]
+pub enum UpdateOutcome {
+    Updated(Teacher),
+    Stale(Teacher),
+}
+
+// expected_version 為 None 時不檢查版本（If-Match: *）；
+// teacher.user_id 只在登記尚未連結帳號時寫入，不會改掉既有的連結
 pub async fn update_teacher(
     pool: &Pool<Postgres>,
     teacher_id: i32,
     teacher: Teacher,
-) -> Result<Teacher, sqlx::Error> {
+    expected_version: Option<i32>,
+    actor: &str,
+) -> Result<UpdateOutcome, sqlx::Error> {
     // Ensure the teacher ID matches
     if teacher.id.is_some() && teacher.id.unwrap() != teacher_id {
         return Err(sqlx::Error::RowNotFound);
@@ -609,105 +1283,764 @@ pub async fn update_teacher(
     // Using transaction to ensure atomicity
     let mut tx = pool.begin().await?;
 
-    // Check if teacher exists first
-    let existing = sqlx::query("SELECT id FROM teachers WHERE id = $1")
-        .bind(teacher_id)
-        .fetch_optional(&mut *tx)
-        .await?;
+    // Check if teacher exists first, locking the row until the update is committed
+    let existing = sqlx::query(&format!(
+        "SELECT {} FROM teachers WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        TEACHER_COLUMNS
+    ))
+    .bind(teacher_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
 
-    if existing.is_none() {
-        return Err(sqlx::Error::RowNotFound);
+    let current = teacher_from_row(&existing);
+    if expected_version.is_some_and(|version| version != current.version) {
+        return Ok(UpdateOutcome::Stale(current));
     }
 
     // Update teacher data
-    let row = sqlx::query(
+    let row = sqlx::query(&format!(
         r#"
         UPDATE teachers 
         SET 
             name = $1,
             email = $2,
             year = $3,
-            subject = $4,
-            current_county = $5, 
-            current_district = $6, 
-            current_school = $7, 
-            target_counties = $8,
-            target_districts = $9
-        WHERE id = $10
-        RETURNING 
-            id, 
-            name,
-            display_id,
-            email,
-            google_id,
-            year,
-            subject,
-            current_county, 
-            current_district, 
-            current_school, 
-            target_counties,
-            target_districts,
-            created_at
-        "#
-    )
-    .bind(&teacher.name.unwrap_or_else(|| format!("User-{}", Uuid::new_v4())))
+            school_level = $4,
+            subject = $5,
+            current_county = $6, 
+            current_district = $7, 
+            current_school = $8, 
+            school_code = $9,
+            target_counties = $10,
+            target_districts = $11,
+            updated_at = $12,
+            version = version + 1,
+            user_id = COALESCE(user_id, $14)
+        WHERE id = $13
+        RETURNING {}
+        "#,
+        TEACHER_COLUMNS
+    ))
+    .bind(teacher.name.unwrap_or_else(|| format!("User-{}", Uuid::new_v4())))
     .bind(&teacher.email)
-    .bind(&teacher.year)
+    .bind(teacher.year)
+    .bind(&teacher.school_level)
     .bind(&teacher.subject)
     .bind(&teacher.current_county)
     .bind(&teacher.current_district)
     .bind(&teacher.current_school)
+    .bind(&teacher.school_code)
     .bind(&teacher.target_counties)
     .bind(&teacher.target_districts)
+    .bind(Utc::now())
     .bind(teacher_id)
+    .bind(teacher.user_id)
     .fetch_one(&mut *tx)
     .await?;
 
+    let updated = teacher_from_row(&row);
+    record_history(&mut *tx, &updated, HistoryAction::Update, actor).await?;
+
     // Commit the transaction
     tx.commit().await?;
 
-    Ok(Teacher {
-        id: row.get("id"),
-        name: Some(row.get("name")),
-        display_id: Some(row.get("display_id")),
-        email: row.get("email"),
-        google_id: row.get("google_id"),
-        year: row.get("year"),
-        subject: row.get("subject"),
-        current_county: row.get("current_county"),
-        current_district: row.get("current_district"),
-        current_school: row.get("current_school"),
-        target_counties: row.get("target_counties"),
-        target_districts: row.get("target_districts"),
-        created_at: row.get("created_at"),
-    })
+    Ok(UpdateOutcome::Updated(updated))
 }
 
+// 軟刪除：只標記 deleted_at，保留資料供歷史紀錄查詢
 pub async fn delete_teacher(
     pool: &Pool<Postgres>, 
-    teacher_id: i32
+    teacher_id: i32,
+    actor: &str,
 ) -> Result<(), sqlx::Error> {
     // Using transaction to ensure atomicity
     let mut tx = pool.begin().await?;
 
-    // Check if teacher exists first
-    let existing = sqlx::query("SELECT id FROM teachers WHERE id = $1")
-        .bind(teacher_id)
+    let row = sqlx::query(&format!(
+        "UPDATE teachers SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL RETURNING {}",
+        TEACHER_COLUMNS
+    ))
+    .bind(Utc::now())
+    .bind(teacher_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    record_history(&mut *tx, &teacher_from_row(&row), HistoryAction::Delete, actor).await?;
+
+    // Commit the transaction
+    tx.commit().await?;
+
+    Ok(())
+}
+
+// 教師登記異動的種類
+#[derive(Debug, Clone, Copy)]
+pub enum HistoryAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl HistoryAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HistoryAction::Create => "create",
+            HistoryAction::Update => "update",
+            HistoryAction::Delete => "delete",
+        }
+    }
+}
+
+// 保存異動後的完整快照，與異動本身在同一交易中寫入
+async fn record_history<'e>(
+    executor: impl PgExecutor<'e>,
+    teacher: &Teacher,
+    action: HistoryAction,
+    actor: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO teacher_history (teacher_id, action, snapshot, actor, changed_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#
+    )
+    .bind(teacher.id)
+    .bind(action.as_str())
+    .bind(sqlx::types::Json(teacher))
+    .bind(actor)
+    .bind(Utc::now())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// 教師登記的所有異動紀錄（包含已刪除的登記），依時間排序
+pub async fn get_teacher_history(
+    pool: &Pool<Postgres>,
+    teacher_id: i32,
+) -> Result<Vec<TeacherHistoryEntry>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, teacher_id, action, snapshot, actor, changed_at
+        FROM teacher_history
+        WHERE teacher_id = $1
+        ORDER BY changed_at, id
+        "#
+    )
+    .bind(teacher_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            let snapshot: sqlx::types::Json<Teacher> = row.try_get("snapshot")?;
+            Ok(TeacherHistoryEntry {
+                id: row.get("id"),
+                teacher_id: row.get("teacher_id"),
+                action: row.get("action"),
+                actor: row.get("actor"),
+                changed_at: row.get("changed_at"),
+                snapshot: snapshot.0,
+            })
+        })
+        .collect()
+}
+// rounds 表查詢時共用的欄位列表
+const ROUND_COLUMNS: &str = "year, state, registration_opens_at, registration_closes_at, created_at";
+
+fn round_from_row(row: &PgRow) -> Round {
+    let state: String = row.get("state");
+    Round {
+        year: row.get("year"),
+        // state 欄位有 CHECK 限制，不會出現其他值
+        state: RoundState::from_db(&state).unwrap_or(RoundState::Draft),
+        registration_opens_at: row.get("registration_opens_at"),
+        registration_closes_at: row.get("registration_closes_at"),
+        created_at: row.get("created_at"),
+    }
+}
+
+pub async fn get_rounds(pool: &Pool<Postgres>) -> Result<Vec<Round>, sqlx::Error> {
+    let rows = sqlx::query(&format!("SELECT {} FROM rounds ORDER BY year DESC", ROUND_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(round_from_row).collect())
+}
+
+pub async fn get_round(pool: &Pool<Postgres>, year: i32) -> Result<Option<Round>, sqlx::Error> {
+    let row = sqlx::query(&format!("SELECT {} FROM rounds WHERE year = $1", ROUND_COLUMNS))
+        .bind(year)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.as_ref().map(round_from_row))
+}
+
+// 目前輪次：最新一個開放中或已截止（配對作業中）的輪次
+pub async fn get_current_round(pool: &Pool<Postgres>) -> Result<Option<Round>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM rounds WHERE state IN ('open', 'closed') ORDER BY year DESC LIMIT 1",
+        ROUND_COLUMNS
+    ))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(round_from_row))
+}
+
+// 指定年度之後最早的開放中輪次，用於把登記複製到下一輪
+pub async fn get_next_open_round(pool: &Pool<Postgres>, after_year: i32) -> Result<Option<Round>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM rounds WHERE year > $1 AND state = 'open' ORDER BY year LIMIT 1",
+        ROUND_COLUMNS
+    ))
+    .bind(after_year)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(round_from_row))
+}
+
+pub async fn create_round(pool: &Pool<Postgres>, round: NewRound) -> Result<Round, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        r#"
+        INSERT INTO rounds (year, state, registration_opens_at, registration_closes_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING {}
+        "#,
+        ROUND_COLUMNS
+    ))
+    .bind(round.year)
+    .bind(RoundState::Draft.as_str())
+    .bind(round.registration_opens_at)
+    .bind(round.registration_closes_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(round_from_row(&row))
+}
+
+pub async fn update_round(pool: &Pool<Postgres>, year: i32, update: RoundUpdate) -> Result<Round, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE rounds
+        SET state = $1,
+            registration_opens_at = COALESCE($2, registration_opens_at),
+            registration_closes_at = COALESCE($3, registration_closes_at)
+        WHERE year = $4
+        RETURNING {}
+        "#,
+        ROUND_COLUMNS
+    ))
+    .bind(update.state.as_str())
+    .bind(update.registration_opens_at)
+    .bind(update.registration_closes_at)
+    .bind(year)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(round_from_row).ok_or(sqlx::Error::RowNotFound)
+}
+
+const ANNOUNCEMENT_COLUMNS: &str =
+    "id, title, body, severity, publish_at, expire_at, created_by, created_at, updated_at";
+
+fn announcement_from_row(row: &PgRow) -> Announcement {
+    let severity: String = row.get("severity");
+    Announcement {
+        id: row.get("id"),
+        title: row.get("title"),
+        body: row.get("body"),
+        severity: AnnouncementSeverity::from_db(&severity).unwrap_or_default(),
+        publish_at: row.get("publish_at"),
+        expire_at: row.get("expire_at"),
+        created_by: row.get("created_by"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+// 目前顯示中的公告，重要程度高的在前，同等級時較新的在前
+pub async fn get_active_announcements(pool: &Pool<Postgres>) -> Result<Vec<Announcement>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT {} FROM announcements
+        WHERE publish_at <= NOW() AND (expire_at IS NULL OR expire_at > NOW())
+        ORDER BY CASE severity WHEN 'critical' THEN 0 WHEN 'warning' THEN 1 ELSE 2 END, publish_at DESC
+        "#,
+        ANNOUNCEMENT_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(announcement_from_row).collect())
+}
+
+// 所有公告（含排程中與已過期），供管理介面使用
+pub async fn get_announcements(pool: &Pool<Postgres>) -> Result<Vec<Announcement>, sqlx::Error> {
+    let rows = sqlx::query(&format!("SELECT {} FROM announcements ORDER BY publish_at DESC, id DESC", ANNOUNCEMENT_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(announcement_from_row).collect())
+}
+
+pub async fn create_announcement(pool: &Pool<Postgres>, input: &AnnouncementInput, actor: &str) -> Result<Announcement, sqlx::Error> {
+    let now = Utc::now();
+    let row = sqlx::query(&format!(
+        r#"
+        INSERT INTO announcements (title, body, severity, publish_at, expire_at, created_by, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+        RETURNING {}
+        "#,
+        ANNOUNCEMENT_COLUMNS
+    ))
+    .bind(&input.title)
+    .bind(&input.body)
+    .bind(input.severity.as_str())
+    .bind(input.publish_at.unwrap_or(now))
+    .bind(input.expire_at)
+    .bind(actor)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(announcement_from_row(&row))
+}
+
+// 修改公告，找不到時返回 None；未指定 publish_at 時保留原本的發布時間
+pub async fn update_announcement(pool: &Pool<Postgres>, id: i32, input: &AnnouncementInput) -> Result<Option<Announcement>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE announcements
+        SET title = $1, body = $2, severity = $3, publish_at = COALESCE($4, publish_at), expire_at = $5, updated_at = $6
+        WHERE id = $7
+        RETURNING {}
+        "#,
+        ANNOUNCEMENT_COLUMNS
+    ))
+    .bind(&input.title)
+    .bind(&input.body)
+    .bind(input.severity.as_str())
+    .bind(input.publish_at)
+    .bind(input.expire_at)
+    .bind(Utc::now())
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(announcement_from_row))
+}
+
+// 刪除公告，返回是否有刪除資料
+pub async fn delete_announcement(pool: &Pool<Postgres>, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM announcements WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// match_cycles 表查詢時共用的欄位列表
+const CYCLE_COLUMNS: &str =
+    "id, cycle_key, year, school_level, teacher_ids, accepted_teacher_ids, status, found_at, broken_at, broken_reason";
+
+fn cycle_from_row(row: &PgRow) -> MatchCycle {
+    MatchCycle {
+        id: row.get("id"),
+        cycle_key: row.get("cycle_key"),
+        year: row.get("year"),
+        school_level: row.get("school_level"),
+        teacher_ids: row.get("teacher_ids"),
+        accepted_teacher_ids: row.get("accepted_teacher_ids"),
+        status: row.get("status"),
+        found_at: row.get("found_at"),
+        broken_at: row.get("broken_at"),
+        broken_reason: row.get("broken_reason"),
+    }
+}
+
+// 保存配對結果中的循環，返回這次新出現（或失效後再次成立）的循環
+pub async fn save_found_cycles(pool: &Pool<Postgres>, matches: &[MatchResult]) -> Result<Vec<MatchCycle>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut newly_active = Vec::new();
+
+    for result in matches {
+        let Some(first) = result.teachers.first() else { continue };
+        let teacher_ids: Vec<i32> = result.teachers.iter().filter_map(|t| t.id).collect();
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO match_cycles (cycle_key, year, school_level, teacher_ids, status, found_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (cycle_key) DO UPDATE
+                SET status = EXCLUDED.status,
+                    accepted_teacher_ids = '{{}}',
+                    found_at = EXCLUDED.found_at,
+                    broken_at = NULL,
+                    broken_reason = NULL
+                WHERE match_cycles.status = $7
+            RETURNING {}
+            "#,
+            CYCLE_COLUMNS
+        ))
+        .bind(report::cycle_key(result))
+        .bind(first.year)
+        .bind(&first.school_level)
+        .bind(&teacher_ids)
+        .bind(CYCLE_STATUS_ACTIVE)
+        .bind(Utc::now())
+        .bind(CYCLE_STATUS_BROKEN)
         .fetch_optional(&mut *tx)
         .await?;
 
-    if existing.is_none() {
-        return Err(sqlx::Error::RowNotFound);
+        if let Some(row) = row {
+            newly_active.push(cycle_from_row(&row));
+        }
     }
 
-    // Delete the teacher
-    sqlx::query("DELETE FROM teachers WHERE id = $1")
-        .bind(teacher_id)
-        .execute(&mut *tx)
+    tx.commit().await?;
+    Ok(newly_active)
+}
+
+// 包含指定教師、尚未失效（active 或 accepted）的循環
+pub async fn get_active_cycles_with_teacher(pool: &Pool<Postgres>, teacher_id: i32) -> Result<Vec<MatchCycle>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM match_cycles WHERE status IN ($1, $2) AND $3 = ANY(teacher_ids) ORDER BY id",
+        CYCLE_COLUMNS
+    ))
+    .bind(CYCLE_STATUS_ACTIVE)
+    .bind(CYCLE_STATUS_ACCEPTED)
+    .bind(teacher_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(cycle_from_row).collect())
+}
+
+// 將循環標記為失效並寫入通知；循環已被其他請求標記時返回 None 且不重複通知
+pub async fn mark_cycle_broken(
+    pool: &Pool<Postgres>,
+    cycle_id: i32,
+    reason: &str,
+    notifications: &[NewNotification],
+) -> Result<Option<MatchCycle>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE match_cycles
+        SET status = $1, broken_at = $2, broken_reason = $3
+        WHERE id = $4 AND status IN ($5, $6)
+        RETURNING {}
+        "#,
+        CYCLE_COLUMNS
+    ))
+    .bind(CYCLE_STATUS_BROKEN)
+    .bind(Utc::now())
+    .bind(reason)
+    .bind(cycle_id)
+    .bind(CYCLE_STATUS_ACTIVE)
+    .bind(CYCLE_STATUS_ACCEPTED)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    for notification in notifications {
+        insert_notification(&mut *tx, notification, Some(cycle_id)).await?;
+    }
+
+    tx.commit().await?;
+    Ok(Some(cycle_from_row(&row)))
+}
+
+// 寫入通知，並依收件者的通知偏好決定是否加入 Email 寄送佇列
+async fn insert_notification<'e>(
+    executor: impl PgExecutor<'e>,
+    notification: &NewNotification,
+    cycle_id: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (
+            teacher_id, google_id, kind, cycle_id, message, created_at,
+            email_status, email_next_attempt_at
+        )
+        SELECT
+            t.id, t.google_id, $2, $3, $4, $5,
+            CASE
+                WHEN t.email <> ''
+                    AND COALESCE((t.notification_preferences->>'email')::BOOLEAN, TRUE)
+                    AND COALESCE((t.notification_preferences->>$2)::BOOLEAN, TRUE)
+                THEN 'pending'
+                ELSE 'skipped'
+            END,
+            $5
+        FROM teachers t
+        WHERE t.id = $1
+        "#
+    )
+    .bind(notification.teacher_id)
+    .bind(notification.kind)
+    .bind(cycle_id)
+    .bind(&notification.message)
+    .bind(Utc::now())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// 為循環的參與者寫入通知（例如找到新循環時）
+pub async fn add_cycle_notifications(
+    pool: &Pool<Postgres>,
+    cycle_id: i32,
+    notifications: &[NewNotification],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for notification in notifications {
+        insert_notification(&mut *tx, notification, Some(cycle_id)).await?;
+    }
+    tx.commit().await
+}
+
+const NOTIFICATION_COLUMNS: &str = "id, teacher_id, google_id, kind, cycle_id, message, created_at, read_at, email_status";
+
+fn notification_from_row(row: &PgRow) -> Notification {
+    Notification {
+        id: row.get("id"),
+        teacher_id: row.get("teacher_id"),
+        google_id: row.get("google_id"),
+        kind: row.get("kind"),
+        cycle_id: row.get("cycle_id"),
+        message: row.get("message"),
+        created_at: row.get("created_at"),
+        read_at: row.get("read_at"),
+        email_status: row.get("email_status"),
+    }
+}
+
+pub async fn get_notifications_by_google_id(
+    pool: &Pool<Postgres>,
+    google_id: &str,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM notifications WHERE google_id = $1 ORDER BY created_at DESC, id DESC",
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(google_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(notification_from_row).collect())
+}
+
+pub async fn get_notification(pool: &Pool<Postgres>, notification_id: i32) -> Result<Option<Notification>, sqlx::Error> {
+    let row = sqlx::query(&format!("SELECT {} FROM notifications WHERE id = $1", NOTIFICATION_COLUMNS))
+        .bind(notification_id)
+        .fetch_optional(pool)
         .await?;
 
-    // Commit the transaction
+    Ok(row.as_ref().map(notification_from_row))
+}
+
+pub async fn mark_notification_read(pool: &Pool<Postgres>, notification_id: i32) -> Result<Notification, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "UPDATE notifications SET read_at = COALESCE(read_at, $1) WHERE id = $2 RETURNING {}",
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(Utc::now())
+    .bind(notification_id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(notification_from_row).ok_or(sqlx::Error::RowNotFound)
+}
+
+pub async fn get_cycle(pool: &Pool<Postgres>, cycle_id: i32) -> Result<Option<MatchCycle>, sqlx::Error> {
+    let row = sqlx::query(&format!("SELECT {} FROM match_cycles WHERE id = $1", CYCLE_COLUMNS))
+        .bind(cycle_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.as_ref().map(cycle_from_row))
+}
+
+// 記錄教師確認接受循環；全員確認時將循環標記為 accepted 並寫入通知
+// 循環已失效或教師已確認過時返回 None
+pub async fn accept_cycle(
+    pool: &Pool<Postgres>,
+    cycle_id: i32,
+    teacher_id: i32,
+    accepted_message: &str,
+) -> Result<Option<MatchCycle>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE match_cycles
+        SET accepted_teacher_ids = array_append(accepted_teacher_ids, $1)
+        WHERE id = $2 AND status = $3
+            AND $1 = ANY(teacher_ids)
+            AND NOT ($1 = ANY(accepted_teacher_ids))
+        RETURNING {}
+        "#,
+        CYCLE_COLUMNS
+    ))
+    .bind(teacher_id)
+    .bind(cycle_id)
+    .bind(CYCLE_STATUS_ACTIVE)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let mut cycle = cycle_from_row(&row);
+
+    if cycle.teacher_ids.iter().all(|id| cycle.accepted_teacher_ids.contains(id)) {
+        sqlx::query("UPDATE match_cycles SET status = $1 WHERE id = $2")
+            .bind(CYCLE_STATUS_ACCEPTED)
+            .bind(cycle_id)
+            .execute(&mut *tx)
+            .await?;
+        cycle.status = CYCLE_STATUS_ACCEPTED.to_string();
+
+        for participant_id in &cycle.teacher_ids {
+            let notification = NewNotification {
+                teacher_id: *participant_id,
+                kind: NOTIFICATION_MATCH_ACCEPTED,
+                message: accepted_message.to_string(),
+            };
+            insert_notification(&mut *tx, &notification, Some(cycle_id)).await?;
+        }
+    }
+
     tx.commit().await?;
+    Ok(Some(cycle))
+}
 
+pub async fn get_notification_preferences(
+    pool: &Pool<Postgres>,
+    teacher_id: i32,
+) -> Result<NotificationPreferences, sqlx::Error> {
+    let row = sqlx::query("SELECT notification_preferences FROM teachers WHERE id = $1 AND deleted_at IS NULL")
+        .bind(teacher_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    let preferences: sqlx::types::Json<NotificationPreferences> = row.try_get("notification_preferences")?;
+    Ok(preferences.0)
+}
+
+pub async fn update_notification_preferences(
+    pool: &Pool<Postgres>,
+    teacher_id: i32,
+    preferences: &NotificationPreferences,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query("UPDATE teachers SET notification_preferences = $1 WHERE id = $2 AND deleted_at IS NULL")
+        .bind(sqlx::types::Json(preferences))
+        .bind(teacher_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+// 佇列中一封待寄送的通知信
+#[derive(Debug, Clone)]
+pub struct PendingEmail {
+    pub notification_id: i32,
+    pub kind: String,
+    pub message: String,
+    pub email: String,
+    pub name: String,
+    pub attempts: i32,
+}
+
+// 取出到期的待寄信件並延後其下次嘗試時間（租約），避免多個工作同時寄送同一封
+pub async fn claim_pending_emails(
+    pool: &Pool<Postgres>,
+    limit: i64,
+    lease: chrono::Duration,
+) -> Result<Vec<PendingEmail>, sqlx::Error> {
+    let now = Utc::now();
+    let rows = sqlx::query(
+        r#"
+        UPDATE notifications n
+        SET email_attempts = n.email_attempts + 1,
+            email_next_attempt_at = $1
+        FROM teachers t
+        WHERE t.id = n.teacher_id
+            AND n.id IN (
+                SELECT id FROM notifications
+                WHERE email_status = 'pending' AND email_next_attempt_at <= $2
+                ORDER BY email_next_attempt_at, id
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+        RETURNING n.id, n.kind, n.message, n.email_attempts, t.email, t.name
+        "#
+    )
+    .bind(now + lease)
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter()
+        .map(|row| PendingEmail {
+            notification_id: row.get("id"),
+            kind: row.get("kind"),
+            message: row.get("message"),
+            email: row.get("email"),
+            name: row.get("name"),
+            attempts: row.get("email_attempts"),
+        })
+        .collect())
+}
+
+pub async fn mark_email_sent(pool: &Pool<Postgres>, notification_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE notifications SET email_status = 'sent', email_sent_at = $1, email_last_error = NULL WHERE id = $2")
+        .bind(Utc::now())
+        .bind(notification_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// 寄送失敗：retry_at 為 None 時不再重試
+pub async fn mark_email_failed(
+    pool: &Pool<Postgres>,
+    notification_id: i32,
+    error: &str,
+    retry_at: Option<chrono::DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE notifications
+        SET email_status = CASE WHEN $1::TIMESTAMPTZ IS NULL THEN 'failed' ELSE 'pending' END,
+            email_next_attempt_at = $1,
+            email_last_error = $2
+        WHERE id = $3
+        "#
+    )
+    .bind(retry_at)
+    .bind(error)
+    .bind(notification_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use crate::config::MatcherLimits;
+use crate::db;
+use crate::match_cache::MatchCache;
+use crate::model::{MatchCycle, MatchResult, NewNotification, Teacher, NOTIFICATION_MATCH_FOUND};
+use circlematch_matcher::matcher::PartitionKey;
+use circlematch_matcher::report;
+use circlematch_matcher::subject::SubjectCompatibility;
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+// 配對相關事件，由教師資料的新增、修改、刪除觸發
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MatchEvent {
+    // 教師登記有變動，需要重新配對所在的分區
+    TeachersChanged { partitions: Vec<PartitionKey> },
+    // 找到新循環，google_ids 為參與者的帳號
+    CycleFound { cycle: MatchCycle, google_ids: Vec<String> },
+    // 循環因參與者修改或刪除登記而失效
+    CycleBroken { cycle: MatchCycle, google_ids: Vec<String> },
+}
+
+impl MatchEvent {
+    // 由變動前後的教師資料建立事件，修改時兩者所在的分區都需要重算
+    pub fn teachers_changed<'a>(teachers: impl IntoIterator<Item = &'a Teacher>) -> Self {
+        let mut partitions: Vec<PartitionKey> = teachers.into_iter()
+            .map(|teacher| PartitionKey::of(teacher, SubjectCompatibility::bundled()))
+            .collect();
+        partitions.sort();
+        partitions.dedup();
+        MatchEvent::TeachersChanged { partitions }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MatchEvent::TeachersChanged { .. } => "teachers_changed",
+            MatchEvent::CycleFound { .. } => "cycle_found",
+            MatchEvent::CycleBroken { .. } => "cycle_broken",
+        }
+    }
+
+    // 事件是否與指定帳號的登記有關
+    pub fn involves(&self, google_id: &str) -> bool {
+        match self {
+            MatchEvent::TeachersChanged { .. } => false,
+            MatchEvent::CycleFound { google_ids, .. } | MatchEvent::CycleBroken { google_ids, .. } => {
+                google_ids.iter().any(|id| id == google_id)
+            }
+        }
+    }
+}
+
+// 事件匯流排，放在 AppState 中，每個狀態各自一份；訂閱者跟不上時會遺失較舊的事件
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<MatchEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus { sender: broadcast::channel(256).0 }
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, event: MatchEvent) {
+        // 沒有訂閱者時 send 會失敗，不需處理
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MatchEvent> {
+        self.sender.subscribe()
+    }
+}
+
+// 保存配對結果中的循環，對新出現的循環寫入通知並發布事件
+pub async fn record_matches(pool: &Pool<Postgres>, events: &EventBus, matches: &[MatchResult]) {
+    let new_cycles = match db::save_found_cycles(pool, matches).await {
+        Ok(cycles) => cycles,
+        Err(e) => {
+            tracing::error!("保存配對循環失敗: {}", e);
+            return;
+        }
+    };
+
+    for cycle in new_cycles {
+        let message = format!("系統找到包含您的 {} 人調動循環，請登入查看並確認是否接受。", cycle.teacher_ids.len());
+        let notifications: Vec<NewNotification> = cycle.teacher_ids
+            .iter()
+            .map(|teacher_id| NewNotification {
+                teacher_id: *teacher_id,
+                kind: NOTIFICATION_MATCH_FOUND,
+                message: message.clone(),
+            })
+            .collect();
+
+        if let Err(e) = db::add_cycle_notifications(pool, cycle.id, &notifications).await {
+            tracing::error!("寫入循環 {} 的通知失敗: {}", cycle.cycle_key, e);
+        }
+
+        let google_ids = matches.iter()
+            .find(|result| report::cycle_key(result) == cycle.cycle_key)
+            .map(|result| result.teachers.iter().filter_map(|t| t.google_id.clone()).collect())
+            .unwrap_or_default();
+        events.publish(MatchEvent::CycleFound { cycle, google_ids });
+    }
+}
+
+// 背景工作：教師登記變動後重新計算所在的分區，讓新循環不必等到有人查詢配對結果才出現
+pub fn spawn_rematch_worker(pool: Pool<Postgres>, cache: Arc<MatchCache>, limits: MatcherLimits) {
+    let mut receiver = cache.events().subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(MatchEvent::TeachersChanged { .. }) => {}
+                Ok(_) => continue,
+                // 變動的分區已在發布時標記，略過事件不會遺漏重算
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+
+            // 合併短時間內的連續變動（例如批次匯入）
+            tokio::time::sleep(limits.rematch_debounce()).await;
+            while receiver.try_recv().is_ok() {}
+
+            if let Err(e) = cache.refresh_dirty(&pool, limits.max_cycle_size).await {
+                tracing::error!("重新配對失敗: {}", e);
+            }
+        }
+    });
+}
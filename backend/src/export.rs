@@ -0,0 +1,113 @@
+use axum::body::Body;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use rust_xlsxwriter::Workbook;
+use serde::{Deserialize, Serialize};
+
+// 試算表匯出格式
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+// 加上 BOM，Excel 才會以 UTF-8 開啟中文內容
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+pub fn to_csv<T: Serialize + Default>(rows: &[T]) -> Result<Vec<u8>, String> {
+    let mut writer = csv::Writer::from_writer(UTF8_BOM.to_vec());
+
+    if rows.is_empty() {
+        // 沒有資料時仍輸出標題列：以預設值產生一列後只保留標題
+        writer.serialize(T::default()).map_err(|e| format!("無法輸出 CSV: {}", e))?;
+        let mut bytes = writer.into_inner().map_err(|e| format!("無法輸出 CSV: {}", e))?;
+        let header_end = bytes.iter().position(|&b| b == b'\n').map_or(bytes.len(), |i| i + 1);
+        bytes.truncate(header_end);
+        return Ok(bytes);
+    }
+
+    for row in rows {
+        writer.serialize(row).map_err(|e| format!("無法輸出 CSV: {}", e))?;
+    }
+    writer.into_inner().map_err(|e| format!("無法輸出 CSV: {}", e))
+}
+
+pub fn to_xlsx<T: Serialize + Default>(rows: &[T], sheet_name: &str) -> Result<Vec<u8>, String> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet_name).map_err(|e| format!("無法輸出 XLSX: {}", e))?;
+    worksheet.serialize_headers(0, 0, &T::default()).map_err(|e| format!("無法輸出 XLSX: {}", e))?;
+
+    for row in rows {
+        worksheet.serialize(row).map_err(|e| format!("無法輸出 XLSX: {}", e))?;
+    }
+    worksheet.autofit();
+
+    workbook.save_to_buffer().map_err(|e| format!("無法輸出 XLSX: {}", e))
+}
+
+// CSV 逐列串流輸出：先送出 BOM 與標題，之後每筆資料序列化後立即送出，不先組成整個檔案。
+// 開始傳送後才發生的序列化錯誤無法改變狀態碼，只會中斷連線
+fn csv_stream<T, I>(rows: I) -> Body
+where
+    T: Serialize + Default + Send + 'static,
+    I: Iterator<Item = T> + Send + 'static,
+{
+    let mut rows = rows.peekable();
+    if rows.peek().is_none() {
+        let header = to_csv::<T>(&[]).map_err(std::io::Error::other);
+        return Body::from_stream(tokio_stream::iter([header]));
+    }
+
+    // 第一筆會連同 BOM 與標題列一起送出，之後每筆只輸出資料列
+    let mut first = true;
+    let chunks = rows.map(move |row| {
+        let prefix = if first { UTF8_BOM.to_vec() } else { Vec::new() };
+        let mut writer = csv::WriterBuilder::new().has_headers(first).from_writer(prefix);
+        first = false;
+        writer.serialize(row)?;
+        writer.into_inner().map_err(|e| std::io::Error::other(e.to_string()))
+    });
+    Body::from_stream(tokio_stream::iter(chunks))
+}
+
+// 依格式產生檔案並以附件形式下載。CSV 以串流逐列輸出；
+// XLSX 是壓縮的 ZIP 格式，rust_xlsxwriter 只能在記憶體中產生完整檔案後再送出
+pub fn spreadsheet_response<T, I>(rows: I, format: ExportFormat, file_stem: &str) -> Result<Response, String>
+where
+    T: Serialize + Default + Send + 'static,
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send + 'static,
+{
+    let body = match format {
+        ExportFormat::Csv => csv_stream(rows.into_iter()),
+        ExportFormat::Xlsx => Body::from(to_xlsx(&rows.into_iter().collect::<Vec<T>>(), file_stem)?),
+    };
+    let disposition = format!("attachment; filename=\"{}.{}\"", file_stem, format.extension());
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ).into_response())
+}
@@ -0,0 +1,57 @@
+use axum::async_trait;
+use sqlx::{Pool, Postgres};
+
+// 連線池目前的連線數
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+}
+
+// /readyz 與 /metrics 透過此介面檢查資料庫，測試時改用 NoDatabase
+#[async_trait]
+pub trait DatabaseHealth: Send + Sync {
+    async fn ping(&self) -> Result<(), sqlx::Error>;
+    // 沒有連線池時返回 None，指標中不輸出連線數
+    fn pool_stats(&self) -> Option<PoolStats>;
+}
+
+pub struct PgDatabaseHealth {
+    pool: Pool<Postgres>,
+}
+
+impl PgDatabaseHealth {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        PgDatabaseHealth { pool }
+    }
+}
+
+#[async_trait]
+impl DatabaseHealth for PgDatabaseHealth {
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await.map(|_| ())
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max_connections: self.pool.options().get_max_connections(),
+        })
+    }
+}
+
+// 資料全部放在記憶體中時使用，永遠視為可連線
+pub struct NoDatabase;
+
+#[async_trait]
+impl DatabaseHealth for NoDatabase {
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+}
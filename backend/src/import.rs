@@ -0,0 +1,167 @@
+use crate::model::Teacher;
+use crate::repository::TeacherRepository;
+use crate::validation;
+use circlematch_matcher::roster;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+// 名冊中單一列的檢查結果
+#[derive(Serialize, Debug)]
+pub struct ImportRowReport {
+    // 在 CSV 檔案中的列號，標題為第 1 列
+    pub line: usize,
+    pub display_id: Option<String>,
+    pub email: Option<String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub error_rows: usize,
+    // 實際寫入資料庫的筆數，試算或有錯誤時為 0
+    pub imported: usize,
+    pub rows: Vec<ImportRowReport>,
+    // 寫入的登記，呼叫端據此通知配對服務
+    #[serde(skip)]
+    pub created: Vec<Teacher>,
+}
+
+impl ImportReport {
+    pub fn has_errors(&self) -> bool {
+        self.error_rows > 0
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    // 名冊本身無法解析（例如標題列錯誤）
+    InvalidRoster(String),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::InvalidRoster(e) => write!(f, "{}", e),
+            ImportError::Database(e) => write!(f, "資料庫錯誤: {}", e),
+        }
+    }
+}
+
+// 批次匯入在異動紀錄中的操作者
+const IMPORT_ACTOR: &str = "import";
+
+// 用來找出重複登記的欄位組合
+fn registration_key(teacher: &Teacher) -> (String, i32, String, String) {
+    (
+        teacher.google_id.clone().unwrap_or_default(),
+        teacher.year,
+        teacher.school_level.clone(),
+        teacher.subject.trim().to_string(),
+    )
+}
+
+// 匯入 CSV 名冊：逐列執行與 POST /api/teachers 相同的檢查，
+// 全部通過且非試算時才在單一交易中寫入
+pub async fn import_roster(
+    teachers: &dyn TeacherRepository,
+    csv: &str,
+    dry_run: bool,
+) -> Result<ImportReport, ImportError> {
+    let records = roster::parse_csv(csv.as_bytes()).map_err(ImportError::InvalidRoster)?;
+
+    // 被隱藏的登記仍然存在，重複匯入時同樣視為重複
+    let existing: HashSet<_> = teachers.list_filtered(None, None, true)
+        .await
+        .map_err(ImportError::Database)?
+        .iter()
+        .map(registration_key)
+        .collect();
+
+    // 管理者可以在輪次開放前預先匯入，未開放時只提出警告
+    let rounds: HashMap<i32, _> = teachers.rounds()
+        .await
+        .map_err(ImportError::Database)?
+        .into_iter()
+        .map(|round| (round.year, round))
+        .collect();
+
+    let mut seen_in_file = HashSet::new();
+    let mut rows = Vec::with_capacity(records.len());
+    let mut valid_teachers = Vec::new();
+
+    for record in records {
+        let mut report = ImportRowReport {
+            line: record.line,
+            display_id: None,
+            email: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        let mut teacher = match record.teacher {
+            Ok(teacher) => teacher,
+            Err(e) => {
+                report.errors.push(e);
+                rows.push(report);
+                continue;
+            }
+        };
+
+        // 名冊通常沒有 google_id，而登入時以 email 作為 google_id
+        if teacher.google_id.is_none() && !teacher.email.is_empty() {
+            teacher.google_id = Some(teacher.email.clone());
+            report.warnings.push("未提供 google_id，以 email 代替".to_string());
+        }
+
+        match validation::prepare_new_teacher(&mut teacher) {
+            Ok(()) => {
+                report.warnings.extend(validation::teacher_warnings(&teacher));
+                if let Err(e) = validation::ensure_round_open(rounds.get(&teacher.year), teacher.year) {
+                    report.warnings.push(e);
+                }
+
+                let key = registration_key(&teacher);
+                if existing.contains(&key) {
+                    report.warnings.push("資料庫中已有相同帳號、年度與科目的登記".to_string());
+                }
+                if !seen_in_file.insert(key) {
+                    report.warnings.push("名冊中有相同帳號、年度與科目的重複列".to_string());
+                }
+            }
+            Err(e) => report.errors.push(e),
+        }
+
+        report.display_id = teacher.display_id.clone();
+        report.email = Some(teacher.email.clone()).filter(|email| !email.is_empty());
+        if report.errors.is_empty() {
+            valid_teachers.push(teacher);
+        }
+        rows.push(report);
+    }
+
+    let mut report = ImportReport {
+        dry_run,
+        total_rows: rows.len(),
+        valid_rows: valid_teachers.len(),
+        error_rows: rows.iter().filter(|row| !row.errors.is_empty()).count(),
+        imported: 0,
+        rows,
+        created: Vec::new(),
+    };
+
+    if !dry_run && !report.has_errors() && !valid_teachers.is_empty() {
+        let created = teachers.create_many(valid_teachers, IMPORT_ACTOR)
+            .await
+            .map_err(ImportError::Database)?;
+        report.imported = created.len();
+        report.created = created;
+        tracing::info!("成功匯入 {} 位教師", report.imported);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,76 @@
+use crate::db;
+use crate::events::{EventBus, MatchEvent};
+use crate::model::{MatchCycle, NewNotification, Teacher, NOTIFICATION_MATCH_BROKEN};
+use circlematch_matcher::matcher;
+use circlematch_matcher::subject::SubjectCompatibility;
+use sqlx::{Pool, Postgres};
+
+fn teacher_label(teacher: &Teacher) -> String {
+    teacher.display_id.clone().unwrap_or_else(|| format!("#{}", teacher.id.unwrap_or(0)))
+}
+
+// 教師修改、刪除或被隱藏登記後，檢查包含該教師的已保存循環是否仍然成立，
+// 不成立的循環標記為 broken，並通知循環中的其他參與者
+pub async fn invalidate_cycles(pool: &Pool<Postgres>, events: &EventBus, changed: &Teacher) -> Result<Vec<MatchCycle>, sqlx::Error> {
+    let Some(changed_id) = changed.id else {
+        return Ok(Vec::new());
+    };
+
+    let mut broken = Vec::new();
+    for cycle in db::get_active_cycles_with_teacher(pool, changed_id).await? {
+        let participants = db::get_teachers_by_ids(pool, &cycle.teacher_ids).await?;
+
+        // 依循環順序排列，已刪除的參與者會找不到
+        let ordered: Option<Vec<Teacher>> = cycle.teacher_ids
+            .iter()
+            .map(|id| participants.iter().find(|t| t.id == Some(*id)).cloned())
+            .collect();
+
+        let reason = match &ordered {
+            None if participants.iter().all(|t| t.id != Some(changed_id)) => {
+                if changed.hidden {
+                    format!("{} 的登記已被管理者隱藏", teacher_label(changed))
+                } else {
+                    format!("{} 已刪除登記", teacher_label(changed))
+                }
+            }
+            None => "有參與者已刪除或隱藏登記".to_string(),
+            Some(ordered) => match matcher::cycle_break_reason(ordered, SubjectCompatibility::bundled()) {
+                Some(reason) => reason,
+                None => continue,
+            },
+        };
+
+        let message = format!("您參與的 {} 人調動循環已失效：{}", cycle.teacher_ids.len(), reason);
+        let notifications: Vec<NewNotification> = participants
+            .iter()
+            .filter(|t| t.id != Some(changed_id))
+            .filter_map(|t| Some(NewNotification {
+                teacher_id: t.id?,
+                kind: NOTIFICATION_MATCH_BROKEN,
+                message: message.clone(),
+            }))
+            .collect();
+
+        if let Some(cycle) = db::mark_cycle_broken(pool, cycle.id, &reason, &notifications).await? {
+            tracing::info!("循環 {} 已失效: {}", cycle.cycle_key, reason);
+
+            let google_ids = participants.iter()
+                .filter(|t| t.id != Some(changed_id))
+                .chain(std::iter::once(changed))
+                .filter_map(|t| t.google_id.clone())
+                .collect();
+            events.publish(MatchEvent::CycleBroken { cycle: cycle.clone(), google_ids });
+            broken.push(cycle);
+        }
+    }
+
+    Ok(broken)
+}
+
+// 登記已寫入後才檢查循環，失敗時只記錄錯誤，不影響原本的請求
+pub async fn invalidate_cycles_logged(pool: &Pool<Postgres>, events: &EventBus, changed: &Teacher) {
+    if let Err(e) = invalidate_cycles(pool, events, changed).await {
+        tracing::error!("檢查教師 {:?} 的配對循環失敗: {}", changed.id, e);
+    }
+}
@@ -0,0 +1,18 @@
+// 伺服器的所有模組；main.rs 只負責讀取設定、連線資料庫與啟動，整合測試直接使用 routes::router
+pub mod model;
+pub mod db;
+pub mod validation;
+pub mod import;
+pub mod export;
+pub mod invalidation;
+pub mod mailer;
+pub mod auth;
+pub mod events;
+pub mod match_cache;
+pub mod metrics;
+pub mod health;
+pub mod config;
+pub mod state;
+pub mod repository;
+pub mod match_service;
+pub mod routes;
@@ -0,0 +1,207 @@
+use crate::config::MailConfig;
+use crate::db;
+use crate::model::{NOTIFICATION_MATCH_ACCEPTED, NOTIFICATION_MATCH_BROKEN, NOTIFICATION_MATCH_FOUND};
+use chrono::{DateTime, Utc};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use std::str::FromStr;
+use std::time::Duration;
+
+// 每輪從佇列取出的信件數
+const BATCH_SIZE: i64 = 20;
+// 超過此次數仍失敗就不再重試
+const MAX_ATTEMPTS: i32 = 6;
+// 取出後若工作中斷，這段時間後會被重新寄送
+const CLAIM_LEASE_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    // 不加密，適用於 MailHog 等本機測試伺服器
+    None,
+    StartTls,
+    Tls,
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(SmtpSecurity::None),
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" => Ok(SmtpSecurity::Tls),
+            other => Err(format!("未知的 SMTP 加密方式: {}（可用 none/starttls/tls）", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    // 信件中的網站連結
+    pub app_url: Option<String>,
+    pub poll_interval: Duration,
+}
+
+impl SmtpSettings {
+    // 未設定 SMTP 主機時不寄送 Email，通知仍保留在佇列中；設定已在 Config 載入時驗證
+    pub fn from_config(config: &MailConfig) -> Option<Self> {
+        let host = config.smtp_host.clone()?;
+        let default_port = match config.smtp_security {
+            SmtpSecurity::None => 1025,
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+        };
+
+        Some(SmtpSettings {
+            host,
+            port: config.smtp_port.unwrap_or(default_port),
+            security: config.smtp_security,
+            username: config.smtp_username.clone(),
+            password: config.smtp_password.clone(),
+            from: config.from.clone(),
+            app_url: config.app_url.clone(),
+            poll_interval: Duration::from_secs(config.poll_seconds),
+        })
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+        let mut builder = match self.security {
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+                .map_err(|e| format!("SMTP 設定錯誤: {}", e))?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)
+                .map_err(|e| format!("SMTP 設定錯誤: {}", e))?,
+        };
+        builder = builder.port(self.port);
+
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(builder.build())
+    }
+}
+
+// 依通知種類產生信件主旨與內文
+pub fn render_email(kind: &str, name: &str, message: &str, app_url: Option<&str>) -> (String, String) {
+    let subject = match kind {
+        NOTIFICATION_MATCH_FOUND => "【CircleMatch】找到新的調動循環",
+        NOTIFICATION_MATCH_ACCEPTED => "【CircleMatch】調動循環已獲全員確認",
+        NOTIFICATION_MATCH_BROKEN => "【CircleMatch】您的調動循環已失效",
+        _ => "【CircleMatch】通知",
+    };
+
+    let next_step = match kind {
+        NOTIFICATION_MATCH_FOUND => "請登入網站查看循環內容，確認後按下「接受」。所有參與者都確認後會再通知您。",
+        NOTIFICATION_MATCH_ACCEPTED => "請依各縣市教育局的規定辦理後續互調作業。",
+        NOTIFICATION_MATCH_BROKEN => "系統會持續為您尋找新的配對，您也可以登入網站調整調動志願。",
+        _ => "",
+    };
+
+    let link = app_url
+        .map(|url| format!("\n網站連結：{}\n", url))
+        .unwrap_or_default();
+
+    let body = format!(
+        "{} 老師您好：\n\n{}\n\n{}\n{}\n此信件由系統自動寄出，請勿直接回覆。\n如不想再收到 Email 通知，可在網站的通知設定中關閉。\n",
+        name, message, next_step, link
+    );
+
+    (subject.to_string(), body)
+}
+
+// 第 n 次失敗後等待的時間：1、2、4、8... 分鐘
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    chrono::Duration::minutes(1i64 << attempts.clamp(1, 10).saturating_sub(1))
+}
+
+// 第 attempts 次寄送失敗後的下次重試時間，達到 MAX_ATTEMPTS 次後返回 None（不再重試）
+pub fn next_attempt_at(attempts: i32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    (attempts < MAX_ATTEMPTS).then(|| now + retry_delay(attempts))
+}
+
+// 背景工作：定期從 notifications 佇列取出待寄信件並寄送，失敗時依次數延後重試
+pub fn spawn_email_worker(pool: Pool<Postgres>, settings: SmtpSettings) {
+    tokio::spawn(async move {
+        let transport = match settings.transport() {
+            Ok(transport) => transport,
+            Err(e) => {
+                tracing::error!("無法啟動 Email 寄送: {}", e);
+                return;
+            }
+        };
+        let from: Mailbox = match settings.from.parse() {
+            Ok(from) => from,
+            Err(e) => {
+                tracing::error!("SMTP_FROM 格式錯誤 {}: {}", settings.from, e);
+                return;
+            }
+        };
+
+        tracing::info!("Email 寄送已啟動: {}:{}", settings.host, settings.port);
+        loop {
+            if let Err(e) = send_pending(&pool, &transport, &from, &settings).await {
+                tracing::error!("讀取 Email 佇列失敗: {}", e);
+            }
+            tokio::time::sleep(settings.poll_interval).await;
+        }
+    });
+}
+
+async fn send_pending(
+    pool: &Pool<Postgres>,
+    transport: &AsyncSmtpTransport<Tokio1Executor>,
+    from: &Mailbox,
+    settings: &SmtpSettings,
+) -> Result<(), sqlx::Error> {
+    let emails = db::claim_pending_emails(pool, BATCH_SIZE, chrono::Duration::minutes(CLAIM_LEASE_MINUTES)).await?;
+
+    for email in emails {
+        let (subject, body) = render_email(&email.kind, &email.name, &email.message, settings.app_url.as_deref());
+        let result = match email.email.parse::<Mailbox>() {
+            Ok(to) => {
+                let message = Message::builder()
+                    .from(from.clone())
+                    .to(to)
+                    .subject(subject)
+                    .header(ContentType::TEXT_PLAIN)
+                    .body(body)
+                    .map_err(|e| e.to_string());
+                match message {
+                    Ok(message) => transport.send(message).await.map(|_| ()).map_err(|e| e.to_string()),
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(format!("收件地址錯誤 {}: {}", email.email, e)),
+        };
+
+        match result {
+            Ok(()) => {
+                tracing::info!("已寄出通知 {} 至 {}", email.notification_id, email.email);
+                db::mark_email_sent(pool, email.notification_id).await?;
+            }
+            Err(e) => {
+                let retry_at = next_attempt_at(email.attempts, Utc::now());
+                tracing::warn!(
+                    "通知 {} 寄送失敗（第 {} 次）: {}{}",
+                    email.notification_id,
+                    email.attempts,
+                    e,
+                    if retry_at.is_none() { "，不再重試" } else { "" }
+                );
+                db::mark_email_failed(pool, email.notification_id, &e, retry_at).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,354 +1,117 @@
-mod model;
-mod db;
-mod matcher;
-
-use axum::{
-    routing::{get, post, put, delete},
-    Router, extract::State,
-    http::Method, extract::Json,
-    response::IntoResponse,
-    extract::Path,
-};
-use serde::Serialize;
-use serde::Deserialize;
-use reqwest::Client;
-use serde_json::Value;
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row};
-use tower_http::cors::{CorsLayer, Any};
-use std::net::SocketAddr;
-use model::{Teacher, MatchResult};
-use axum::extract::Query;
-use std::collections::HashMap;
+use circlematch_api::config::Config;
+use circlematch_api::match_cache::MatchCache;
+use circlematch_api::repository::PgTeacherRepository;
+use circlematch_api::state::AppState;
+use circlematch_api::{db, events, import, mailer, routes};
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use std::sync::Arc;
+
+async fn connect_pool(config: &Config) -> Pool<Postgres> {
+    PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .acquire_timeout(config.acquire_timeout())
+        .connect(config.database_url())
+        .await
+        .expect("Failed to connect to database")
+}
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
 
-    let database_url = std::env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "postgres://app:password@db:5432/circlematch".to_string());
-
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .expect("Failed to connect to database");
-
-    // 初始化資料庫
-    db::init_db(&pool).await.expect("Failed to initialize database");
-
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers(Any);
-
-    let app = Router::new()
-        .route("/", get(root))
-        .route("/api/hello", get(hello))
-        .route("/api/teachers", get(get_teachers))
-        .route("/api/teachers", post(create_teacher))
-        .route("/api/matches", get(find_matches))
-        .route("/api/google-login", post(google_login))
-        .route("/api/districts", get(get_districts))
-        .route("/api/subjects", get(get_subjects))
-        .route("/api/teachers/:id", put(update_teacher_handler))
-        .route("/api/teachers/:id", delete(delete_teacher_handler))
-        .with_state(pool)
-        .layer(cors);
-
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8000));
-    tracing::info!("Server listening on {}", addr);
-    
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-}
-
-// 返回縣市區域資料
-async fn get_districts() -> impl IntoResponse {
-    let districts = db::get_taiwan_districts();
-    Json(districts)
-}
-
-async fn root() -> &'static str {
-    "CircleMatch API is running"
-}
-
-async fn hello(State(pool): State<sqlx::PgPool>) -> Json<Message> {
-    let row = sqlx::query("SELECT text FROM messages LIMIT 1")
-        .fetch_optional(&pool)
-        .await
-        .unwrap_or(None);
-    
-    let text = match row {
-        Some(row) => row.get::<String, _>(0),
-        None => "Default message".to_string()
+    // 設定錯誤時列出所有問題後結束，不啟動伺服器
+    let config = match Config::load() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
     };
-    
-    Json(Message { text })
-}
 
-#[derive(Serialize)]
-struct Message {
-    text: String,
-}
-
-async fn get_teachers(
-    State(pool): State<Pool<Postgres>>,
-    Query(params): Query<HashMap<String, String>>,
-) -> Json<Vec<Teacher>> {
-    // Log the parameters for debugging
-    tracing::info!("Get teachers params: {:?}", params);
-    
-    // Check if we have google_id parameter
-    if let Some(google_id) = params.get("google_id") {
-        // Try to get all teachers for this Google ID
-        match db::get_teachers_by_google_id(&pool, google_id).await {
-            Ok(teachers) => {
-                tracing::info!("Found {} teachers for Google ID {}", teachers.len(), google_id);
-                return Json(teachers);
-            }
+    // 設定的學校名錄無法使用時直接結束，不要在執行中才發現代碼無法核對
+    if let Some(path) = &config.data.school_directory_file {
+        match db::load_school_directory(path) {
+            Ok(count) => tracing::info!("已載入學校名錄 {}（{} 所學校）", path.display(), count),
             Err(e) => {
-                tracing::error!("Error getting teachers by Google ID: {}", e);
-                return Json(vec![]);
+                eprintln!("{}", e);
+                std::process::exit(2);
             }
         }
     }
 
-    // No google_id parameter - get all teachers
-    let teachers = db::get_all_teachers(&pool)
-        .await
-        .unwrap_or_default();
-    
-    Json(teachers)
-}
-
-async fn create_teacher(
-    State(pool): State<Pool<Postgres>>,
-    Json(mut teacher): Json<Teacher>
-) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
-    tracing::info!("接收到的教師數據: {:?}", teacher);
-
-    // 檢查必填欄位
-    if teacher.current_county.trim().is_empty() {
-        return Err((
-            axum::http::StatusCode::BAD_REQUEST,
-            "縣市不能為空".to_string(),
-        ));
-    }
-    
-    if teacher.current_district.trim().is_empty() {
-        return Err((
-            axum::http::StatusCode::BAD_REQUEST,
-            "區域不能為空".to_string(),
-        ));
+    // 子命令：circlematch-api import <roster.csv> [--dry-run]
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("import") {
+        std::process::exit(run_import_command(&config, &args[1..]).await);
     }
 
-    // 如果名稱為空，設置為預設值
-    if teacher.name.is_none() || teacher.name.as_ref().unwrap().is_empty() {
-        teacher.name = Some("Anonymous".to_string());
+    if config.auth.google_client_id.is_none() {
+        tracing::warn!("未設定 GOOGLE_CLIENT_ID，Google 登入與需要登入的功能將無法使用");
     }
 
-    // 如果 display_id 為空，生成一個新的 display_id
-    if teacher.display_id.is_none() || teacher.display_id.as_ref().unwrap().is_empty() {
-        teacher.display_id = Some(db::generate_display_id(&teacher.current_county, &teacher.current_district));
-    }
+    let pool = connect_pool(&config).await;
 
-    // 確保 google_id 被正確處理
-    match &teacher.google_id {
-        Some(google_id) if !google_id.is_empty() => {
-            tracing::info!("Google ID 已提供: {}", google_id);
-        }
-        _ => {
-            return Err((
-                axum::http::StatusCode::BAD_REQUEST,
-                "缺少 google_id，請重新登入".to_string(),
-            ));
-        }
-    }
+    // 初始化資料庫
+    db::init_db(&pool).await.expect("Failed to initialize database");
 
-    // 將教師數據寫入資料庫
-    match db::create_teacher(&pool, teacher).await {
-        Ok(created) => {
-            tracing::info!("成功創建教師: {:?}", created);
-            Ok(Json(created))
-        },
-        Err(e) => {
-            let error_msg = format!("創建教師失敗: {}", e);
-            tracing::error!("{}", error_msg);
-            Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
-        }
+    // 設定 SMTP_HOST 後才寄送 Email 通知
+    match mailer::SmtpSettings::from_config(&config.mail) {
+        Some(settings) => mailer::spawn_email_worker(pool.clone(), settings),
+        None => tracing::info!("未設定 SMTP_HOST，Email 通知不會寄出"),
     }
-}
+    let cache = Arc::new(MatchCache::default());
+    events::spawn_rematch_worker(pool.clone(), cache.clone(), config.matcher.clone());
 
-async fn find_matches(
-    State(pool): State<Pool<Postgres>>,
-    Query(_params): Query<HashMap<String, String>>,
-) -> Json<Vec<MatchResult>> {
-    tracing::info!("收到配對結果請求");
+    let app = routes::router(AppState::postgres(pool, config.clone(), cache));
 
-    let all_teachers = db::get_all_teachers(&pool).await.unwrap_or_default();
-
-    tracing::info!("教師數量: {}", all_teachers.len());
-
-    // 處理所有教師的配對邏輯
-    let matches = matcher::find_matches(all_teachers);
-
-    tracing::info!("配對結果數量: {}", matches.len());
-
-    Json(matches)
-}
-
-#[derive(Deserialize)]
-struct GoogleLoginRequest {
-    token: String,
+    let addr = config.server.bind_addr;
+    tracing::info!("Server listening on {}", addr);
+    
+    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
 }
 
-async fn google_login(
-    State(pool): State<Pool<Postgres>>,
-    Json(payload): Json<GoogleLoginRequest>,
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
-    let client_id = std::env::var("GOOGLE_CLIENT_ID").expect("GOOGLE_CLIENT_ID must be set");
-    let client_secret = std::env::var("GOOGLE_CLIENT_SECRET").expect("GOOGLE_CLIENT_SECRET must be set");
-
-    // 驗證 Google Token
-    match verify_google_token(&payload.token, &client_id, &client_secret).await {
-        Ok(user_info) => {
-            tracing::info!("Google 登入成功: {:?}", user_info);
+// 從命令列匯入 CSV 名冊，返回程式結束代碼
+async fn run_import_command(config: &Config, args: &[String]) -> i32 {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        eprintln!("用法: circlematch-api import <roster.csv> [--dry-run]");
+        return 2;
+    };
 
-            // 在驗證成功後，獲取與 google_id 關聯的所有教師資料
-            let google_id = &user_info.email;
-            let teachers = db::get_teachers_by_google_id(&pool, google_id).await.unwrap_or_default();
+    let csv = match std::fs::read_to_string(path) {
+        Ok(csv) => csv,
+        Err(e) => {
+            eprintln!("無法讀取 {}: {}", path, e);
+            return 1;
+        }
+    };
 
-            // 如果有教師資料，選擇第一筆作為主要資料（向後兼容）
-            let primary_teacher = teachers.first().cloned();
+    let teachers = PgTeacherRepository::new(connect_pool(config).await);
+    let report = match import::import_roster(&teachers, &csv, dry_run).await {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("匯入失敗: {}", e);
+            return 1;
+        }
+    };
 
-            Ok(Json(serde_json::json!({
-                "email": user_info.email,
-                "google_id": user_info.email,
-                "name": user_info.name,
-                "picture": user_info.picture,
-                "teacher": primary_teacher, // 向後兼容，只返回第一筆教師記錄
-                "teachers": teachers // 新增: 返回所有關聯的教師記錄
-            })))
+    for row in &report.rows {
+        for error in &row.errors {
+            println!("第 {} 列 錯誤: {}", row.line, error);
         }
-        Err(err) => {
-            tracing::error!("Google 登入失敗: {}", err);
-            Err((axum::http::StatusCode::UNAUTHORIZED, "驗證失敗".to_string()))
+        for warning in &row.warnings {
+            println!("第 {} 列 警告: {}", row.line, warning);
         }
     }
-}
-
-async fn verify_google_token(token: &str, client_id: &str, _client_secret: &str) -> Result<GoogleTokenInfo, String> {
-    let client = Client::new();
-    let google_api_url = format!(
-        "https://oauth2.googleapis.com/tokeninfo?id_token={}",
-        token
+    println!(
+        "共 {} 列，{} 列可匯入，{} 列有錯誤，已寫入 {} 筆{}",
+        report.total_rows,
+        report.valid_rows,
+        report.error_rows,
+        report.imported,
+        if report.dry_run { "（試算模式，未寫入資料庫）" } else { "" },
     );
 
-    let response = client.get(&google_api_url).send().await.map_err(|err| {
-        format!("無法連接到 Google API: {}", err)
-    })?;
-
-    if !response.status().is_success() {
-        return Err("Google Token 驗證失敗".to_string());
-    }
-
-    let token_info: Value = response.json().await.map_err(|err| {
-        format!("解析 Google 回應失敗: {}", err)
-    })?;
-
-    if token_info["aud"].as_str() != Some(client_id) {
-        return Err("Token 的 audience 不匹配".to_string());
-    }
-
-    Ok(GoogleTokenInfo {
-        email: token_info["email"].as_str().unwrap_or("未知使用者").to_string(),
-        name: token_info["name"].as_str().unwrap_or("未知使用者").to_string(),
-        picture: token_info["picture"].as_str().unwrap_or("").to_string(),
-    })
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct GoogleTokenInfo {
-    email: String,
-    name: String,
-    picture: String,
-}
-
-// 返回科目資料
-async fn get_subjects() -> impl IntoResponse {
-    let subjects = db::get_elementary_subjects();
-    Json(subjects)
+    if report.has_errors() { 1 } else { 0 }
 }
-
-async fn update_teacher_handler(
-    State(pool): State<Pool<Postgres>>,
-    Path(teacher_id): Path<i32>,
-    Json(teacher): Json<Teacher>,
-) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
-    tracing::info!("接收到的教師更新數據: {:?}", teacher);
-    
-    // 檢查必填欄位
-    if teacher.current_county.trim().is_empty() {
-        return Err((
-            axum::http::StatusCode::BAD_REQUEST,
-            "縣市不能為空".to_string(),
-        ));
-    }
-    
-    if teacher.current_district.trim().is_empty() {
-        return Err((
-            axum::http::StatusCode::BAD_REQUEST,
-            "區域不能為空".to_string(),
-        ));
-    }
-    
-    // 更新教師數據
-    match db::update_teacher(&pool, teacher_id, teacher).await {
-        Ok(updated) => {
-            tracing::info!("成功更新教師: {:?}", updated);
-            Ok(Json(updated))
-        },
-        Err(e) => {
-            let error_msg = format!("更新教師失敗: {}", e);
-            tracing::error!("{}", error_msg);
-            
-            // 區分不同類型的錯誤
-            match e {
-                sqlx::Error::RowNotFound => {
-                    Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))
-                },
-                _ => {
-                    Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
-                }
-            }
-        }
-    }
-}
-
-async fn delete_teacher_handler(
-    State(pool): State<Pool<Postgres>>,
-    Path(teacher_id): Path<i32>,
-) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
-    tracing::info!("請求刪除教師 ID: {}", teacher_id);
-    
-    match db::delete_teacher(&pool, teacher_id).await {
-        Ok(_) => {
-            tracing::info!("成功刪除教師 ID: {}", teacher_id);
-            Ok(axum::http::StatusCode::NO_CONTENT)
-        },
-        Err(e) => {
-            let error_msg = format!("刪除教師失敗: {}", e);
-            tracing::error!("{}", error_msg);
-            
-            // 區分不同類型的錯誤
-            match e {
-                sqlx::Error::RowNotFound => {
-                    Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))
-                },
-                _ => {
-                    Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
-                }
-            }
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,369 @@
+use crate::db;
+use crate::events::{self, EventBus};
+use crate::metrics;
+use crate::model::{MatchResult, Teacher};
+use circlematch_matcher::matcher::{self, MatcherConfig, PartitionKey};
+use circlematch_matcher::subject::SubjectCompatibility;
+use sqlx::{Pool, Postgres};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use tokio::sync::Notify;
+
+// 記憶體中的配對索引：各分區的循環只在第一次查詢該年度，或分區內的教師登記變動時才重新計算，
+// 查詢時只需合併快取的循環並依參數選取、排序
+#[derive(Default)]
+struct MatchIndex {
+    // 每次有分區重新計算就遞增，客戶端可據此判斷配對結果是否變動
+    generation: u64,
+    // 已載入的年度；all_years 表示已載入所有年度（尚未建立任何輪次時）
+    years: HashSet<i32>,
+    all_years: bool,
+    // 各分區去除重複後的循環，尚未選取或排序
+    partitions: HashMap<PartitionKey, CachedPartition>,
+    // 待重新計算的分區，登記變動時同步標記
+    dirty: HashSet<PartitionKey>,
+    // 每次取出待重算的分區就遞增；計算結果依此判斷先後，較舊的結果不會覆蓋較新的
+    epoch: u64,
+    // 計算中的工作，查詢需等待同一年度的計算完成才能拿到最新結果
+    in_flight: Vec<InFlight>,
+}
+
+struct CachedPartition {
+    epoch: u64,
+    results: Vec<MatchResult>,
+}
+
+struct InFlight {
+    epoch: u64,
+    // None 表示整批載入所有年度
+    year: Option<i32>,
+    loading: bool,
+}
+
+// 在鎖內決定的工作，之後在鎖外讀取資料、計算並保存循環
+enum RefreshPlan {
+    // 整個年度（None 為所有年度）重新載入
+    Load { year: Option<i32>, epoch: u64 },
+    // 只重新計算有變動的分區
+    Partitions { keys: HashSet<PartitionKey>, epoch: u64 },
+}
+
+impl RefreshPlan {
+    fn epoch(&self) -> u64 {
+        match self {
+            RefreshPlan::Load { epoch, .. } | RefreshPlan::Partitions { epoch, .. } => *epoch,
+        }
+    }
+}
+
+// 各分區的計算結果；分區已沒有登記時為空，仍保留計算的先後以免被較舊的結果取代
+type Computed = Vec<(PartitionKey, Vec<MatchResult>)>;
+
+// 配對快取：索引、待重算的分區與計算中的工作。由 CachedMatchService 與重新配對的背景工作共用，
+// 每個 AppState 各自擁有一份，不同資料庫的狀態不會互相影響。
+// 鎖只在取出待重算的分區與換上計算結果時短暫持有，讀取資料庫、列舉循環與保存循環都在鎖外進行
+#[derive(Default)]
+pub struct MatchCache {
+    index: Mutex<MatchIndex>,
+    // 計算完成（或失敗）時通知等待中的查詢
+    refreshed: Notify,
+    // 新循環的事件，AppState 與重新配對的背景工作共用同一個匯流排
+    events: EventBus,
+}
+
+pub struct CachedMatches {
+    pub generation: u64,
+    pub matches: Vec<MatchResult>,
+    // 循環選取超過搜尋節點上限時為 false，見 matcher::FinalizedMatches
+    pub exhaustive: bool,
+}
+
+// 查詢在鎖內決定的下一步
+enum Step {
+    Run(RefreshPlan),
+    Wait,
+    // 目前的版本與尚未選取、排序的循環
+    Done(u64, Vec<MatchResult>),
+}
+
+// 計算單一分區並去除重複排列，新出現的循環會保存並通知參與者
+async fn compute_partition(
+    pool: &Pool<Postgres>,
+    events: &EventBus,
+    teachers: &[Teacher],
+    max_cycle_size: usize,
+) -> Vec<MatchResult> {
+    let config = MatcherConfig { max_cycle_size, ..Default::default() };
+    let started = Instant::now();
+    let cycles = matcher::partition_cycles(teachers, SubjectCompatibility::bundled(), config.max_cycle_size);
+    let results = matcher::finalize_matches(cycles, &config);
+    metrics::observe_matcher_run(teachers.len(), started);
+
+    if !results.is_empty() {
+        events::record_matches(pool, events, &results).await;
+    }
+    results
+}
+
+// 在鎖外執行計畫：讀取登記、計算並保存各分區的循環
+async fn compute(
+    pool: &Pool<Postgres>,
+    events: &EventBus,
+    plan: &RefreshPlan,
+    max_cycle_size: usize,
+) -> Result<Computed, sqlx::Error> {
+    let mut computed = Vec::new();
+
+    match plan {
+        RefreshPlan::Load { year, .. } => {
+            let teachers = db::get_teachers_filtered(pool, *year, None).await?;
+            for (key, partition) in matcher::group_by_partition(&teachers, SubjectCompatibility::bundled()) {
+                let results = compute_partition(pool, events, &partition, max_cycle_size).await;
+                computed.push((key, results));
+            }
+        }
+        RefreshPlan::Partitions { keys, .. } => {
+            // 分區是以程式計算的科目群組區分，因此讀取整個年度的教師後再取出需要的分區；
+            // 讀取資料的成本遠低於在整個年度中列舉循環
+            let years: BTreeSet<i32> = keys.iter().map(|key| key.year).collect();
+            for dirty_year in years {
+                let teachers = db::get_teachers_filtered(pool, Some(dirty_year), None).await?;
+                let mut groups = matcher::group_by_partition(&teachers, SubjectCompatibility::bundled());
+
+                for key in keys.iter().filter(|key| key.year == dirty_year) {
+                    let results = match groups.remove(key) {
+                        Some(partition) => compute_partition(pool, events, &partition, max_cycle_size).await,
+                        None => Vec::new(),
+                    };
+                    computed.push((key.clone(), results));
+                }
+            }
+        }
+    }
+
+    Ok(computed)
+}
+
+impl MatchIndex {
+    fn update_cycle_metrics(&self) {
+        let mut counts = BTreeMap::new();
+        for partition in self.partitions.values() {
+            for result in &partition.results {
+                *counts.entry(result.teachers.len()).or_insert(0) += 1;
+            }
+        }
+        metrics::set_cycle_counts(&counts);
+    }
+
+    fn is_loaded(&self, year: Option<i32>) -> bool {
+        self.all_years || year.is_some_and(|year| self.years.contains(&year))
+    }
+
+    // 是否有會影響該年度結果的計算尚未完成
+    fn is_refreshing(&self, year: Option<i32>) -> bool {
+        self.in_flight.iter().any(|job| match (year, job.year) {
+            (Some(year), Some(job_year)) => year == job_year,
+            _ => true,
+        })
+    }
+
+    // 取出符合年度條件的待重算分區
+    fn take_dirty(&mut self, year: Option<i32>) -> HashSet<PartitionKey> {
+        let (taken, rest) = self.dirty.drain().partition(|key| year.is_none_or(|year| key.year == year));
+        self.dirty = rest;
+        taken
+    }
+
+    // 決定下一個要執行的計算；沒有需要計算的分區，或同一年度已在載入中時返回 None
+    fn plan(&mut self, year: Option<i32>) -> Option<RefreshPlan> {
+        if !self.is_loaded(year) {
+            if self.in_flight.iter().any(|job| job.loading && (job.year.is_none() || job.year == year)) {
+                return None;
+            }
+            // 整個年度重新載入，之前標記的變動也一併涵蓋
+            self.take_dirty(year);
+            self.epoch += 1;
+            self.in_flight.push(InFlight { epoch: self.epoch, year, loading: true });
+            return Some(RefreshPlan::Load { year, epoch: self.epoch });
+        }
+
+        let keys = self.take_dirty(year);
+        if keys.is_empty() {
+            return None;
+        }
+        self.epoch += 1;
+        let years: BTreeSet<i32> = keys.iter().map(|key| key.year).collect();
+        for dirty_year in years {
+            self.in_flight.push(InFlight { epoch: self.epoch, year: Some(dirty_year), loading: false });
+        }
+        Some(RefreshPlan::Partitions { keys, epoch: self.epoch })
+    }
+
+    // 以較新的計算結果取代分區，之後完成的較舊計算不會蓋掉
+    fn replace(&mut self, key: PartitionKey, results: Vec<MatchResult>, epoch: u64) {
+        if self.partitions.get(&key).is_some_and(|cached| cached.epoch > epoch) {
+            return;
+        }
+        self.partitions.insert(key, CachedPartition { epoch, results });
+    }
+
+    // 換上計算結果並遞增版本
+    fn apply(&mut self, plan: RefreshPlan, computed: Computed) {
+        self.in_flight.retain(|job| job.epoch != plan.epoch());
+
+        match plan {
+            RefreshPlan::Load { year, epoch } => {
+                let loaded: HashSet<&PartitionKey> = computed.iter().map(|(key, _)| key).collect();
+                let removed: Vec<PartitionKey> = self.partitions
+                    .keys()
+                    .filter(|key| year.is_none_or(|year| key.year == year) && !loaded.contains(key))
+                    .cloned()
+                    .collect();
+                for key in removed {
+                    self.replace(key, Vec::new(), epoch);
+                }
+                for (key, results) in computed {
+                    self.replace(key, results, epoch);
+                }
+
+                match year {
+                    Some(year) => {
+                        self.years.insert(year);
+                    }
+                    None => self.all_years = true,
+                }
+                self.generation += 1;
+                tracing::info!("已載入 {:?} 年度的配對結果（第 {} 版）", year, self.generation);
+            }
+            RefreshPlan::Partitions { epoch, .. } => {
+                let count = computed.len();
+                for (key, results) in computed {
+                    self.replace(key, results, epoch);
+                }
+                self.generation += 1;
+                tracing::info!("已重新計算 {} 個配對分區（第 {} 版）", count, self.generation);
+            }
+        }
+
+        self.update_cycle_metrics();
+    }
+
+    // 計算失敗時放回取出的分區，下次查詢或背景工作會再重試
+    fn abandon(&mut self, plan: RefreshPlan) {
+        self.in_flight.retain(|job| job.epoch != plan.epoch());
+        if let RefreshPlan::Partitions { keys, .. } = plan {
+            self.dirty.extend(keys);
+        }
+    }
+
+    fn snapshot(&self, year: Option<i32>) -> (u64, Vec<MatchResult>) {
+        let results = self.partitions
+            .iter()
+            .filter(|(key, _)| year.is_none_or(|year| key.year == year))
+            .flat_map(|(_, partition)| partition.results.iter().cloned())
+            .collect();
+        (self.generation, results)
+    }
+}
+
+impl MatchCache {
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MatchIndex> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn mark_dirty(&self, keys: impl IntoIterator<Item = PartitionKey>) {
+        self.lock().dirty.extend(keys);
+    }
+
+    // 在鎖外執行計畫，完成後短暫取得鎖換上結果
+    async fn run(&self, pool: &Pool<Postgres>, plan: RefreshPlan, max_cycle_size: usize) -> Result<(), sqlx::Error> {
+        let result = compute(pool, &self.events, &plan, max_cycle_size).await;
+        let outcome = {
+            let mut index = self.lock();
+            match result {
+                Ok(computed) => {
+                    index.apply(plan, computed);
+                    Ok(())
+                }
+                Err(e) => {
+                    index.abandon(plan);
+                    Err(e)
+                }
+            }
+        };
+        self.refreshed.notify_waiters();
+        outcome
+    }
+
+    // 取得年度的配對結果，year 為 None 時包含所有年度。
+    // 有變動的分區先重新計算；同一年度正由其他查詢或背景工作計算時等待其完成
+    pub async fn get_matches(
+        &self,
+        pool: &Pool<Postgres>,
+        year: Option<i32>,
+        config: &MatcherConfig,
+    ) -> Result<CachedMatches, sqlx::Error> {
+        loop {
+            // 先建立等待通知，檢查狀態後才完成的計算也不會錯過
+            let refreshed = self.refreshed.notified();
+
+            let step = {
+                let mut index = self.lock();
+                if let Some(plan) = index.plan(year) {
+                    Step::Run(plan)
+                } else if index.is_refreshing(year) {
+                    Step::Wait
+                } else {
+                    let (generation, results) = index.snapshot(year);
+                    Step::Done(generation, results)
+                }
+            };
+
+            match step {
+                Step::Run(plan) => self.run(pool, plan, config.max_cycle_size).await?,
+                Step::Wait => refreshed.await,
+                Step::Done(generation, results) => {
+                    let finalized = matcher::finalize_matches_with_report(results, config);
+                    return Ok(CachedMatches {
+                        generation,
+                        matches: finalized.matches,
+                        exhaustive: finalized.exhaustive,
+                    });
+                }
+            }
+        }
+    }
+
+    // 重新計算所有待重算的分區，讓新循環不必等到有人查詢配對結果才被保存與通知
+    // 尚未載入的年度會整年載入
+    pub async fn refresh_dirty(&self, pool: &Pool<Postgres>, max_cycle_size: usize) -> Result<(), sqlx::Error> {
+        let years: BTreeSet<i32> = self.lock().dirty.iter().map(|key| key.year).collect();
+
+        for year in years {
+            loop {
+                let refreshed = self.refreshed.notified();
+
+                let plan = {
+                    let mut index = self.lock();
+                    match index.plan(Some(year)) {
+                        Some(plan) => Some(plan),
+                        // 該年度正在載入，完成後再計算之後標記的分區
+                        None if index.is_refreshing(Some(year)) && index.dirty.iter().any(|key| key.year == year) => None,
+                        None => break,
+                    }
+                };
+
+                match plan {
+                    Some(plan) => self.run(pool, plan, max_cycle_size).await?,
+                    None => refreshed.await,
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use crate::events::{EventBus, MatchEvent};
+use crate::invalidation;
+use crate::match_cache::{CachedMatches, MatchCache};
+use crate::model::Teacher;
+use crate::repository::TeacherRepository;
+use axum::async_trait;
+use circlematch_matcher::matcher::{self, MatcherConfig};
+use circlematch_matcher::subject::SubjectCompatibility;
+use sqlx::{Pool, Postgres};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// 配對結果與登記變動後的處理；處理函式不直接使用配對快取或循環資料表
+#[async_trait]
+pub trait MatchService: Send + Sync {
+    // year 為 None 時包含所有年度
+    async fn matches(&self, year: Option<i32>, config: &MatcherConfig) -> Result<CachedMatches, sqlx::Error>;
+    // 登記新增、修改、刪除或隱藏後呼叫，changed 包含變動前後的內容
+    async fn teachers_changed(&self, changed: &[&Teacher]);
+    // 檢查包含該教師的已保存循環是否仍然成立
+    async fn invalidate_cycles(&self, teacher: &Teacher);
+}
+
+// 正式環境：由 match_cache 增量重算，並透過事件通知重新配對與 SSE 訂閱者
+pub struct CachedMatchService {
+    pool: Pool<Postgres>,
+    cache: Arc<MatchCache>,
+}
+
+impl CachedMatchService {
+    pub fn new(pool: Pool<Postgres>, cache: Arc<MatchCache>) -> Self {
+        CachedMatchService { pool, cache }
+    }
+}
+
+#[async_trait]
+impl MatchService for CachedMatchService {
+    async fn matches(&self, year: Option<i32>, config: &MatcherConfig) -> Result<CachedMatches, sqlx::Error> {
+        self.cache.get_matches(&self.pool, year, config).await
+    }
+
+    async fn teachers_changed(&self, changed: &[&Teacher]) {
+        let event = MatchEvent::teachers_changed(changed.iter().copied());
+        // 先同步標記配對快取，之後的查詢即使早於背景工作也會拿到新結果
+        if let MatchEvent::TeachersChanged { partitions } = &event {
+            self.cache.mark_dirty(partitions.iter().cloned());
+        }
+        self.cache.events().publish(event);
+    }
+
+    async fn invalidate_cycles(&self, teacher: &Teacher) {
+        invalidation::invalidate_cycles_logged(&self.pool, self.cache.events(), teacher).await;
+    }
+}
+
+// 不使用快取，每次查詢都以儲存庫中的登記重新配對；不保存循環，只發布登記變動的事件
+pub struct DirectMatchService {
+    teachers: Arc<dyn TeacherRepository>,
+    events: EventBus,
+    generation: AtomicU64,
+}
+
+impl DirectMatchService {
+    pub fn new(teachers: Arc<dyn TeacherRepository>, events: EventBus) -> Self {
+        DirectMatchService { teachers, events, generation: AtomicU64::new(0) }
+    }
+}
+
+#[async_trait]
+impl MatchService for DirectMatchService {
+    async fn matches(&self, year: Option<i32>, config: &MatcherConfig) -> Result<CachedMatches, sqlx::Error> {
+        let teachers = self.teachers.list_filtered(year, None, false).await?;
+        let finalized = matcher::find_matches_with_report(teachers, SubjectCompatibility::bundled(), config);
+        Ok(CachedMatches {
+            generation: self.generation.load(Ordering::SeqCst),
+            matches: finalized.matches,
+            exhaustive: finalized.exhaustive,
+        })
+    }
+
+    async fn teachers_changed(&self, changed: &[&Teacher]) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.events.publish(MatchEvent::teachers_changed(changed.iter().copied()));
+    }
+
+    async fn invalidate_cycles(&self, _teacher: &Teacher) {}
+}
@@ -0,0 +1,114 @@
+use crate::health::PoolStats;
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, Encoder, Histogram,
+    HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+// 指標以全域預設 registry 註冊，/metrics 輸出 Prometheus 文字格式
+
+static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "circlematch_http_requests_total",
+        "HTTP 請求數，依路由、方法與狀態碼分類",
+        &["method", "route", "status"]
+    )
+    .expect("註冊 HTTP 請求指標失敗")
+});
+
+static HTTP_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "circlematch_http_request_duration_seconds",
+        "HTTP 請求處理時間（秒）",
+        &["method", "route"]
+    )
+    .expect("註冊 HTTP 延遲指標失敗")
+});
+
+static DB_POOL_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "circlematch_db_pool_connections",
+        "資料庫連線池的連線數（active/idle/max）",
+        &["state"]
+    )
+    .expect("註冊連線池指標失敗")
+});
+
+static MATCHER_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "circlematch_matcher_run_duration_seconds",
+        "單一配對分區的循環搜尋時間（秒）",
+        vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0]
+    )
+    .expect("註冊配對時間指標失敗")
+});
+
+static MATCHER_PARTITION_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "circlematch_matcher_partition_teachers",
+        "配對分區（年度、學制、科目群組）的教師人數",
+        vec![1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0]
+    )
+    .expect("註冊分區大小指標失敗")
+});
+
+static MATCHER_CYCLES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "circlematch_matcher_cycles",
+        "配對快取中目前的循環數，依循環人數分類",
+        &["size"]
+    )
+    .expect("註冊循環數指標失敗")
+});
+
+// 記錄每個請求的路由、狀態碼與處理時間；路由使用比對到的樣式（例如 /api/teachers/:id）以免標籤過多
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let route = request.extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    HTTP_DURATION.with_label_values(&[&method, &route]).observe(started.elapsed().as_secs_f64());
+    HTTP_REQUESTS.with_label_values(&[&method, &route, response.status().as_str()]).inc();
+    response
+}
+
+// 記錄一次分區配對的人數與耗時
+pub fn observe_matcher_run(teachers: usize, started: Instant) {
+    MATCHER_DURATION.observe(started.elapsed().as_secs_f64());
+    MATCHER_PARTITION_SIZE.observe(teachers as f64);
+}
+
+// 以快取中的循環更新各人數的循環數，已不存在的人數歸零
+pub fn set_cycle_counts(counts: &BTreeMap<usize, i64>) {
+    MATCHER_CYCLES.reset();
+    for (size, count) in counts {
+        MATCHER_CYCLES.with_label_values(&[&size.to_string()]).set(*count);
+    }
+}
+
+// 輸出所有指標，連線池狀態在讀取時更新
+pub fn render(pool: Option<PoolStats>) -> Result<String, String> {
+    if let Some(pool) = pool {
+        let size = pool.size as i64;
+        let idle = pool.idle as i64;
+        DB_POOL_CONNECTIONS.with_label_values(&["active"]).set(size - idle);
+        DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(idle);
+        DB_POOL_CONNECTIONS.with_label_values(&["max"]).set(pool.max_connections as i64);
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|e| format!("輸出指標失敗: {}", e))?;
+    String::from_utf8(buffer).map_err(|e| format!("輸出指標失敗: {}", e))
+}
@@ -1,25 +1,303 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use chrono::{Utc, DateTime};
 
+// 教師與配對結果的資料結構由配對引擎定義
+pub use circlematch_matcher::model::*;
+
+// 帳號的權限
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    // 一般教師，只能管理自己的登記
+    User,
+    // 縣市管理者，只能存取所屬縣市（admin_county）的登記
+    CountyAdmin,
+    // 系統管理者，可存取所有資料並管理輪次與帳號權限
+    SuperAdmin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::CountyAdmin => "county_admin",
+            Role::SuperAdmin => "super_admin",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "user" => Some(Role::User),
+            "county_admin" => Some(Role::CountyAdmin),
+            "super_admin" => Some(Role::SuperAdmin),
+            _ => None,
+        }
+    }
+}
+
+// 使用者帳號，google_sub 為 Google 帳號不會變動的識別碼（ID token 的 sub）
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Teacher {
-    pub id: Option<i32>,
+pub struct User {
+    pub id: i32,
+    pub google_sub: String,
+    pub email: String,
     pub name: Option<String>,
-    pub display_id: Option<String>,
+    pub picture: Option<String>,
+    pub role: Role,
+    // county_admin 管理的縣市
+    pub admin_county: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: DateTime<Utc>,
+}
+
+// 修改帳號權限，county_admin 必須指定縣市
+#[derive(Deserialize, Debug)]
+pub struct RoleUpdate {
+    pub role: Role,
+    pub admin_county: Option<String>,
+}
+
+// 登入時由 Google 回傳的帳號資料
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub google_sub: String,
     pub email: String,
+    pub name: Option<String>,
+    pub picture: Option<String>,
+}
+
+// /api/me 的回應：帳號與其下所有登記
+#[derive(Serialize, Debug)]
+pub struct Me {
+    pub user: User,
+    pub teachers: Vec<Teacher>,
+}
+
+// 學校名錄中的一筆學校資料
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct School {
+    // 教育部學校代碼；內建的範例名錄沒有代碼
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub name: String,
+    pub county: String,
+    pub district: String,
+    pub level: String,
+}
+
+// 教師登記的一筆異動紀錄，snapshot 為異動後的完整內容
+#[derive(Serialize, Debug, Clone)]
+pub struct TeacherHistoryEntry {
+    pub id: i32,
+    pub teacher_id: i32,
+    // create / update / delete
+    pub action: String,
+    pub actor: String,
+    pub changed_at: DateTime<Utc>,
+    pub snapshot: Teacher,
+}
+
+// 通知種類，同時也是通知偏好中的欄位名稱
+pub const NOTIFICATION_MATCH_FOUND: &str = "match_found";
+pub const NOTIFICATION_MATCH_ACCEPTED: &str = "match_accepted";
+pub const NOTIFICATION_MATCH_BROKEN: &str = "match_broken";
+
+pub const CYCLE_STATUS_ACTIVE: &str = "active";
+pub const CYCLE_STATUS_ACCEPTED: &str = "accepted";
+pub const CYCLE_STATUS_BROKEN: &str = "broken";
+
+// 已找到並保存的調動循環，teacher_ids 依調動順序排列
+#[derive(Serialize, Debug, Clone)]
+pub struct MatchCycle {
+    pub id: i32,
+    pub cycle_key: String,
+    pub year: i32,
+    pub school_level: String,
+    pub teacher_ids: Vec<i32>,
+    // 已確認接受此循環的教師
+    pub accepted_teacher_ids: Vec<i32>,
+    // active / accepted / broken
+    pub status: String,
+    pub found_at: DateTime<Utc>,
+    pub broken_at: Option<DateTime<Utc>>,
+    pub broken_reason: Option<String>,
+}
+
+// 給單一教師登記的通知
+#[derive(Serialize, Debug, Clone)]
+pub struct Notification {
+    pub id: i32,
+    pub teacher_id: i32,
     pub google_id: Option<String>,
+    pub kind: String,
+    pub cycle_id: Option<i32>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+    // Email 寄送狀態：pending / sent / failed / skipped
+    pub email_status: String,
+}
+
+// 待寫入的通知
+#[derive(Debug, Clone)]
+pub struct NewNotification {
+    pub teacher_id: i32,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+// 每筆登記的通知偏好，未設定的項目預設為開啟
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    // 是否寄送 Email，關閉時通知只會出現在網站上
+    pub email: bool,
+    pub match_found: bool,
+    pub match_accepted: bool,
+    pub match_broken: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        NotificationPreferences {
+            email: true,
+            match_found: true,
+            match_accepted: true,
+            match_broken: true,
+        }
+    }
+}
+
+// 調動輪次（以民國年度區分）的狀態
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundState {
+    // 準備中，尚未開放登記
+    Draft,
+    // 開放登記與修改
+    Open,
+    // 停止登記，進行配對與後續作業
+    Closed,
+    // 已封存的歷史輪次
+    Archived,
+}
+
+impl RoundState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RoundState::Draft => "draft",
+            RoundState::Open => "open",
+            RoundState::Closed => "closed",
+            RoundState::Archived => "archived",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(RoundState::Draft),
+            "open" => Some(RoundState::Open),
+            "closed" => Some(RoundState::Closed),
+            "archived" => Some(RoundState::Archived),
+            _ => None,
+        }
+    }
+
+    // 允許的狀態轉換：draft -> open -> closed -> archived，closed 可重新開放
+    pub fn can_transition_to(self, next: RoundState) -> bool {
+        matches!(
+            (self, next),
+            (RoundState::Draft, RoundState::Open)
+                | (RoundState::Open, RoundState::Closed)
+                | (RoundState::Closed, RoundState::Open)
+                | (RoundState::Closed, RoundState::Archived)
+        ) || self == next
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Round {
     pub year: i32,
-    pub subject: String,  // Added subject field
-    pub current_county: String,
-    pub current_district: String,
-    pub current_school: String,
-    pub target_counties: Vec<String>,
-    pub target_districts: Vec<String>,
+    pub state: RoundState,
+    pub registration_opens_at: Option<DateTime<Utc>>,
+    pub registration_closes_at: Option<DateTime<Utc>>,
     pub created_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct MatchResult {
-    pub match_type: String,
-    pub teachers: Vec<Teacher>,
-}
\ No newline at end of file
+impl Round {
+    // 狀態為 open 且在登記期間內才接受新增與修改
+    pub fn accepts_registrations(&self, now: DateTime<Utc>) -> bool {
+        self.state == RoundState::Open
+            && self.registration_opens_at.is_none_or(|opens_at| now >= opens_at)
+            && self.registration_closes_at.is_none_or(|closes_at| now < closes_at)
+    }
+}
+
+// 新增輪次時的內容，新輪次一律從 draft 開始
+#[derive(Deserialize, Debug)]
+pub struct NewRound {
+    pub year: i32,
+    pub registration_opens_at: Option<DateTime<Utc>>,
+    pub registration_closes_at: Option<DateTime<Utc>>,
+}
+
+// 更新輪次狀態與登記期間，未提供的時間維持原值
+#[derive(Deserialize, Debug)]
+pub struct RoundUpdate {
+    pub state: RoundState,
+    pub registration_opens_at: Option<DateTime<Utc>>,
+    pub registration_closes_at: Option<DateTime<Utc>>,
+}
+
+// 公告的重要程度，對應前端橫幅的樣式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AnnouncementSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnnouncementSeverity::Info => "info",
+            AnnouncementSeverity::Warning => "warning",
+            AnnouncementSeverity::Critical => "critical",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "info" => Some(AnnouncementSeverity::Info),
+            "warning" => Some(AnnouncementSeverity::Warning),
+            "critical" => Some(AnnouncementSeverity::Critical),
+            _ => None,
+        }
+    }
+}
+
+// 網站公告，在 publish_at 之後、expire_at 之前顯示
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Announcement {
+    pub id: i32,
+    pub title: Option<String>,
+    pub body: String,
+    pub severity: AnnouncementSeverity,
+    pub publish_at: DateTime<Utc>,
+    pub expire_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 新增或修改公告，未指定 publish_at 時立即發布，未指定 expire_at 時不會過期
+#[derive(Deserialize, Debug)]
+pub struct AnnouncementInput {
+    pub title: Option<String>,
+    pub body: String,
+    #[serde(default)]
+    pub severity: AnnouncementSeverity,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub expire_at: Option<DateTime<Utc>>,
+}
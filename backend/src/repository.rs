@@ -0,0 +1,826 @@
+use crate::db::{self, HistoryAction, UpdateOutcome};
+use crate::model::{
+    Announcement, AnnouncementInput, AnnouncementSeverity, MatchCycle, NewRound, NewUser, Notification,
+    NotificationPreferences, Role, RoleUpdate, Round, RoundState, RoundUpdate, Teacher, TeacherHistoryEntry, User,
+    CYCLE_STATUS_ACCEPTED, CYCLE_STATUS_ACTIVE, NOTIFICATION_MATCH_ACCEPTED,
+};
+use axum::async_trait;
+use chrono::Utc;
+use sqlx::{Pool, Postgres};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// 教師登記與其所屬輪次的存取；處理函式只透過此介面讀寫登記，
+// 測試可改用 InMemoryTeacherRepository 而不需要資料庫
+#[async_trait]
+pub trait TeacherRepository: Send + Sync {
+    // 參與配對的登記（不含隱藏）
+    async fn list_visible(&self) -> Result<Vec<Teacher>, sqlx::Error>;
+    async fn list_by_google_id(&self, google_id: &str) -> Result<Vec<Teacher>, sqlx::Error>;
+    // 連結到帳號（users.id）的登記
+    async fn list_by_user_id(&self, user_id: i32) -> Result<Vec<Teacher>, sqlx::Error>;
+    // 依年度與目前縣市篩選，未指定的條件不篩選
+    async fn list_filtered(
+        &self,
+        year: Option<i32>,
+        county: Option<&str>,
+        include_hidden: bool,
+    ) -> Result<Vec<Teacher>, sqlx::Error>;
+    async fn get(&self, teacher_id: i32) -> Result<Option<Teacher>, sqlx::Error>;
+    async fn create(&self, teacher: Teacher, actor: &str) -> Result<Teacher, sqlx::Error>;
+    // 批次匯入：任一筆失敗則全部不寫入
+    async fn create_many(&self, teachers: Vec<Teacher>, actor: &str) -> Result<Vec<Teacher>, sqlx::Error>;
+    // expected_version 為 None 時不檢查版本；找不到登記時返回 RowNotFound。
+    // teacher.user_id 只在登記尚未連結帳號時寫入
+    async fn update(
+        &self,
+        teacher_id: i32,
+        teacher: Teacher,
+        expected_version: Option<i32>,
+        actor: &str,
+    ) -> Result<UpdateOutcome, sqlx::Error>;
+    async fn delete(&self, teacher_id: i32, actor: &str) -> Result<(), sqlx::Error>;
+    async fn set_hidden(&self, teacher_id: i32, hidden: bool, actor: &str) -> Result<Option<Teacher>, sqlx::Error>;
+    // 異動紀錄（包含已刪除的登記），依時間排序
+    async fn history(&self, teacher_id: i32) -> Result<Vec<TeacherHistoryEntry>, sqlx::Error>;
+
+    // 輪次決定登記能否新增或修改，以及配對時預設的年度
+    async fn rounds(&self) -> Result<Vec<Round>, sqlx::Error>;
+    async fn round(&self, year: i32) -> Result<Option<Round>, sqlx::Error>;
+    async fn current_round(&self) -> Result<Option<Round>, sqlx::Error>;
+    async fn next_open_round(&self, after_year: i32) -> Result<Option<Round>, sqlx::Error>;
+    async fn create_round(&self, round: NewRound) -> Result<Round, sqlx::Error>;
+    // 找不到輪次時返回 RowNotFound
+    async fn update_round(&self, year: i32, update: RoundUpdate) -> Result<Round, sqlx::Error>;
+}
+
+pub struct PgTeacherRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PgTeacherRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        PgTeacherRepository { pool }
+    }
+}
+
+#[async_trait]
+impl TeacherRepository for PgTeacherRepository {
+    async fn list_visible(&self) -> Result<Vec<Teacher>, sqlx::Error> {
+        db::get_all_teachers(&self.pool).await
+    }
+
+    async fn list_by_google_id(&self, google_id: &str) -> Result<Vec<Teacher>, sqlx::Error> {
+        db::get_teachers_by_google_id(&self.pool, google_id).await
+    }
+
+    async fn list_by_user_id(&self, user_id: i32) -> Result<Vec<Teacher>, sqlx::Error> {
+        db::get_teachers_by_user_id(&self.pool, user_id).await
+    }
+
+    async fn list_filtered(
+        &self,
+        year: Option<i32>,
+        county: Option<&str>,
+        include_hidden: bool,
+    ) -> Result<Vec<Teacher>, sqlx::Error> {
+        db::get_teachers_for_admin(&self.pool, year, county, include_hidden).await
+    }
+
+    async fn get(&self, teacher_id: i32) -> Result<Option<Teacher>, sqlx::Error> {
+        db::get_teacher_by_id(&self.pool, teacher_id).await
+    }
+
+    async fn create(&self, teacher: Teacher, actor: &str) -> Result<Teacher, sqlx::Error> {
+        db::create_teacher(&self.pool, teacher, actor).await
+    }
+
+    async fn create_many(&self, teachers: Vec<Teacher>, actor: &str) -> Result<Vec<Teacher>, sqlx::Error> {
+        db::create_teachers(&self.pool, teachers, actor).await
+    }
+
+    async fn update(
+        &self,
+        teacher_id: i32,
+        teacher: Teacher,
+        expected_version: Option<i32>,
+        actor: &str,
+    ) -> Result<UpdateOutcome, sqlx::Error> {
+        db::update_teacher(&self.pool, teacher_id, teacher, expected_version, actor).await
+    }
+
+    async fn delete(&self, teacher_id: i32, actor: &str) -> Result<(), sqlx::Error> {
+        db::delete_teacher(&self.pool, teacher_id, actor).await
+    }
+
+    async fn set_hidden(&self, teacher_id: i32, hidden: bool, actor: &str) -> Result<Option<Teacher>, sqlx::Error> {
+        db::set_teacher_hidden(&self.pool, teacher_id, hidden, actor).await
+    }
+
+    async fn history(&self, teacher_id: i32) -> Result<Vec<TeacherHistoryEntry>, sqlx::Error> {
+        db::get_teacher_history(&self.pool, teacher_id).await
+    }
+
+    async fn rounds(&self) -> Result<Vec<Round>, sqlx::Error> {
+        db::get_rounds(&self.pool).await
+    }
+
+    async fn round(&self, year: i32) -> Result<Option<Round>, sqlx::Error> {
+        db::get_round(&self.pool, year).await
+    }
+
+    async fn current_round(&self) -> Result<Option<Round>, sqlx::Error> {
+        db::get_current_round(&self.pool).await
+    }
+
+    async fn next_open_round(&self, after_year: i32) -> Result<Option<Round>, sqlx::Error> {
+        db::get_next_open_round(&self.pool, after_year).await
+    }
+
+    async fn create_round(&self, round: NewRound) -> Result<Round, sqlx::Error> {
+        db::create_round(&self.pool, round).await
+    }
+
+    async fn update_round(&self, year: i32, update: RoundUpdate) -> Result<Round, sqlx::Error> {
+        db::update_round(&self.pool, year, update).await
+    }
+}
+
+// 記憶體中的實作，版本號、篩選規則與異動紀錄與資料表相同；刪除的登記直接移除，異動紀錄仍保留
+#[derive(Default)]
+pub struct InMemoryTeacherRepository {
+    data: Mutex<InMemoryData>,
+}
+
+#[derive(Default)]
+struct InMemoryData {
+    next_id: i32,
+    teachers: BTreeMap<i32, Teacher>,
+    rounds: BTreeMap<i32, Round>,
+    history: Vec<TeacherHistoryEntry>,
+}
+
+impl InMemoryData {
+    fn insert(&mut self, mut teacher: Teacher, actor: &str) -> Teacher {
+        self.next_id += 1;
+        let id = self.next_id;
+        let now = Utc::now();
+
+        teacher.id = Some(id);
+        teacher.name = teacher.name.or_else(|| Some(format!("User-{}", id)));
+        teacher.display_id = teacher.display_id
+            .or_else(|| Some(db::generate_display_id(&teacher.current_county, &teacher.current_district)));
+        teacher.created_at = Some(now);
+        teacher.updated_at = Some(now);
+        teacher.version = 1;
+        teacher.hidden = false;
+
+        self.teachers.insert(id, teacher.clone());
+        self.record(&teacher, HistoryAction::Create, actor);
+        teacher
+    }
+
+    fn record(&mut self, teacher: &Teacher, action: HistoryAction, actor: &str) {
+        self.history.push(TeacherHistoryEntry {
+            id: self.history.len() as i32 + 1,
+            teacher_id: teacher.id.unwrap_or_default(),
+            action: action.as_str().to_string(),
+            actor: actor.to_string(),
+            changed_at: Utc::now(),
+            snapshot: teacher.clone(),
+        });
+    }
+}
+
+impl InMemoryTeacherRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 新增或取代輪次，供測試設定登記期間
+    pub fn put_round(&self, round: Round) {
+        self.lock().rounds.insert(round.year, round);
+    }
+
+    // 與 db::upsert_user 相同，登入時把同一 email 下尚未連結的登記連到帳號
+    pub fn link_user(&self, user_id: i32, email: &str) {
+        for teacher in self.lock().teachers.values_mut() {
+            if teacher.google_id.as_deref() == Some(email) && teacher.user_id.is_none() {
+                teacher.user_id = Some(user_id);
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, InMemoryData> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl TeacherRepository for InMemoryTeacherRepository {
+    async fn list_visible(&self) -> Result<Vec<Teacher>, sqlx::Error> {
+        Ok(self.lock().teachers.values().filter(|t| !t.hidden).cloned().collect())
+    }
+
+    async fn list_by_google_id(&self, google_id: &str) -> Result<Vec<Teacher>, sqlx::Error> {
+        Ok(self.lock()
+            .teachers
+            .values()
+            .filter(|t| t.google_id.as_deref() == Some(google_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn list_by_user_id(&self, user_id: i32) -> Result<Vec<Teacher>, sqlx::Error> {
+        Ok(self.lock()
+            .teachers
+            .values()
+            .filter(|t| t.user_id == Some(user_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn list_filtered(
+        &self,
+        year: Option<i32>,
+        county: Option<&str>,
+        include_hidden: bool,
+    ) -> Result<Vec<Teacher>, sqlx::Error> {
+        let mut teachers: Vec<Teacher> = self.lock()
+            .teachers
+            .values()
+            .filter(|t| year.is_none_or(|year| t.year == year))
+            .filter(|t| county.is_none_or(|county| t.current_county == county))
+            .filter(|t| include_hidden || !t.hidden)
+            .cloned()
+            .collect();
+        teachers.sort_by(|a, b| {
+            (a.year, &a.current_county, &a.current_district, a.id)
+                .cmp(&(b.year, &b.current_county, &b.current_district, b.id))
+        });
+        Ok(teachers)
+    }
+
+    async fn get(&self, teacher_id: i32) -> Result<Option<Teacher>, sqlx::Error> {
+        Ok(self.lock().teachers.get(&teacher_id).cloned())
+    }
+
+    async fn create(&self, teacher: Teacher, actor: &str) -> Result<Teacher, sqlx::Error> {
+        Ok(self.lock().insert(teacher, actor))
+    }
+
+    async fn create_many(&self, teachers: Vec<Teacher>, actor: &str) -> Result<Vec<Teacher>, sqlx::Error> {
+        let mut data = self.lock();
+        Ok(teachers.into_iter().map(|teacher| data.insert(teacher, actor)).collect())
+    }
+
+    async fn update(
+        &self,
+        teacher_id: i32,
+        teacher: Teacher,
+        expected_version: Option<i32>,
+        actor: &str,
+    ) -> Result<UpdateOutcome, sqlx::Error> {
+        if teacher.id.is_some_and(|id| id != teacher_id) {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        let mut data = self.lock();
+        let current = data.teachers.get_mut(&teacher_id).ok_or(sqlx::Error::RowNotFound)?;
+        if expected_version.is_some_and(|version| version != current.version) {
+            return Ok(UpdateOutcome::Stale(current.clone()));
+        }
+
+        // 與 db::update_teacher 相同，只更新登記內容，識別資料與既有的帳號連結保持不變
+        *current = Teacher {
+            name: teacher.name.or_else(|| current.name.clone()),
+            email: teacher.email,
+            year: teacher.year,
+            school_level: teacher.school_level,
+            subject: teacher.subject,
+            current_county: teacher.current_county,
+            current_district: teacher.current_district,
+            current_school: teacher.current_school,
+            school_code: teacher.school_code,
+            target_counties: teacher.target_counties,
+            target_districts: teacher.target_districts,
+            updated_at: Some(Utc::now()),
+            version: current.version + 1,
+            user_id: current.user_id.or(teacher.user_id),
+            ..current.clone()
+        };
+        let updated = current.clone();
+        data.record(&updated, HistoryAction::Update, actor);
+        Ok(UpdateOutcome::Updated(updated))
+    }
+
+    async fn delete(&self, teacher_id: i32, actor: &str) -> Result<(), sqlx::Error> {
+        let mut data = self.lock();
+        let deleted = data.teachers.remove(&teacher_id).ok_or(sqlx::Error::RowNotFound)?;
+        data.record(&deleted, HistoryAction::Delete, actor);
+        Ok(())
+    }
+
+    async fn set_hidden(&self, teacher_id: i32, hidden: bool, actor: &str) -> Result<Option<Teacher>, sqlx::Error> {
+        let mut data = self.lock();
+        let Some(teacher) = data.teachers.get_mut(&teacher_id) else {
+            return Ok(None);
+        };
+        teacher.hidden = hidden;
+        teacher.updated_at = Some(Utc::now());
+        teacher.version += 1;
+        let teacher = teacher.clone();
+        data.record(&teacher, HistoryAction::Update, actor);
+        Ok(Some(teacher))
+    }
+
+    async fn history(&self, teacher_id: i32) -> Result<Vec<TeacherHistoryEntry>, sqlx::Error> {
+        Ok(self.lock()
+            .history
+            .iter()
+            .filter(|entry| entry.teacher_id == teacher_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn rounds(&self) -> Result<Vec<Round>, sqlx::Error> {
+        Ok(self.lock().rounds.values().rev().cloned().collect())
+    }
+
+    async fn round(&self, year: i32) -> Result<Option<Round>, sqlx::Error> {
+        Ok(self.lock().rounds.get(&year).cloned())
+    }
+
+    async fn current_round(&self) -> Result<Option<Round>, sqlx::Error> {
+        Ok(self.lock()
+            .rounds
+            .values()
+            .rev()
+            .find(|round| matches!(round.state, RoundState::Open | RoundState::Closed))
+            .cloned())
+    }
+
+    async fn next_open_round(&self, after_year: i32) -> Result<Option<Round>, sqlx::Error> {
+        Ok(self.lock()
+            .rounds
+            .range(after_year + 1..)
+            .map(|(_, round)| round)
+            .find(|round| round.state == RoundState::Open)
+            .cloned())
+    }
+
+    // 年度重複時資料表以唯一鍵拒絕；處理函式會先檢查，這裡保留既有的輪次不覆寫
+    async fn create_round(&self, round: NewRound) -> Result<Round, sqlx::Error> {
+        Ok(self.lock()
+            .rounds
+            .entry(round.year)
+            .or_insert_with(|| Round {
+                year: round.year,
+                state: RoundState::Draft,
+                registration_opens_at: round.registration_opens_at,
+                registration_closes_at: round.registration_closes_at,
+                created_at: Some(Utc::now()),
+            })
+            .clone())
+    }
+
+    async fn update_round(&self, year: i32, update: RoundUpdate) -> Result<Round, sqlx::Error> {
+        let mut data = self.lock();
+        let round = data.rounds.get_mut(&year).ok_or(sqlx::Error::RowNotFound)?;
+        round.state = update.state;
+        round.registration_opens_at = update.registration_opens_at.or(round.registration_opens_at);
+        round.registration_closes_at = update.registration_closes_at.or(round.registration_closes_at);
+        Ok(round.clone())
+    }
+}
+
+// 登入帳號與權限；AdminUser 等擷取器也透過此介面查詢帳號
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    // 登入時建立或更新帳號，並連結同一 email 下尚未連結的登記
+    async fn upsert(&self, login: &NewUser) -> Result<User, sqlx::Error>;
+    async fn get_by_sub(&self, google_sub: &str) -> Result<Option<User>, sqlx::Error>;
+    async fn list(&self) -> Result<Vec<User>, sqlx::Error>;
+    // 找不到帳號時返回 None
+    async fn update_role(&self, user_id: i32, update: &RoleUpdate) -> Result<Option<User>, sqlx::Error>;
+}
+
+pub struct PgUserRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PgUserRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        PgUserRepository { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PgUserRepository {
+    async fn upsert(&self, login: &NewUser) -> Result<User, sqlx::Error> {
+        db::upsert_user(&self.pool, login).await
+    }
+
+    async fn get_by_sub(&self, google_sub: &str) -> Result<Option<User>, sqlx::Error> {
+        db::get_user_by_sub(&self.pool, google_sub).await
+    }
+
+    async fn list(&self) -> Result<Vec<User>, sqlx::Error> {
+        db::get_users(&self.pool).await
+    }
+
+    async fn update_role(&self, user_id: i32, update: &RoleUpdate) -> Result<Option<User>, sqlx::Error> {
+        db::update_user_role(&self.pool, user_id, update).await
+    }
+}
+
+// 記憶體中的帳號，登入時連結 teachers 中同一 email 的登記
+pub struct InMemoryUserRepository {
+    teachers: Arc<InMemoryTeacherRepository>,
+    users: Mutex<BTreeMap<i32, User>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new(teachers: Arc<InMemoryTeacherRepository>) -> Self {
+        InMemoryUserRepository { teachers, users: Mutex::default() }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<i32, User>> {
+        self.users.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn upsert(&self, login: &NewUser) -> Result<User, sqlx::Error> {
+        let now = Utc::now();
+        let user = {
+            let mut users = self.lock();
+            let existing = users.values_mut().find(|user| user.google_sub == login.google_sub);
+            match existing {
+                Some(user) => {
+                    user.email = login.email.clone();
+                    user.name = login.name.clone();
+                    user.picture = login.picture.clone();
+                    user.last_login_at = now;
+                    user.clone()
+                }
+                None => {
+                    let id = users.keys().next_back().map_or(1, |id| id + 1);
+                    let user = User {
+                        id,
+                        google_sub: login.google_sub.clone(),
+                        email: login.email.clone(),
+                        name: login.name.clone(),
+                        picture: login.picture.clone(),
+                        role: Role::User,
+                        admin_county: None,
+                        created_at: now,
+                        last_login_at: now,
+                    };
+                    users.insert(id, user.clone());
+                    user
+                }
+            }
+        };
+
+        self.teachers.link_user(user.id, &user.email);
+        Ok(user)
+    }
+
+    async fn get_by_sub(&self, google_sub: &str) -> Result<Option<User>, sqlx::Error> {
+        Ok(self.lock().values().find(|user| user.google_sub == google_sub).cloned())
+    }
+
+    async fn list(&self) -> Result<Vec<User>, sqlx::Error> {
+        Ok(self.lock().values().cloned().collect())
+    }
+
+    async fn update_role(&self, user_id: i32, update: &RoleUpdate) -> Result<Option<User>, sqlx::Error> {
+        Ok(self.lock().get_mut(&user_id).map(|user| {
+            user.role = update.role;
+            user.admin_county = update.admin_county.clone().filter(|_| update.role == Role::CountyAdmin);
+            user.clone()
+        }))
+    }
+}
+
+// 站內通知、循環確認與通知偏好
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    // 帳號下所有登記的通知，最新的在前
+    async fn list_for_account(&self, google_id: &str) -> Result<Vec<Notification>, sqlx::Error>;
+    async fn get(&self, notification_id: i32) -> Result<Option<Notification>, sqlx::Error>;
+    // 找不到通知時返回 RowNotFound
+    async fn mark_read(&self, notification_id: i32) -> Result<Notification, sqlx::Error>;
+    async fn cycle(&self, cycle_id: i32) -> Result<Option<MatchCycle>, sqlx::Error>;
+    // 記錄參與者確認，全員確認後通知所有參與者；循環已失效或已確認時返回 None
+    async fn accept_cycle(
+        &self,
+        cycle_id: i32,
+        teacher_id: i32,
+        accepted_message: &str,
+    ) -> Result<Option<MatchCycle>, sqlx::Error>;
+    // 找不到登記時返回 RowNotFound
+    async fn preferences(&self, teacher_id: i32) -> Result<NotificationPreferences, sqlx::Error>;
+    async fn update_preferences(&self, teacher_id: i32, preferences: &NotificationPreferences) -> Result<(), sqlx::Error>;
+}
+
+pub struct PgNotificationRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PgNotificationRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        PgNotificationRepository { pool }
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for PgNotificationRepository {
+    async fn list_for_account(&self, google_id: &str) -> Result<Vec<Notification>, sqlx::Error> {
+        db::get_notifications_by_google_id(&self.pool, google_id).await
+    }
+
+    async fn get(&self, notification_id: i32) -> Result<Option<Notification>, sqlx::Error> {
+        db::get_notification(&self.pool, notification_id).await
+    }
+
+    async fn mark_read(&self, notification_id: i32) -> Result<Notification, sqlx::Error> {
+        db::mark_notification_read(&self.pool, notification_id).await
+    }
+
+    async fn cycle(&self, cycle_id: i32) -> Result<Option<MatchCycle>, sqlx::Error> {
+        db::get_cycle(&self.pool, cycle_id).await
+    }
+
+    async fn accept_cycle(
+        &self,
+        cycle_id: i32,
+        teacher_id: i32,
+        accepted_message: &str,
+    ) -> Result<Option<MatchCycle>, sqlx::Error> {
+        db::accept_cycle(&self.pool, cycle_id, teacher_id, accepted_message).await
+    }
+
+    async fn preferences(&self, teacher_id: i32) -> Result<NotificationPreferences, sqlx::Error> {
+        db::get_notification_preferences(&self.pool, teacher_id).await
+    }
+
+    async fn update_preferences(&self, teacher_id: i32, preferences: &NotificationPreferences) -> Result<(), sqlx::Error> {
+        db::update_notification_preferences(&self.pool, teacher_id, preferences).await
+    }
+}
+
+// 記憶體中的通知與循環；不寄送 Email，通知的 email_status 一律為 skipped
+pub struct InMemoryNotificationRepository {
+    teachers: Arc<InMemoryTeacherRepository>,
+    data: Mutex<NotificationData>,
+}
+
+#[derive(Default)]
+struct NotificationData {
+    notifications: BTreeMap<i32, Notification>,
+    cycles: BTreeMap<i32, MatchCycle>,
+    preferences: HashMap<i32, NotificationPreferences>,
+}
+
+impl InMemoryNotificationRepository {
+    pub fn new(teachers: Arc<InMemoryTeacherRepository>) -> Self {
+        InMemoryNotificationRepository { teachers, data: Mutex::default() }
+    }
+
+    // 新增或取代循環，供測試建立待確認的循環
+    pub fn put_cycle(&self, cycle: MatchCycle) {
+        self.lock().cycles.insert(cycle.id, cycle);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, NotificationData> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn existing_teacher(&self, teacher_id: i32) -> Result<Teacher, sqlx::Error> {
+        self.teachers.get(teacher_id).await?.ok_or(sqlx::Error::RowNotFound)
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for InMemoryNotificationRepository {
+    async fn list_for_account(&self, google_id: &str) -> Result<Vec<Notification>, sqlx::Error> {
+        Ok(self.lock()
+            .notifications
+            .values()
+            .rev()
+            .filter(|notification| notification.google_id.as_deref() == Some(google_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn get(&self, notification_id: i32) -> Result<Option<Notification>, sqlx::Error> {
+        Ok(self.lock().notifications.get(&notification_id).cloned())
+    }
+
+    async fn mark_read(&self, notification_id: i32) -> Result<Notification, sqlx::Error> {
+        let mut data = self.lock();
+        let notification = data.notifications.get_mut(&notification_id).ok_or(sqlx::Error::RowNotFound)?;
+        notification.read_at = notification.read_at.or_else(|| Some(Utc::now()));
+        Ok(notification.clone())
+    }
+
+    async fn cycle(&self, cycle_id: i32) -> Result<Option<MatchCycle>, sqlx::Error> {
+        Ok(self.lock().cycles.get(&cycle_id).cloned())
+    }
+
+    async fn accept_cycle(
+        &self,
+        cycle_id: i32,
+        teacher_id: i32,
+        accepted_message: &str,
+    ) -> Result<Option<MatchCycle>, sqlx::Error> {
+        // 先取得參與者的帳號，寫入通知時才能依帳號查詢
+        let Some(participants) = self.cycle(cycle_id).await?.map(|cycle| cycle.teacher_ids) else {
+            return Ok(None);
+        };
+        let mut google_ids = HashMap::new();
+        for participant_id in participants {
+            if let Some(teacher) = self.teachers.get(participant_id).await? {
+                google_ids.insert(participant_id, teacher.google_id);
+            }
+        }
+
+        let mut data = self.lock();
+        let Some(cycle) = data.cycles.get_mut(&cycle_id) else {
+            return Ok(None);
+        };
+        if cycle.status != CYCLE_STATUS_ACTIVE
+            || !cycle.teacher_ids.contains(&teacher_id)
+            || cycle.accepted_teacher_ids.contains(&teacher_id)
+        {
+            return Ok(None);
+        }
+
+        cycle.accepted_teacher_ids.push(teacher_id);
+        let cycle = cycle.clone();
+        if !cycle.teacher_ids.iter().all(|id| cycle.accepted_teacher_ids.contains(id)) {
+            return Ok(Some(cycle));
+        }
+
+        let cycle = MatchCycle { status: CYCLE_STATUS_ACCEPTED.to_string(), ..cycle };
+        data.cycles.insert(cycle_id, cycle.clone());
+        for participant_id in cycle.teacher_ids.iter().filter(|id| google_ids.contains_key(id)) {
+            let id = data.notifications.keys().next_back().map_or(1, |id| id + 1);
+            data.notifications.insert(id, Notification {
+                id,
+                teacher_id: *participant_id,
+                google_id: google_ids[participant_id].clone(),
+                kind: NOTIFICATION_MATCH_ACCEPTED.to_string(),
+                cycle_id: Some(cycle_id),
+                message: accepted_message.to_string(),
+                created_at: Utc::now(),
+                read_at: None,
+                email_status: "skipped".to_string(),
+            });
+        }
+        Ok(Some(cycle))
+    }
+
+    async fn preferences(&self, teacher_id: i32) -> Result<NotificationPreferences, sqlx::Error> {
+        self.existing_teacher(teacher_id).await?;
+        Ok(self.lock().preferences.get(&teacher_id).cloned().unwrap_or_default())
+    }
+
+    async fn update_preferences(&self, teacher_id: i32, preferences: &NotificationPreferences) -> Result<(), sqlx::Error> {
+        self.existing_teacher(teacher_id).await?;
+        self.lock().preferences.insert(teacher_id, preferences.clone());
+        Ok(())
+    }
+}
+
+// 網站公告
+#[async_trait]
+pub trait AnnouncementRepository: Send + Sync {
+    // 目前顯示中的公告，重要程度高的在前，同等級時較新的在前
+    async fn active(&self) -> Result<Vec<Announcement>, sqlx::Error>;
+    // 所有公告（含排程中與已過期），供管理介面使用
+    async fn list(&self) -> Result<Vec<Announcement>, sqlx::Error>;
+    async fn create(&self, input: &AnnouncementInput, actor: &str) -> Result<Announcement, sqlx::Error>;
+    // 找不到公告時返回 None；未指定 publish_at 時保留原本的發布時間
+    async fn update(&self, id: i32, input: &AnnouncementInput) -> Result<Option<Announcement>, sqlx::Error>;
+    // 返回是否有刪除資料
+    async fn delete(&self, id: i32) -> Result<bool, sqlx::Error>;
+}
+
+pub struct PgAnnouncementRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PgAnnouncementRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        PgAnnouncementRepository { pool }
+    }
+}
+
+#[async_trait]
+impl AnnouncementRepository for PgAnnouncementRepository {
+    async fn active(&self) -> Result<Vec<Announcement>, sqlx::Error> {
+        db::get_active_announcements(&self.pool).await
+    }
+
+    async fn list(&self) -> Result<Vec<Announcement>, sqlx::Error> {
+        db::get_announcements(&self.pool).await
+    }
+
+    async fn create(&self, input: &AnnouncementInput, actor: &str) -> Result<Announcement, sqlx::Error> {
+        db::create_announcement(&self.pool, input, actor).await
+    }
+
+    async fn update(&self, id: i32, input: &AnnouncementInput) -> Result<Option<Announcement>, sqlx::Error> {
+        db::update_announcement(&self.pool, id, input).await
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool, sqlx::Error> {
+        db::delete_announcement(&self.pool, id).await
+    }
+}
+
+// 記憶體中的公告；發布與過期時間的先後只由 validation::validate_announcement 檢查，
+// 未指定發布時間時不像資料表的檢查條件那樣拒絕
+#[derive(Default)]
+pub struct InMemoryAnnouncementRepository {
+    announcements: Mutex<BTreeMap<i32, Announcement>>,
+}
+
+impl InMemoryAnnouncementRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<i32, Announcement>> {
+        self.announcements.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn severity_rank(severity: AnnouncementSeverity) -> u8 {
+    match severity {
+        AnnouncementSeverity::Critical => 0,
+        AnnouncementSeverity::Warning => 1,
+        AnnouncementSeverity::Info => 2,
+    }
+}
+
+#[async_trait]
+impl AnnouncementRepository for InMemoryAnnouncementRepository {
+    async fn active(&self) -> Result<Vec<Announcement>, sqlx::Error> {
+        let now = Utc::now();
+        let mut announcements: Vec<Announcement> = self.lock()
+            .values()
+            .filter(|a| a.publish_at <= now && a.expire_at.is_none_or(|expire_at| expire_at > now))
+            .cloned()
+            .collect();
+        announcements.sort_by(|a, b| {
+            severity_rank(a.severity).cmp(&severity_rank(b.severity)).then(b.publish_at.cmp(&a.publish_at))
+        });
+        Ok(announcements)
+    }
+
+    async fn list(&self) -> Result<Vec<Announcement>, sqlx::Error> {
+        let mut announcements: Vec<Announcement> = self.lock().values().cloned().collect();
+        announcements.sort_by_key(|a| std::cmp::Reverse((a.publish_at, a.id)));
+        Ok(announcements)
+    }
+
+    async fn create(&self, input: &AnnouncementInput, actor: &str) -> Result<Announcement, sqlx::Error> {
+        let now = Utc::now();
+        let mut announcements = self.lock();
+        let id = announcements.keys().next_back().map_or(1, |id| id + 1);
+        let announcement = Announcement {
+            id,
+            title: input.title.clone(),
+            body: input.body.clone(),
+            severity: input.severity,
+            publish_at: input.publish_at.unwrap_or(now),
+            expire_at: input.expire_at,
+            created_by: actor.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        announcements.insert(id, announcement.clone());
+        Ok(announcement)
+    }
+
+    async fn update(&self, id: i32, input: &AnnouncementInput) -> Result<Option<Announcement>, sqlx::Error> {
+        Ok(self.lock().get_mut(&id).map(|announcement| {
+            announcement.title = input.title.clone();
+            announcement.body = input.body.clone();
+            announcement.severity = input.severity;
+            announcement.publish_at = input.publish_at.unwrap_or(announcement.publish_at);
+            announcement.expire_at = input.expire_at;
+            announcement.updated_at = Utc::now();
+            announcement.clone()
+        }))
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool, sqlx::Error> {
+        Ok(self.lock().remove(&id).is_some())
+    }
+}
@@ -0,0 +1,1267 @@
+use axum::{
+    routing::{get, post, put, delete},
+    Router, extract::State,
+    http::Method, extract::Json,
+    response::IntoResponse,
+    extract::Path,
+};
+use serde::Serialize;
+use serde::Deserialize;
+use serde_json::Value;
+use tower_http::cors::{AllowOrigin, CorsLayer, Any};
+use crate::repository::TeacherRepository;
+use crate::state::AppState;
+use crate::{auth, db, export, import, metrics, model, validation};
+use crate::model::{Announcement, AnnouncementInput, Me, NewUser, Role, RoleUpdate, User, Teacher, TeacherHistoryEntry, MatchCycle, Notification, NotificationPreferences, School, Round, NewRound, RoundUpdate};
+use axum::extract::Query;
+use circlematch_matcher::{matcher, subject, graph_export, report};
+
+// 建立完整的路由；main 與測試共用，測試可傳入記憶體中的實作
+pub fn router(state: AppState) -> Router {
+    let config = state.config.clone();
+
+    let allow_origin = if config.cors.allows_any() {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(config.cors.origins())
+    };
+    let cors = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers(Any)
+        .expose_headers([axum::http::header::ETAG, axum::http::HeaderName::from_static(MATCH_GENERATION_HEADER)]);
+
+    // 管理功能：每個處理函式都以 AdminUser（縣市管理者只能存取所屬縣市）或 SuperAdmin 驗證
+    let admin_routes = Router::new()
+        .route("/teachers", get(admin_list_teachers))
+        .route("/teachers/:id", put(admin_update_teacher_handler))
+        .route("/teachers/:id/hide", post(admin_hide_teacher))
+        .route("/teachers/:id/unhide", post(admin_unhide_teacher))
+        .route("/users", get(get_users_handler))
+        .route("/announcements", get(admin_list_announcements))
+        .route("/announcements", post(create_announcement_handler))
+        .route("/announcements/:id", put(update_announcement_handler))
+        .route("/announcements/:id", delete(delete_announcement_handler))
+        .route("/users/:id/role", put(update_user_role_handler))
+        .route("/graph", get(export_preference_graph))
+        .route("/rounds", post(create_round_handler))
+        .route("/rounds/:year", put(update_round_handler))
+        .route("/import/teachers", post(import_teachers_handler))
+        .route("/export/teachers", get(export_teachers_handler))
+        .route("/export/matches", get(export_matches_handler));
+
+    Router::new()
+        .route("/", get(root))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics_handler))
+        .route("/api/hello", get(hello))
+        .route("/api/announcements", get(get_announcements))
+        .route("/api/teachers", get(get_teachers))
+        .route("/api/teachers", post(create_teacher))
+        .route("/api/matches", get(find_matches))
+        .route("/api/google-login", post(google_login))
+        .route("/api/me", get(get_me))
+        .route("/api/districts", get(get_districts))
+        .route("/api/subjects", get(get_subjects))
+        .route("/api/schools", get(search_schools))
+        .route("/api/teachers/:id", get(get_teacher_handler))
+        .route("/api/teachers/:id", put(update_teacher_handler))
+        .route("/api/teachers/:id", delete(delete_teacher_handler))
+        .route("/api/teachers/:id/next-round", post(copy_to_next_round_handler))
+        .route("/api/teachers/:id/history", get(teacher_history_handler))
+        .route("/api/events", get(match_events))
+        .route("/api/events/ticket", post(issue_event_ticket))
+        .route("/api/notifications", get(get_notifications))
+        .route("/api/cycles/:id/accept", post(accept_cycle_handler))
+        .route("/api/teachers/:id/notification-preferences", get(get_notification_preferences))
+        .route("/api/teachers/:id/notification-preferences", put(update_notification_preferences))
+        .route("/api/notifications/:id/read", post(mark_notification_read))
+        .route("/api/rounds", get(get_rounds))
+        .route("/api/rounds/current", get(get_current_round))
+        .nest("/api/admin", admin_routes)
+        .with_state(state)
+        .layer(axum::middleware::from_fn(metrics::track_requests))
+        .layer(cors)
+}
+
+// 返回縣市區域資料
+async fn get_districts() -> impl IntoResponse {
+    let districts = db::get_taiwan_districts();
+    Json(districts)
+}
+
+async fn root() -> &'static str {
+    "CircleMatch API is running"
+}
+
+// 存活檢查：程序能回應請求即可，不檢查資料庫
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+// 就緒檢查：資料庫可連線且參考資料（縣市、科目、學校名錄）已載入
+async fn readyz(State(state): State<AppState>) -> (axum::http::StatusCode, Json<Value>) {
+    let database = state.database.ping().await;
+    let missing = db::missing_reference_data();
+
+    let ready = database.is_ok() && missing.is_empty();
+    if let Err(e) = &database {
+        tracing::warn!("就緒檢查：資料庫無法連線: {}", e);
+    }
+
+    let status = if ready {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(serde_json::json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "database": database.is_ok(),
+        "missing_reference_data": missing,
+    })))
+}
+
+// Prometheus 指標
+async fn metrics_handler(State(state): State<AppState>) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
+    let body = metrics::render(state.database.pool_stats()).map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(([(axum::http::header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body))
+}
+
+// 返回目前最重要的一則公告，沒有公告時返回預設訊息
+async fn hello(State(state): State<AppState>) -> Json<Message> {
+    let text = state.announcements.active()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .map(|announcement| announcement.body)
+        .unwrap_or_else(|| "Default message".to_string());
+    
+    Json(Message { text })
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+// 目前顯示中的公告
+async fn get_announcements(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Announcement>>, (axum::http::StatusCode, String)> {
+    state.announcements.active()
+        .await
+        .map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取公告失敗: {}", e)))
+}
+
+fn announcement_error(e: sqlx::Error) -> (axum::http::StatusCode, String) {
+    match e {
+        sqlx::Error::Database(e) if e.kind() == sqlx::error::ErrorKind::CheckViolation => {
+            (axum::http::StatusCode::BAD_REQUEST, "公告的過期時間必須晚於發布時間".to_string())
+        }
+        e => {
+            let error_msg = format!("保存公告失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+        }
+    }
+}
+
+// 所有公告，包含排程中與已過期的
+async fn admin_list_announcements(
+    State(state): State<AppState>,
+    _admin: auth::SuperAdmin,
+) -> Result<Json<Vec<Announcement>>, (axum::http::StatusCode, String)> {
+    state.announcements.list()
+        .await
+        .map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取公告失敗: {}", e)))
+}
+
+async fn create_announcement_handler(
+    State(state): State<AppState>,
+    auth::SuperAdmin(admin): auth::SuperAdmin,
+    Json(input): Json<AnnouncementInput>,
+) -> Result<(axum::http::StatusCode, Json<Announcement>), (axum::http::StatusCode, String)> {
+    validation::validate_announcement(&input)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e))?;
+
+    let created = state.announcements.create(&input, &admin.user.email)
+        .await
+        .map_err(announcement_error)?;
+    tracing::info!("{} 新增公告 {}", admin.user.email, created.id);
+    Ok((axum::http::StatusCode::CREATED, Json(created)))
+}
+
+async fn update_announcement_handler(
+    State(state): State<AppState>,
+    auth::SuperAdmin(admin): auth::SuperAdmin,
+    Path(id): Path<i32>,
+    Json(input): Json<AnnouncementInput>,
+) -> Result<Json<Announcement>, (axum::http::StatusCode, String)> {
+    validation::validate_announcement(&input)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e))?;
+
+    let updated = state.announcements.update(id, &input)
+        .await
+        .map_err(announcement_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該公告".to_string()))?;
+    tracing::info!("{} 修改公告 {}", admin.user.email, id);
+    Ok(Json(updated))
+}
+
+async fn delete_announcement_handler(
+    State(state): State<AppState>,
+    auth::SuperAdmin(admin): auth::SuperAdmin,
+    Path(id): Path<i32>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+    let deleted = state.announcements.delete(id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("刪除公告失敗: {}", e)))?;
+    if !deleted {
+        return Err((axum::http::StatusCode::NOT_FOUND, "找不到該公告".to_string()));
+    }
+
+    tracing::info!("{} 刪除公告 {}", admin.user.email, id);
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct TeachersQuery {
+    google_id: Option<String>,
+}
+
+// 登入帳號自己的登記；完整列表只在 /api/admin/teachers 提供給管理者
+async fn get_teachers(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Query(params): Query<TeachersQuery>,
+) -> Result<Json<Vec<Teacher>>, (axum::http::StatusCode, String)> {
+    if params.google_id.as_deref().is_some_and(|google_id| google_id != auth.google_id) {
+        return Err((axum::http::StatusCode::FORBIDDEN, "只能查詢自己帳號的登記".to_string()));
+    }
+
+    let account = account(&state, &auth).await?;
+    let teachers = state.teachers.list_by_user_id(account.id).await.map_err(|e| {
+        let error_msg = format!("讀取教師資料失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+
+    Ok(Json(teachers))
+}
+
+// 公開的登記不含帳號資料（email、google_id）
+fn redact_account(teacher: &mut Teacher) {
+    teacher.email.clear();
+    teacher.google_id = None;
+}
+
+// 新增登記需要登入，登記屬於登入的帳號並以 users.id 連結
+async fn create_teacher(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Json(mut teacher): Json<Teacher>
+) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
+    tracing::info!("接收到的教師數據: {:?}", teacher);
+
+    if teacher.google_id.as_deref().is_none_or(str::is_empty) {
+        teacher.google_id = Some(auth.google_id.clone());
+    }
+    // 新登記一律連結到登入的帳號，不採用請求中的 user_id
+    teacher.user_id = None;
+    auth.ensure_owns(&teacher)?;
+    teacher.user_id = Some(account(&state, &auth).await?.id);
+
+    validation::prepare_new_teacher(&mut teacher)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e))?;
+    ensure_round_open(state.teachers.as_ref(), teacher.year).await?;
+
+    // 將教師數據寫入資料庫
+    match state.teachers.create(teacher, &auth.google_id).await {
+        Ok(created) => {
+            tracing::info!("成功創建教師: {:?}", created);
+            state.matches.teachers_changed(&[&created]).await;
+            Ok(Json(created))
+        },
+        Err(e) => {
+            let error_msg = format!("創建教師失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MatchesQuery {
+    year: Option<i32>,
+    sort: Option<matcher::MatchSortKey>,
+    packing: Option<matcher::PackingObjective>,
+}
+
+// 配對結果的版本號，快取中任一分區重新計算後就會改變
+const MATCH_GENERATION_HEADER: &str = "x-match-generation";
+// packing=max_teachers 的搜尋超過節點上限時為 false，表示組合不保證調動人數最多
+const MATCH_EXHAUSTIVE_HEADER: &str = "x-match-exhaustive";
+
+async fn find_matches(
+    State(state): State<AppState>,
+    auth: Option<auth::AuthUser>,
+    Query(params): Query<MatchesQuery>,
+) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
+    tracing::info!("收到配對結果請求");
+
+    // 未指定年度時使用目前輪次，尚未建立任何輪次時配對所有教師
+    let year = match params.year {
+        Some(year) => Some(year),
+        None => state.teachers.current_round().await.ok().flatten().map(|round| round.year),
+    };
+
+    let config = state.config.matcher_config(params.sort, params.packing);
+
+    // 由配對快取取得結果，新出現的循環在計算時已保存並通知參與者
+    let cached = state.matches.matches(year, &config).await.map_err(|e| {
+        let error_msg = format!("讀取配對結果失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+
+    tracing::info!("配對結果數量: {}（第 {} 版）", cached.matches.len(), cached.generation);
+
+    // 登入者只在自己參與的循環中看得到其他參與者的 email 以便聯絡，google_id 一律不公開
+    let mine: Vec<i32> = match auth.and_then(|auth| auth.user_id) {
+        Some(user_id) => state.teachers.list_by_user_id(user_id)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|teacher| teacher.id)
+            .collect(),
+        None => Vec::new(),
+    };
+    let mut matches = cached.matches;
+    for result in &mut matches {
+        let participant = result.teachers.iter().any(|teacher| teacher.id.is_some_and(|id| mine.contains(&id)));
+        for teacher in &mut result.teachers {
+            if participant {
+                teacher.google_id = None;
+            } else {
+                redact_account(teacher);
+            }
+        }
+    }
+
+    let headers = [
+        (MATCH_GENERATION_HEADER, cached.generation.to_string()),
+        (MATCH_EXHAUSTIVE_HEADER, cached.exhaustive.to_string()),
+    ];
+    Ok((headers, Json(matches)))
+}
+
+#[derive(Deserialize)]
+struct GoogleLoginRequest {
+    token: String,
+}
+
+async fn google_login(
+    State(state): State<AppState>,
+    Json(payload): Json<GoogleLoginRequest>,
+) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+    let verifier = auth::token_verifier(&state)?;
+
+    // 驗證 Google Token
+    match verifier.verify(&payload.token).await {
+        Ok(user_info) => {
+            tracing::info!("Google 登入成功");
+
+            // 建立或更新帳號，並連結同一 email 下的既有登記
+            let login = NewUser {
+                google_sub: user_info.sub.clone(),
+                email: user_info.email.clone(),
+                name: Some(user_info.name.clone()),
+                picture: Some(user_info.picture.clone()).filter(|picture| !picture.is_empty()),
+            };
+            let save_error = |e: sqlx::Error| {
+                let error_msg = format!("保存帳號資料失敗: {}", e);
+                tracing::error!("{}", error_msg);
+                (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+            };
+            let mut user = state.users.upsert(&login).await.map_err(save_error)?;
+
+            if user.role != Role::SuperAdmin && state.config.auth.is_bootstrap_super_admin(&user.email) {
+                let update = RoleUpdate { role: Role::SuperAdmin, admin_county: None };
+                if let Some(promoted) = state.users.update_role(user.id, &update).await.map_err(save_error)? {
+                    tracing::info!("{} 依 SUPER_ADMIN_EMAILS 設為系統管理者", promoted.email);
+                    user = promoted;
+                }
+            }
+
+            // 在驗證成功後，獲取帳號下的所有教師資料
+            let teachers = state.teachers.list_by_user_id(user.id).await.unwrap_or_default();
+
+            // 如果有教師資料，選擇第一筆作為主要資料（向後兼容）
+            let primary_teacher = teachers.first().cloned();
+
+            // 以 users 表的帳號識別使用者，email 只是帳號的聯絡資料
+            Ok(Json(serde_json::json!({
+                "user": user,
+                "teacher": primary_teacher, // 向後兼容，只返回第一筆教師記錄
+                "teachers": teachers // 新增: 返回所有關聯的教師記錄
+            })))
+        }
+        Err(err) => {
+            tracing::error!("Google 登入失敗: {}", err);
+            Err((axum::http::StatusCode::UNAUTHORIZED, "驗證失敗".to_string()))
+        }
+    }
+}
+
+// 登入者在 users 表中的帳號，需先透過 /api/google-login 登入過
+async fn account(state: &AppState, auth: &auth::AuthUser) -> Result<User, (axum::http::StatusCode, String)> {
+    state.users.get_by_sub(&auth.sub)
+        .await
+        .map_err(|e| {
+            let error_msg = format!("讀取帳號資料失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+        })?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到帳號，請重新登入".to_string()))
+}
+
+// 目前登入的帳號與其下所有登記
+async fn get_me(
+    State(state): State<AppState>,
+    user: auth::AuthUser,
+) -> Result<Json<Me>, (axum::http::StatusCode, String)> {
+    let account = account(&state, &user).await?;
+    let teachers = state.teachers.list_by_user_id(account.id).await.map_err(|e| {
+        let error_msg = format!("讀取帳號資料失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+
+    Ok(Json(Me { user: account, teachers }))
+}
+
+#[derive(Deserialize)]
+struct SubjectsQuery {
+    level: Option<String>,
+}
+
+// 返回科目資料，可用 level 參數指定學制，預設為國小
+async fn get_subjects(
+    Query(params): Query<SubjectsQuery>,
+) -> Result<Json<std::sync::Arc<Vec<String>>>, (axum::http::StatusCode, String)> {
+    match params.level.as_deref().map(str::trim).filter(|level| !level.is_empty()) {
+        None => Ok(Json(db::get_elementary_subjects())),
+        Some(level) => db::get_subjects_by_level(level)
+            .map(Json)
+            .ok_or_else(|| (
+                axum::http::StatusCode::BAD_REQUEST,
+                format!("不支援的學制: {}", level),
+            )),
+    }
+}
+
+// 本人可讀取完整的登記；其他人看不到帳號資料，也看不到被隱藏的登記
+async fn get_teacher_handler(
+    State(state): State<AppState>,
+    auth: Option<auth::AuthUser>,
+    Path(teacher_id): Path<i32>,
+) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
+    let mut teacher = state.teachers.get(teacher_id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取教師資料失敗: {}", e)))?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+
+    if !auth.is_some_and(|auth| auth.owns(&teacher)) {
+        if teacher.hidden {
+            return Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()));
+        }
+        redact_account(&mut teacher);
+    }
+
+    Ok(with_etag(teacher))
+}
+
+// 以版本號作為 ETag，更新時以 If-Match 帶回
+fn with_etag(teacher: Teacher) -> impl IntoResponse {
+    let etag = format!("\"{}\"", teacher.version);
+    ([(axum::http::header::ETAG, etag)], Json(teacher))
+}
+
+// 解析 If-Match：* 代表不檢查版本，其餘為 ETag 中的版本號
+fn parse_if_match(headers: &axum::http::HeaderMap) -> Result<Option<i32>, (axum::http::StatusCode, String)> {
+    let value = headers
+        .get(axum::http::header::IF_MATCH)
+        .ok_or((
+            axum::http::StatusCode::PRECONDITION_REQUIRED,
+            "更新教師資料需要 If-Match 標頭（資料的版本號）".to_string(),
+        ))?
+        .to_str()
+        .unwrap_or("")
+        .trim();
+
+    if value == "*" {
+        return Ok(None);
+    }
+
+    value.trim_start_matches("W/")
+        .trim_matches('"')
+        .parse()
+        .map(Some)
+        .map_err(|_| (axum::http::StatusCode::BAD_REQUEST, format!("無效的 If-Match: {}", value)))
+}
+
+async fn update_teacher_handler(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(teacher_id): Path<i32>,
+    headers: axum::http::HeaderMap,
+    Json(mut teacher): Json<Teacher>,
+) -> Result<axum::response::Response, axum::response::Response> {
+    tracing::info!("接收到的教師更新數據: {:?}", teacher);
+
+    let expected_version = parse_if_match(&headers).map_err(IntoResponse::into_response)?;
+    
+    validation::validate_teacher(&teacher)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e).into_response())?;
+    validation::apply_school_directory(&mut teacher);
+
+    // 原本與修改後的年度都必須在開放中的輪次
+    let existing = state.teachers.get(teacher_id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取教師資料失敗: {}", e)).into_response())?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()).into_response())?;
+    auth.ensure_owns(&existing).map_err(IntoResponse::into_response)?;
+    // 尚未連結帳號的登記（例如批次匯入的）在本人修改時連結到登入的帳號
+    teacher.user_id = Some(account(&state, &auth).await.map_err(IntoResponse::into_response)?.id);
+    ensure_round_open(state.teachers.as_ref(), existing.year).await.map_err(IntoResponse::into_response)?;
+    if teacher.year != existing.year {
+        ensure_round_open(state.teachers.as_ref(), teacher.year).await.map_err(IntoResponse::into_response)?;
+    }
+    
+    // 更新教師數據
+    let result = state.teachers.update(teacher_id, teacher, expected_version, &auth.google_id).await;
+    teacher_update_response(&state, teacher_id, &existing, result).await
+}
+
+// 依更新結果檢查循環、發布事件並產生回應，教師本人與管理者修改時共用
+async fn teacher_update_response(
+    state: &AppState,
+    teacher_id: i32,
+    existing: &Teacher,
+    result: Result<db::UpdateOutcome, sqlx::Error>,
+) -> Result<axum::response::Response, axum::response::Response> {
+    match result {
+        Ok(db::UpdateOutcome::Updated(updated)) => {
+            tracing::info!("成功更新教師: {:?}", updated);
+            state.matches.invalidate_cycles(&updated).await;
+            state.matches.teachers_changed(&[existing, &updated]).await;
+            Ok(with_etag(updated).into_response())
+        },
+        Ok(db::UpdateOutcome::Stale(current)) => {
+            // 資料已被其他人修改，返回目前內容讓前端重新套用
+            tracing::warn!("教師 {} 的版本已變更，目前版本為 {}", teacher_id, current.version);
+            Err((axum::http::StatusCode::PRECONDITION_FAILED, with_etag(current)).into_response())
+        },
+        Err(e) => {
+            let error_msg = format!("更新教師失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            
+            // 區分不同類型的錯誤
+            match e {
+                sqlx::Error::RowNotFound => {
+                    Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()).into_response())
+                },
+                _ => {
+                    Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response())
+                }
+            }
+        }
+    }
+}
+
+async fn delete_teacher_handler(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(teacher_id): Path<i32>,
+) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
+    tracing::info!("請求刪除教師 ID: {}", teacher_id);
+
+    let existing = state.teachers.get(teacher_id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取教師資料失敗: {}", e)))?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    auth.ensure_owns(&existing)?;
+    
+    match state.teachers.delete(teacher_id, &auth.google_id).await {
+        Ok(_) => {
+            tracing::info!("成功刪除教師 ID: {}", teacher_id);
+            state.matches.invalidate_cycles(&existing).await;
+            state.matches.teachers_changed(&[&existing]).await;
+            Ok(axum::http::StatusCode::NO_CONTENT)
+        },
+        Err(e) => {
+            let error_msg = format!("刪除教師失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            
+            // 區分不同類型的錯誤
+            match e {
+                sqlx::Error::RowNotFound => {
+                    Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))
+                },
+                _ => {
+                    Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AdminTeachersQuery {
+    year: Option<i32>,
+    county: Option<String>,
+    #[serde(default)]
+    include_hidden: bool,
+}
+
+// 管理者的登記列表，縣市管理者只會看到所屬縣市
+async fn admin_list_teachers(
+    State(state): State<AppState>,
+    admin: auth::AdminUser,
+    Query(params): Query<AdminTeachersQuery>,
+) -> Result<Json<Vec<Teacher>>, (axum::http::StatusCode, String)> {
+    let requested = params.county.as_deref().map(str::trim).filter(|county| !county.is_empty());
+    let county = admin.scoped_county(requested)?;
+
+    state.teachers.list_filtered(params.year, county, params.include_hidden)
+        .await
+        .map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取教師資料失敗: {}", e)))
+}
+
+// 管理者修改登記，不受輪次開放期間限制；有 If-Match 時同樣檢查版本
+async fn admin_update_teacher_handler(
+    State(state): State<AppState>,
+    admin: auth::AdminUser,
+    Path(teacher_id): Path<i32>,
+    headers: axum::http::HeaderMap,
+    Json(mut teacher): Json<Teacher>,
+) -> Result<axum::response::Response, axum::response::Response> {
+    let expected_version = if headers.contains_key(axum::http::header::IF_MATCH) {
+        parse_if_match(&headers).map_err(IntoResponse::into_response)?
+    } else {
+        None
+    };
+
+    validation::validate_teacher(&teacher)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e).into_response())?;
+    validation::apply_school_directory(&mut teacher);
+
+    // 原本與修改後的縣市都必須在管理範圍內
+    let existing = state.teachers.get(teacher_id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取教師資料失敗: {}", e)).into_response())?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()).into_response())?;
+    admin.ensure_county(&existing.current_county).map_err(IntoResponse::into_response)?;
+    admin.ensure_county(&teacher.current_county).map_err(IntoResponse::into_response)?;
+    // 帳號連結只由本人登入或修改時建立
+    teacher.user_id = None;
+
+    tracing::info!("管理者 {} 修改教師 {}", admin.user.email, teacher_id);
+    let result = state.teachers.update(teacher_id, teacher, expected_version, &admin.user.email).await;
+    teacher_update_response(&state, teacher_id, &existing, result).await
+}
+
+async fn admin_hide_teacher(
+    State(state): State<AppState>,
+    admin: auth::AdminUser,
+    Path(teacher_id): Path<i32>,
+) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
+    set_teacher_hidden(&state, &admin, teacher_id, true).await
+}
+
+async fn admin_unhide_teacher(
+    State(state): State<AppState>,
+    admin: auth::AdminUser,
+    Path(teacher_id): Path<i32>,
+) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
+    set_teacher_hidden(&state, &admin, teacher_id, false).await
+}
+
+// 隱藏的登記不參與配對也不出現在公開列表，但保留資料，可再恢復
+async fn set_teacher_hidden(
+    state: &AppState,
+    admin: &auth::AdminUser,
+    teacher_id: i32,
+    hidden: bool,
+) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        let error_msg = format!("更新教師隱藏狀態失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    };
+
+    let existing = state.teachers.get(teacher_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    admin.ensure_county(&existing.current_county)?;
+
+    let updated = state.teachers.set_hidden(teacher_id, hidden, &admin.user.email)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    tracing::info!("管理者 {} 將教師 {} 設為{}", admin.user.email, teacher_id, if hidden { "隱藏" } else { "公開" });
+
+    if hidden {
+        state.matches.invalidate_cycles(&updated).await;
+    }
+    state.matches.teachers_changed(&[&updated]).await;
+    Ok(Json(updated))
+}
+
+async fn get_users_handler(
+    State(state): State<AppState>,
+    _admin: auth::SuperAdmin,
+) -> Result<Json<Vec<User>>, (axum::http::StatusCode, String)> {
+    state.users.list()
+        .await
+        .map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取帳號資料失敗: {}", e)))
+}
+
+// 設定帳號權限，縣市管理者必須指定管理的縣市
+async fn update_user_role_handler(
+    State(state): State<AppState>,
+    auth::SuperAdmin(admin): auth::SuperAdmin,
+    Path(user_id): Path<i32>,
+    Json(mut update): Json<RoleUpdate>,
+) -> Result<Json<User>, (axum::http::StatusCode, String)> {
+    update.admin_county = update.admin_county
+        .map(|county| county.trim().to_string())
+        .filter(|county| !county.is_empty());
+    if update.role == Role::CountyAdmin && update.admin_county.is_none() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "縣市管理者必須指定 admin_county".to_string()));
+    }
+
+    let user = state.users.update_role(user_id, &update)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("更新帳號權限失敗: {}", e)))?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該帳號".to_string()))?;
+
+    tracing::info!("{} 將 {} 的權限設為 {}", admin.user.email, user.email, user.role.as_str());
+    Ok(Json(user))
+}
+
+// 教師登記的異動紀錄，已刪除的登記仍可查詢；只有登記所屬的帳號或管理者可以查看
+async fn teacher_history_handler(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(teacher_id): Path<i32>,
+) -> Result<Json<Vec<TeacherHistoryEntry>>, (axum::http::StatusCode, String)> {
+    let history = state.teachers.history(teacher_id).await.map_err(|e| {
+        let error_msg = format!("讀取異動紀錄失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+
+    // 以最新的快照判斷登記屬於哪個帳號與縣市
+    let latest = history.last()
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    auth.ensure_owns_or_admin(&state, &latest.snapshot).await?;
+    Ok(Json(history))
+}
+
+#[derive(Deserialize)]
+struct SchoolSearchQuery {
+    q: Option<String>,
+    county: Option<String>,
+    district: Option<String>,
+    level: Option<String>,
+    limit: Option<usize>,
+}
+
+// 學校名錄搜尋，供前端自動完成使用
+async fn search_schools(Query(params): Query<SchoolSearchQuery>) -> Json<Vec<School>> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let schools = db::search_schools(
+        params.q.as_deref().unwrap_or(""),
+        params.county.as_deref().filter(|county| !county.is_empty()),
+        params.district.as_deref().filter(|district| !district.is_empty()),
+        params.level.as_deref().filter(|level| !level.is_empty()),
+        limit,
+    );
+    Json(schools)
+}
+
+#[derive(Deserialize)]
+struct GraphExportQuery {
+    year: i32,
+    subject: String,
+    level: Option<String>,
+    format: Option<graph_export::GraphFormat>,
+}
+
+// 匯出指定年度/科目分區的偏好圖，供除錯配對結果使用
+async fn export_preference_graph(
+    State(state): State<AppState>,
+    _admin: auth::SuperAdmin,
+    Query(params): Query<GraphExportQuery>,
+) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
+    let all_teachers = state.teachers.list_visible().await.map_err(|e| {
+        let error_msg = format!("讀取教師資料失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+
+    let level = params.level.unwrap_or_else(|| model::SCHOOL_LEVEL_ELEMENTARY.to_string());
+    let graph = matcher::partition_graph(
+        &all_teachers,
+        params.year,
+        &level,
+        &params.subject,
+        subject::SubjectCompatibility::bundled(),
+        &state.config.matcher_config(None, None),
+    );
+
+    let format = params.format.unwrap_or_default();
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, format.content_type())],
+        graph_export::export_graph(&graph, format),
+    ))
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// 以 CSV 批次匯入教師登記，dry_run=true 時只返回檢查結果
+async fn import_teachers_handler(
+    State(state): State<AppState>,
+    _admin: auth::SuperAdmin,
+    Query(params): Query<ImportQuery>,
+    body: String,
+) -> Result<(axum::http::StatusCode, Json<import::ImportReport>), (axum::http::StatusCode, String)> {
+    let report = import::import_roster(state.teachers.as_ref(), &body, params.dry_run)
+        .await
+        .map_err(|e| {
+            let error_msg = format!("匯入教師失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            match e {
+                import::ImportError::InvalidRoster(_) => (axum::http::StatusCode::BAD_REQUEST, error_msg),
+                import::ImportError::Database(_) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg),
+            }
+        })?;
+    if !report.created.is_empty() {
+        let created: Vec<&Teacher> = report.created.iter().collect();
+        state.matches.teachers_changed(&created).await;
+    }
+
+    let status = if report.has_errors() && !report.dry_run {
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        axum::http::StatusCode::OK
+    };
+    Ok((status, Json(report)))
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    year: Option<i32>,
+    county: Option<String>,
+    format: Option<export::ExportFormat>,
+    sort: Option<matcher::MatchSortKey>,
+    packing: Option<matcher::PackingObjective>,
+}
+
+impl ExportQuery {
+    fn county(&self) -> Option<&str> {
+        self.county.as_deref().map(str::trim).filter(|county| !county.is_empty())
+    }
+
+    fn file_stem(&self, kind: &str) -> String {
+        match self.year {
+            Some(year) => format!("{}-{}", kind, year),
+            None => kind.to_string(),
+        }
+    }
+}
+
+fn export_error(e: String) -> (axum::http::StatusCode, String) {
+    tracing::error!("{}", e);
+    (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e)
+}
+
+// 匯出教師登記資料（CSV/XLSX），可依年度與目前縣市篩選，縣市管理者只能匯出所屬縣市
+async fn export_teachers_handler(
+    State(state): State<AppState>,
+    admin: auth::AdminUser,
+    Query(params): Query<ExportQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+    let county = admin.scoped_county(params.county())?;
+    let teachers = state.teachers.list_filtered(params.year, county, false)
+        .await
+        .map_err(|e| export_error(format!("讀取教師資料失敗: {}", e)))?;
+
+    let rows = teachers.into_iter().map(|teacher| report::registration_row(&teacher));
+    export::spreadsheet_response(rows, params.format.unwrap_or_default(), &params.file_stem("teachers"))
+        .map_err(export_error)
+}
+
+// 匯出配對結果，每位參與者一列；指定縣市時保留有人調出或調入該縣市的完整循環
+// 縣市管理者一律套用所屬縣市
+async fn export_matches_handler(
+    State(state): State<AppState>,
+    admin: auth::AdminUser,
+    Query(params): Query<ExportQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+    let county = admin.scoped_county(params.county())?;
+    // 循環會跨縣市，因此只依年度篩選配對對象，縣市條件套用在結果上
+    let config = state.config.matcher_config(params.sort, params.packing);
+    let mut matches = state.matches.matches(params.year, &config)
+        .await
+        .map_err(|e| export_error(format!("讀取配對結果失敗: {}", e)))?
+        .matches;
+
+    if let Some(county) = county {
+        matches.retain(|result| result.teachers.iter().any(|t| t.current_county == county));
+    }
+
+    let rows = matches.into_iter()
+        .enumerate()
+        .flat_map(|(cycle_index, result)| report::cycle_participant_rows(cycle_index, &result));
+    export::spreadsheet_response(rows, params.format.unwrap_or_default(), &params.file_stem("matches"))
+        .map_err(export_error)
+}
+
+// 檢查年度對應的輪次是否開放登記
+async fn ensure_round_open(teachers: &dyn TeacherRepository, year: i32) -> Result<(), (axum::http::StatusCode, String)> {
+    let round = teachers.round(year).await.map_err(|e| {
+        let error_msg = format!("讀取輪次資料失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    })?;
+
+    validation::ensure_round_open(round.as_ref(), year)
+        .map_err(|e| (axum::http::StatusCode::FORBIDDEN, e))
+}
+
+async fn get_rounds(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Round>>, (axum::http::StatusCode, String)> {
+    state.teachers.rounds()
+        .await
+        .map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取輪次資料失敗: {}", e)))
+}
+
+// 目前輪次，沒有開放中或已截止的輪次時返回 404
+async fn get_current_round(
+    State(state): State<AppState>,
+) -> Result<Json<Round>, (axum::http::StatusCode, String)> {
+    state.teachers.current_round()
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取輪次資料失敗: {}", e)))?
+        .map(Json)
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "目前沒有進行中的輪次".to_string()))
+}
+
+async fn create_round_handler(
+    State(state): State<AppState>,
+    _admin: auth::SuperAdmin,
+    Json(round): Json<NewRound>,
+) -> Result<Json<Round>, (axum::http::StatusCode, String)> {
+    validation::validate_round_dates(round.registration_opens_at, round.registration_closes_at)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e))?;
+
+    let year = round.year;
+    let existing = state.teachers.round(year)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取輪次資料失敗: {}", e)))?;
+    if existing.is_some() {
+        return Err((axum::http::StatusCode::CONFLICT, format!("{} 年度輪次已存在", year)));
+    }
+
+    match state.teachers.create_round(round).await {
+        Ok(created) => {
+            tracing::info!("建立 {} 年度輪次", created.year);
+            Ok(Json(created))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err((axum::http::StatusCode::CONFLICT, format!("{} 年度輪次已存在", year)))
+        }
+        Err(e) => {
+            let error_msg = format!("建立輪次失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+// 更新輪次狀態與登記期間，狀態只能依 draft -> open -> closed -> archived 前進
+async fn update_round_handler(
+    State(state): State<AppState>,
+    _admin: auth::SuperAdmin,
+    Path(year): Path<i32>,
+    Json(update): Json<RoundUpdate>,
+) -> Result<Json<Round>, (axum::http::StatusCode, String)> {
+    let current = state.teachers.round(year)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取輪次資料失敗: {}", e)))?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, format!("找不到 {} 年度輪次", year)))?;
+
+    // 未提供的時間沿用目前的設定，以更新後的結果檢查先後順序
+    validation::validate_round_dates(
+        update.registration_opens_at.or(current.registration_opens_at),
+        update.registration_closes_at.or(current.registration_closes_at),
+    )
+    .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e))?;
+
+    if !current.state.can_transition_to(update.state) {
+        return Err((
+            axum::http::StatusCode::CONFLICT,
+            format!("輪次狀態不能從 {} 改為 {}", current.state.as_str(), update.state.as_str()),
+        ));
+    }
+
+    match state.teachers.update_round(year, update).await {
+        Ok(updated) => {
+            tracing::info!("{} 年度輪次更新為 {}", year, updated.state.as_str());
+            Ok(Json(updated))
+        }
+        Err(e) => {
+            let error_msg = format!("更新輪次失敗: {}", e);
+            tracing::error!("{}", error_msg);
+            Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg))
+        }
+    }
+}
+
+// 將教師登記複製到下一個開放中的輪次，沿用原本的代號與志願；登記所屬的帳號或管理者可以操作
+async fn copy_to_next_round_handler(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(teacher_id): Path<i32>,
+) -> Result<Json<Teacher>, (axum::http::StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        let error_msg = format!("複製登記失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    };
+
+    let source = state.teachers.get(teacher_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    let actor = match auth.ensure_owns_or_admin(&state, &source).await? {
+        Some(admin) => admin.user.email,
+        None => auth.google_id.clone(),
+    };
+
+    let next_round = state.teachers.next_open_round(source.year)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::CONFLICT, format!("{} 年度之後沒有開放中的輪次", source.year)))?;
+    validation::ensure_round_open(Some(&next_round), next_round.year)
+        .map_err(|e| (axum::http::StatusCode::FORBIDDEN, e))?;
+
+    // 同一帳號在下一輪已有相同學制與科目的登記時不重複建立
+    if let Some(google_id) = source.google_id.as_deref() {
+        let existing = state.teachers.list_by_google_id(google_id).await.map_err(db_error)?;
+        if existing.iter().any(|t| {
+            t.year == next_round.year && t.school_level == source.school_level && t.subject == source.subject
+        }) {
+            return Err((
+                axum::http::StatusCode::CONFLICT,
+                format!("{} 年度已有相同科目的登記", next_round.year),
+            ));
+        }
+    }
+
+    let mut teacher = Teacher {
+        id: None,
+        year: next_round.year,
+        created_at: None,
+        ..source
+    };
+    // 科目清單或學校名錄可能在兩輪之間更新
+    validation::validate_teacher(&teacher)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, e))?;
+    validation::apply_school_directory(&mut teacher);
+
+    let created = state.teachers.create(teacher, &actor).await.map_err(db_error)?;
+    tracing::info!("教師 {} 的登記已複製到 {} 年度", teacher_id, next_round.year);
+    state.matches.teachers_changed(&[&created]).await;
+    Ok(Json(created))
+}
+
+#[derive(Deserialize)]
+struct NotificationsQuery {
+    google_id: Option<String>,
+}
+
+// 登入帳號下所有登記的通知，最新的在前；指定其他帳號時拒絕
+async fn get_notifications(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Query(params): Query<NotificationsQuery>,
+) -> Result<Json<Vec<Notification>>, (axum::http::StatusCode, String)> {
+    if params.google_id.as_deref().is_some_and(|google_id| google_id != auth.google_id) {
+        return Err((axum::http::StatusCode::FORBIDDEN, "只能讀取自己帳號的通知".to_string()));
+    }
+
+    state.notifications.list_for_account(&auth.google_id)
+        .await
+        .map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取通知失敗: {}", e)))
+}
+
+async fn mark_notification_read(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(notification_id): Path<i32>,
+) -> Result<Json<Notification>, (axum::http::StatusCode, String)> {
+    let notification = state.notifications.get(notification_id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取通知失敗: {}", e)))?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該通知".to_string()))?;
+    if notification.google_id.as_deref() != Some(auth.google_id.as_str()) {
+        return Err((axum::http::StatusCode::FORBIDDEN, "只能修改自己帳號的通知".to_string()));
+    }
+
+    match state.notifications.mark_read(notification_id).await {
+        Ok(notification) => Ok(Json(notification)),
+        Err(sqlx::Error::RowNotFound) => Err((axum::http::StatusCode::NOT_FOUND, "找不到該通知".to_string())),
+        Err(e) => Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("更新通知失敗: {}", e))),
+    }
+}
+
+#[derive(Deserialize)]
+struct AcceptCycleRequest {
+    teacher_id: i32,
+}
+
+// 參與者確認接受循環，全員確認後通知所有參與者；teacher_id 必須是登入帳號的登記
+async fn accept_cycle_handler(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(cycle_id): Path<i32>,
+    Json(payload): Json<AcceptCycleRequest>,
+) -> Result<Json<MatchCycle>, (axum::http::StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        let error_msg = format!("確認循環失敗: {}", e);
+        tracing::error!("{}", error_msg);
+        (axum::http::StatusCode::INTERNAL_SERVER_ERROR, error_msg)
+    };
+
+    let teacher = state.teachers.get(payload.teacher_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    auth.ensure_owns(&teacher)?;
+
+    let cycle = state.notifications.cycle(cycle_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該循環".to_string()))?;
+
+    if !cycle.teacher_ids.contains(&payload.teacher_id) {
+        return Err((axum::http::StatusCode::FORBIDDEN, "該教師不在此循環中".to_string()));
+    }
+    if cycle.accepted_teacher_ids.contains(&payload.teacher_id) {
+        return Ok(Json(cycle));
+    }
+    if cycle.status != model::CYCLE_STATUS_ACTIVE {
+        return Err((axum::http::StatusCode::CONFLICT, format!("循環目前狀態為 {}，無法確認", cycle.status)));
+    }
+
+    let message = format!(
+        "您參與的 {} 人調動循環已獲所有參與者確認。",
+        cycle.teacher_ids.len()
+    );
+    state.notifications.accept_cycle(cycle_id, payload.teacher_id, &message)
+        .await
+        .map_err(db_error)?
+        .map(Json)
+        .ok_or((axum::http::StatusCode::CONFLICT, "循環已失效或已確認".to_string()))
+}
+
+// 取得登入帳號自己的登記，其他帳號的登記返回 403
+async fn owned_teacher(state: &AppState, auth: &auth::AuthUser, teacher_id: i32) -> Result<Teacher, (axum::http::StatusCode, String)> {
+    let teacher = state.teachers.get(teacher_id)
+        .await
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取教師資料失敗: {}", e)))?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string()))?;
+    auth.ensure_owns(&teacher)?;
+    Ok(teacher)
+}
+
+async fn get_notification_preferences(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(teacher_id): Path<i32>,
+) -> Result<Json<NotificationPreferences>, (axum::http::StatusCode, String)> {
+    // 通知設定只有登記所屬的帳號可以讀取與修改
+    owned_teacher(&state, &auth, teacher_id).await?;
+
+    match state.notifications.preferences(teacher_id).await {
+        Ok(preferences) => Ok(Json(preferences)),
+        Err(sqlx::Error::RowNotFound) => Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string())),
+        Err(e) => Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("讀取通知設定失敗: {}", e))),
+    }
+}
+
+async fn update_notification_preferences(
+    State(state): State<AppState>,
+    auth: auth::AuthUser,
+    Path(teacher_id): Path<i32>,
+    Json(preferences): Json<NotificationPreferences>,
+) -> Result<Json<NotificationPreferences>, (axum::http::StatusCode, String)> {
+    owned_teacher(&state, &auth, teacher_id).await?;
+
+    match state.notifications.update_preferences(teacher_id, &preferences).await {
+        Ok(()) => Ok(Json(preferences)),
+        Err(sqlx::Error::RowNotFound) => Err((axum::http::StatusCode::NOT_FOUND, "找不到該教師資料".to_string())),
+        Err(e) => Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("更新通知設定失敗: {}", e))),
+    }
+}
+
+// 以 Bearer token 換取 /api/events 使用的短期票證，EventSource 無法設定標頭
+async fn issue_event_ticket(
+    State(state): State<AppState>,
+    user: auth::AuthUser,
+) -> Json<Value> {
+    let ticket = state.event_tickets.issue(user);
+    Json(serde_json::json!({ "ticket": ticket, "expires_in": auth::EVENT_TICKET_TTL.as_secs() }))
+}
+
+// 已登入使用者的即時配對事件（SSE）：與其登記有關的循環出現或失效時推送
+async fn match_events(
+    State(state): State<AppState>,
+    auth::EventStreamUser(user): auth::EventStreamUser,
+) -> axum::response::sse::Sse<impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>> {
+    use tokio_stream::StreamExt;
+
+    tracing::info!("{} 開始接收配對事件", user.google_id);
+    let google_id = user.google_id;
+    let stream = tokio_stream::wrappers::BroadcastStream::new(state.events.subscribe())
+        .filter_map(move |event| match event {
+            Ok(event) if event.involves(&google_id) => Some(event),
+            // 跟不上時略過遺失的事件，前端可重新查詢配對結果
+            _ => None,
+        })
+        .map(|event| {
+            Ok(axum::response::sse::Event::default()
+                .event(event.name())
+                .json_data(&event)
+                .unwrap_or_default())
+        });
+
+    axum::response::sse::Sse::new(stream).keep_alive(axum::response::sse::KeepAlive::default())
+}
@@ -0,0 +1,83 @@
+use crate::auth::{EventTickets, GoogleTokenVerifier, TokenVerifier};
+use crate::config::Config;
+use crate::events::EventBus;
+use crate::health::{DatabaseHealth, NoDatabase, PgDatabaseHealth};
+use crate::match_cache::MatchCache;
+use crate::match_service::{CachedMatchService, DirectMatchService, MatchService};
+use crate::repository::{
+    AnnouncementRepository, InMemoryAnnouncementRepository, InMemoryNotificationRepository, InMemoryTeacherRepository,
+    InMemoryUserRepository, NotificationRepository, PgAnnouncementRepository, PgNotificationRepository,
+    PgTeacherRepository, PgUserRepository, TeacherRepository, UserRepository,
+};
+use axum::extract::FromRef;
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+
+// axum 共用狀態；處理函式與擷取器只透過以下介面存取資料，
+// 測試時可換成記憶體中的實作而不需要資料庫
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub teachers: Arc<dyn TeacherRepository>,
+    pub users: Arc<dyn UserRepository>,
+    pub notifications: Arc<dyn NotificationRepository>,
+    pub announcements: Arc<dyn AnnouncementRepository>,
+    pub database: Arc<dyn DatabaseHealth>,
+    pub matches: Arc<dyn MatchService>,
+    // 配對事件，SSE 由此訂閱；正式環境與配對快取、重新配對的背景工作共用
+    pub events: EventBus,
+    // 以 Bearer token 換取的 SSE 票證
+    pub event_tickets: EventTickets,
+    // 未設定 GOOGLE_CLIENT_ID 時為 None，需要登入的功能返回 503
+    pub verifier: Option<Arc<dyn TokenVerifier>>,
+}
+
+impl AppState {
+    // cache 同時交給重新配對的背景工作，讓登記變動後的循環不必等到查詢時才計算
+    pub fn postgres(pool: Pool<Postgres>, config: Arc<Config>, cache: Arc<MatchCache>) -> Self {
+        let verifier = config.auth.google_client_id
+            .as_deref()
+            .map(|client_id| Arc::new(GoogleTokenVerifier::new(client_id)) as Arc<dyn TokenVerifier>);
+
+        AppState {
+            teachers: Arc::new(PgTeacherRepository::new(pool.clone())),
+            users: Arc::new(PgUserRepository::new(pool.clone())),
+            notifications: Arc::new(PgNotificationRepository::new(pool.clone())),
+            announcements: Arc::new(PgAnnouncementRepository::new(pool.clone())),
+            events: cache.events().clone(),
+            event_tickets: EventTickets::default(),
+            matches: Arc::new(CachedMatchService::new(pool.clone(), cache)),
+            database: Arc::new(PgDatabaseHealth::new(pool)),
+            verifier,
+            config,
+        }
+    }
+
+    // 不需要資料庫的狀態：所有資料存放在記憶體中，配對每次重新計算
+    pub fn in_memory(config: Config, teachers: Arc<InMemoryTeacherRepository>) -> Self {
+        let events = EventBus::default();
+        AppState {
+            users: Arc::new(InMemoryUserRepository::new(teachers.clone())),
+            notifications: Arc::new(InMemoryNotificationRepository::new(teachers.clone())),
+            announcements: Arc::new(InMemoryAnnouncementRepository::new()),
+            database: Arc::new(NoDatabase),
+            matches: Arc::new(DirectMatchService::new(teachers.clone(), events.clone())),
+            events,
+            event_tickets: EventTickets::default(),
+            teachers,
+            verifier: None,
+            config: Arc::new(config),
+        }
+    }
+
+    pub fn with_verifier(mut self, verifier: impl TokenVerifier + 'static) -> Self {
+        self.verifier = Some(Arc::new(verifier));
+        self
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
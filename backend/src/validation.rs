@@ -0,0 +1,163 @@
+use crate::db;
+use crate::model::{AnnouncementInput, Round, Teacher, SCHOOL_LEVELS};
+use chrono::{DateTime, Utc};
+
+// 新增與更新教師資料時共用的欄位檢查
+pub fn validate_teacher(teacher: &Teacher) -> Result<(), String> {
+    // 檢查必填欄位
+    if teacher.current_county.trim().is_empty() {
+        return Err("縣市不能為空".to_string());
+    }
+
+    if teacher.current_district.trim().is_empty() {
+        return Err("區域不能為空".to_string());
+    }
+
+    // 檢查學制與該學制的科目
+    let subjects = db::get_subjects_by_level(teacher.school_level.trim()).ok_or_else(|| {
+        format!("不支援的學制: {}，可選擇 {}", teacher.school_level, SCHOOL_LEVELS.join("/"))
+    })?;
+
+    if !subjects.iter().any(|subject| subject == teacher.subject.trim()) {
+        return Err(format!("{}沒有「{}」科目", teacher.school_level, teacher.subject));
+    }
+
+    // 有學校代碼且名錄帶有代碼時，檢查與所在縣市、區域及學制是否一致
+    if let Some(code) = teacher.school_code.as_deref().filter(|code| !code.trim().is_empty()) {
+        let schools = db::get_schools_by_code(code);
+        let Some(school) = schools.first() else {
+            if db::school_codes_available() {
+                return Err(format!("找不到學校代碼: {}", code));
+            }
+            return Ok(());
+        };
+
+        if school.county != teacher.current_county.trim() || school.district != teacher.current_district.trim() {
+            return Err(format!(
+                "學校 {} 位於{}{}，與填寫的{}{}不符",
+                school.name, school.county, school.district,
+                teacher.current_county, teacher.current_district
+            ));
+        }
+
+        if !schools.iter().any(|school| school.level == teacher.school_level.trim()) {
+            let levels: Vec<&str> = schools.iter().map(|school| school.level.as_str()).collect();
+            return Err(format!(
+                "學校 {} 為{}，與填寫的學制{}不符",
+                school.name, levels.join("/"), teacher.school_level
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// 有學校代碼時，以學校名錄的正式名稱為準，避免同一所學校有多種寫法
+pub fn apply_school_directory(teacher: &mut Teacher) {
+    match teacher.school_code.as_deref().map(str::trim) {
+        Some("") => teacher.school_code = None,
+        Some(code) => {
+            if let Some(school) = db::get_schools_by_code(code).into_iter().next() {
+                teacher.school_code = school.code;
+                teacher.current_school = school.name;
+            }
+        }
+        None => {}
+    }
+}
+
+// 新增教師前的完整檢查並補上預設值，POST /api/teachers 與批次匯入共用
+pub fn prepare_new_teacher(teacher: &mut Teacher) -> Result<(), String> {
+    validate_teacher(teacher)?;
+    apply_school_directory(teacher);
+
+    // 如果名稱為空，設置為預設值
+    if teacher.name.as_deref().is_none_or(str::is_empty) {
+        teacher.name = Some("Anonymous".to_string());
+    }
+
+    // 如果 display_id 為空，生成一個新的 display_id
+    if teacher.display_id.as_deref().is_none_or(str::is_empty) {
+        teacher.display_id = Some(db::generate_display_id(&teacher.current_county, &teacher.current_district));
+    }
+
+    // 確保 google_id 被正確處理
+    match &teacher.google_id {
+        Some(google_id) if !google_id.is_empty() => {
+            tracing::info!("Google ID 已提供: {}", google_id);
+            Ok(())
+        }
+        _ => Err("缺少 google_id，請重新登入".to_string()),
+    }
+}
+
+// 不會阻止儲存、但可能讓教師配對不到的問題
+pub fn teacher_warnings(teacher: &Teacher) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if !db::district_exists(&teacher.current_county, &teacher.current_district) {
+        warnings.push(format!(
+            "縣市區域資料中沒有{}{}",
+            teacher.current_county, teacher.current_district
+        ));
+    }
+
+    if teacher.target_counties.is_empty() {
+        warnings.push("未填寫調動志願，不會出現在配對結果中".to_string());
+    }
+
+    if teacher.target_counties.len() != teacher.target_districts.len() {
+        warnings.push(format!(
+            "志願縣市（{} 筆）與區域（{} 筆）數量不一致，未成對的志願會被忽略",
+            teacher.target_counties.len(),
+            teacher.target_districts.len()
+        ));
+    }
+
+    for (county, district) in teacher.target_counties.iter().zip(&teacher.target_districts) {
+        if county == &teacher.current_county {
+            warnings.push(format!("志願 {}{} 與目前縣市相同，同縣市不會配對", county, district));
+        } else if !db::district_exists(county, district) {
+            warnings.push(format!("縣市區域資料中沒有志願 {}{}", county, district));
+        }
+    }
+
+    warnings
+}
+
+// 只有開放中且在登記期間內的輪次可以新增或修改登記
+pub fn ensure_round_open(round: Option<&Round>, year: i32) -> Result<(), String> {
+    match round {
+        None => Err(format!("{} 年度尚未建立調動輪次", year)),
+        Some(round) if round.accepts_registrations(Utc::now()) => Ok(()),
+        Some(round) => Err(format!("{} 年度目前不開放登記（狀態: {}）", year, round.state.as_str())),
+    }
+}
+
+// 登記期間的開始時間必須早於截止時間
+pub fn validate_round_dates(opens_at: Option<DateTime<Utc>>, closes_at: Option<DateTime<Utc>>) -> Result<(), String> {
+    match (opens_at, closes_at) {
+        (Some(opens_at), Some(closes_at)) if opens_at >= closes_at => {
+            Err("登記開始時間必須早於截止時間".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+// 公告內容不可為空，過期時間必須晚於發布時間
+pub fn validate_announcement(input: &AnnouncementInput) -> Result<(), String> {
+    if input.body.trim().is_empty() {
+        return Err("公告內容不可為空".to_string());
+    }
+    if input.title.as_deref().is_some_and(|title| title.chars().count() > 100) {
+        return Err("公告標題不可超過 100 字".to_string());
+    }
+
+    // 未指定發布時間時由資料庫的檢查條件把關（新增時為現在，修改時為原本的發布時間）
+    match (input.publish_at, input.expire_at) {
+        (Some(publish_at), Some(expire_at)) if expire_at <= publish_at => {
+            Err("公告的過期時間必須晚於發布時間".to_string())
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,55 @@
+// 設定的環境變數覆寫與啟動時驗證
+use circlematch_api::config::Config;
+use circlematch_api::mailer::{SmtpSecurity, SmtpSettings};
+use std::collections::HashMap;
+
+fn load(vars: &[(&str, &str)]) -> Result<Config, Vec<String>> {
+    let mut vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    vars.entry("DATABASE_URL".to_string()).or_insert_with(|| "postgres://localhost/circlematch".to_string());
+    Config::default()
+        .with_env(|name| vars.get(name).cloned())
+        .map_err(|e| e.problems)
+}
+
+#[test]
+fn smtp_defaults_follow_the_security_mode() {
+    let config = load(&[("SMTP_HOST", "smtp.example.com"), ("SMTP_SECURITY", "STARTTLS")]).unwrap();
+    let settings = SmtpSettings::from_config(&config.mail).unwrap();
+    assert_eq!(settings.security, SmtpSecurity::StartTls);
+    assert_eq!(settings.port, 587);
+
+    let config = load(&[]).unwrap();
+    assert!(SmtpSettings::from_config(&config.mail).is_none());
+}
+
+#[test]
+fn rejects_unknown_smtp_security_and_bad_ports() {
+    let problems = load(&[
+        ("SMTP_HOST", "smtp.example.com"),
+        ("SMTP_SECURITY", "ssl"),
+        ("SMTP_PORT", "smtp"),
+    ])
+    .unwrap_err();
+    assert!(problems.iter().any(|p| p.contains("SMTP_SECURITY")), "{:?}", problems);
+    assert!(problems.iter().any(|p| p.contains("SMTP_PORT")), "{:?}", problems);
+}
+
+#[test]
+fn rejects_smtp_credentials_without_encryption() {
+    let problems = load(&[
+        ("SMTP_HOST", "smtp.example.com"),
+        ("SMTP_USERNAME", "mailer"),
+        ("SMTP_PASSWORD", "secret"),
+    ])
+    .unwrap_err();
+    assert!(problems.iter().any(|p| p.contains("明文")), "{:?}", problems);
+
+    let config = load(&[
+        ("SMTP_HOST", "smtp.example.com"),
+        ("SMTP_SECURITY", "tls"),
+        ("SMTP_USERNAME", "mailer"),
+        ("SMTP_PASSWORD", "secret"),
+    ])
+    .unwrap();
+    assert_eq!(SmtpSettings::from_config(&config.mail).unwrap().port, 465);
+}
@@ -0,0 +1,43 @@
+// Email 通知的信件內容與寄送失敗後的重試時間
+use chrono::{Duration, TimeZone, Utc};
+use circlematch_api::mailer::{next_attempt_at, render_email, retry_delay};
+use circlematch_api::model::{NOTIFICATION_MATCH_ACCEPTED, NOTIFICATION_MATCH_BROKEN, NOTIFICATION_MATCH_FOUND};
+
+#[test]
+fn renders_each_notification_kind() {
+    let (subject, body) = render_email(NOTIFICATION_MATCH_FOUND, "王小明", "找到 3 人循環", Some("https://circlematch.tw"));
+    assert_eq!(subject, "【CircleMatch】找到新的調動循環");
+    assert!(body.starts_with("王小明 老師您好："), "{}", body);
+    assert!(body.contains("找到 3 人循環"));
+    assert!(body.contains("按下「接受」"));
+    assert!(body.contains("網站連結：https://circlematch.tw"));
+
+    let (subject, body) = render_email(NOTIFICATION_MATCH_ACCEPTED, "王小明", "全員確認", None);
+    assert_eq!(subject, "【CircleMatch】調動循環已獲全員確認");
+    assert!(body.contains("教育局"));
+    assert!(!body.contains("網站連結"));
+
+    let (subject, _) = render_email(NOTIFICATION_MATCH_BROKEN, "王小明", "循環失效", None);
+    assert_eq!(subject, "【CircleMatch】您的調動循環已失效");
+
+    let (subject, body) = render_email("other", "王小明", "其他通知", None);
+    assert_eq!(subject, "【CircleMatch】通知");
+    assert!(body.contains("其他通知"));
+}
+
+#[test]
+fn retries_with_exponential_backoff() {
+    let delays: Vec<i64> = (1..=6).map(|attempts| retry_delay(attempts).num_minutes()).collect();
+    assert_eq!(delays, [1, 2, 4, 8, 16, 32]);
+    // 次數異常時仍落在 1 分鐘與上限之間
+    assert_eq!(retry_delay(0).num_minutes(), 1);
+    assert_eq!(retry_delay(50).num_minutes(), 512);
+}
+
+#[test]
+fn stops_retrying_after_the_last_attempt() {
+    let now = Utc.with_ymd_and_hms(2025, 9, 1, 8, 0, 0).unwrap();
+    assert_eq!(next_attempt_at(1, now), Some(now + Duration::minutes(1)));
+    assert_eq!(next_attempt_at(5, now), Some(now + Duration::minutes(16)));
+    assert_eq!(next_attempt_at(6, now), None);
+}
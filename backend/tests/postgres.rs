@@ -0,0 +1,783 @@
+// 對 Postgres 執行完整路由的整合測試。每個測試建立自己的 schema，以 init_db 建立資料表與測試資料，
+// 結束後刪除；登入以 StaticTokenVerifier 模擬，不需要連線到 Google。
+//
+// 設定 TEST_DATABASE_URL 時使用該資料庫（連不上時測試失敗）：
+//   TEST_DATABASE_URL=postgres://postgres@localhost:5432/circlematch cargo test --test postgres
+// 未設定時以本機的 initdb / pg_ctl 在暫存目錄啟動只供該測試使用的叢集，結束後停止並刪除。
+// 兩者都無法使用時（未安裝 Postgres，或以 root 執行而 initdb 拒絕）印出原因並略過。
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Request, StatusCode};
+use axum::Router;
+use circlematch_api::auth::{GoogleTokenInfo, StaticTokenVerifier};
+use circlematch_api::config::Config;
+use circlematch_api::db;
+use circlematch_api::match_cache::MatchCache;
+use circlematch_api::routes;
+use circlematch_api::state::AppState;
+use serde_json::{json, Value};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Pool, Postgres};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+const YEAR: i32 = 114;
+const ADMIN_EMAIL: &str = "admin@example.com";
+
+struct TestApp {
+    app: Router,
+    pool: Pool<Postgres>,
+    url: String,
+    schema: String,
+    // 未設定 TEST_DATABASE_URL 時自行啟動的叢集，放在最後以便在連線關閉後才停止
+    _cluster: Option<Cluster>,
+}
+
+// 在暫存目錄以 initdb 建立並以 pg_ctl 啟動的 Postgres，drop 時停止並刪除目錄
+struct Cluster {
+    pg_ctl: PathBuf,
+    dir: PathBuf,
+}
+
+impl Cluster {
+    fn start() -> Result<(Cluster, String), String> {
+        let bindir = postgres_bindir().ok_or("找不到 initdb（PATH 與 pg_config --bindir 中都沒有）")?;
+        let dir = std::env::temp_dir().join(format!("circlematch-pg-{}", uuid::Uuid::new_v4().simple()));
+        let data = dir.join("data");
+        let cluster = Cluster { pg_ctl: bindir.join("pg_ctl"), dir: dir.clone() };
+
+        run(Command::new(bindir.join("initdb"))
+            .arg("-D").arg(&data)
+            .args(["-U", "postgres", "-A", "trust", "--no-sync"]))?;
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| format!("無法取得可用的連接埠: {}", e))?
+            .port();
+        run(Command::new(&cluster.pg_ctl)
+            .arg("-D").arg(&data)
+            .arg("-l").arg(dir.join("postgres.log"))
+            .arg("-o").arg(format!("-p {} -k {} -c listen_addresses=127.0.0.1 -F", port, dir.display()))
+            .args(["-w", "start"]))?;
+
+        Ok((cluster, format!("postgres://postgres@127.0.0.1:{}/postgres", port)))
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        let _ = Command::new(&self.pg_ctl)
+            .arg("-D").arg(self.dir.join("data"))
+            .args(["-m", "immediate", "-w", "stop"])
+            .output();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+// initdb 所在目錄：先找 PATH，再找 pg_config --bindir（Debian 的套件不會放進 PATH）
+fn postgres_bindir() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let from_path = std::env::split_paths(&path).find(|dir| dir.join("initdb").is_file());
+    from_path.or_else(|| {
+        let output = Command::new("pg_config").arg("--bindir").output().ok()?;
+        let dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        dir.join("initdb").is_file().then_some(dir)
+    })
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let output = command.output().map_err(|e| format!("無法執行 {:?}: {}", command.get_program(), e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("{:?} 失敗: {}", command.get_program(), String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+fn account(sub: &str, email: &str) -> GoogleTokenInfo {
+    GoogleTokenInfo {
+        sub: sub.to_string(),
+        email: email.to_string(),
+        name: email.split('@').next().unwrap_or_default().to_string(),
+        picture: String::new(),
+    }
+}
+
+impl TestApp {
+    // 沒有可用的 Postgres 時返回 None，測試印出原因後略過
+    async fn start() -> Option<TestApp> {
+        let (url, cluster) = match std::env::var("TEST_DATABASE_URL").ok().filter(|url| !url.is_empty()) {
+            Some(url) => (url, None),
+            None => match Cluster::start() {
+                Ok((cluster, url)) => (url, Some(cluster)),
+                Err(e) => {
+                    eprintln!("略過 Postgres 整合測試：未設定 TEST_DATABASE_URL，且無法啟動暫存的 Postgres（{}）", e);
+                    return None;
+                }
+            },
+        };
+
+        let schema = format!("circlematch_test_{}", uuid::Uuid::new_v4().simple());
+        let admin = PgPoolOptions::new().max_connections(1).connect(&url).await.expect("無法連線到測試資料庫");
+        sqlx::query(&format!("CREATE SCHEMA {}", schema)).execute(&admin).await.unwrap();
+        admin.close().await;
+
+        let options = PgConnectOptions::from_str(&url).unwrap().options([("search_path", schema.as_str())]);
+        let pool = PgPoolOptions::new().max_connections(5).connect_with(options).await.unwrap();
+        db::init_db(&pool).await.expect("建立資料表失敗");
+
+        let mut config = Config::default();
+        config.database.url = Some(url.clone());
+        config.auth.super_admin_emails = vec![ADMIN_EMAIL.to_string()];
+
+        let verifier = StaticTokenVerifier::new()
+            .with_token("token-a", account("sub-a", "a@example.com"))
+            .with_token("token-b", account("sub-b", "b@example.com"))
+            // 與 token-a 相同 email 的另一個 Google 帳號
+            .with_token("token-a2", account("sub-a2", "a@example.com"))
+            .with_token("token-admin", account("sub-admin", ADMIN_EMAIL));
+        let state = AppState::postgres(pool.clone(), Arc::new(config), Arc::new(MatchCache::default()))
+            .with_verifier(verifier);
+
+        Some(TestApp { app: routes::router(state), pool, url, schema, _cluster: cluster })
+    }
+
+    async fn stop(self) {
+        self.pool.close().await;
+        let admin = PgPoolOptions::new().max_connections(1).connect(&self.url).await.unwrap();
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", self.schema)).execute(&admin).await.unwrap();
+    }
+
+    async fn send(&self, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+        (status, headers, body)
+    }
+
+    async fn get(&self, uri: &str) -> (StatusCode, HeaderMap, Value) {
+        self.send(Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    async fn json(&self, method: &str, uri: &str, body: &Value, headers: &[(header::HeaderName, &str)]) -> (StatusCode, HeaderMap, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        self.send(request.body(Body::from(body.to_string())).unwrap()).await
+    }
+
+    async fn login(&self, token: &str) -> Value {
+        let (status, _, login) = self.json("POST", "/api/google-login", &json!({ "token": token }), &[]).await;
+        assert_eq!(status, StatusCode::OK, "{}", login);
+        login
+    }
+
+    // 以 token 對應的帳號登入後新增登記
+    async fn create_teacher(&self, payload: &Value, token: &str) -> Value {
+        self.login(token).await;
+        let (name, value) = bearer(token);
+        let (status, _, created) = self.json("POST", "/api/teachers", payload, &[(name, &value)]).await;
+        assert_eq!(status, StatusCode::OK, "{}", created);
+        created
+    }
+
+    async fn matches(&self) -> (u64, Vec<Value>) {
+        let (status, headers, body) = self.get(&format!("/api/matches?year={}", YEAR)).await;
+        assert_eq!(status, StatusCode::OK);
+        let generation = headers["x-match-generation"].to_str().unwrap().parse().unwrap();
+        (generation, body.as_array().unwrap().clone())
+    }
+}
+
+fn bearer(token: &str) -> (header::HeaderName, String) {
+    (header::AUTHORIZATION, format!("Bearer {}", token))
+}
+
+// 國小體育在測試資料中沒有其他登記，配對結果只受測試新增的登記影響
+fn teacher(google_id: &str, from: (&str, &str), to: (&str, &str)) -> Value {
+    json!({
+        "email": google_id,
+        "google_id": google_id,
+        "year": YEAR,
+        "school_level": "國小",
+        "subject": "體育",
+        "current_county": from.0,
+        "current_district": from.1,
+        "current_school": format!("{}國小", from.1),
+        "target_counties": [to.0],
+        "target_districts": [to.1],
+    })
+}
+
+fn match_types(matches: &[Value]) -> Vec<&str> {
+    let mut types: Vec<&str> = matches.iter().map(|m| m["match_type"].as_str().unwrap()).collect();
+    types.sort();
+    types
+}
+
+#[tokio::test]
+async fn serves_reference_data_and_probes() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let (status, _, body) = app.get("/healthz").await;
+    assert_eq!((status, body), (StatusCode::OK, json!("ok")));
+
+    let (status, _, body) = app.get("/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["database"], true);
+
+    let (status, _, districts) = app.get("/api/districts").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(districts.as_array().is_some_and(|counties| !counties.is_empty()));
+
+    let (status, _, subjects) = app.get("/api/subjects").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(subjects.as_array().unwrap().contains(&json!("一般")));
+
+    let (status, _, subjects) = app.get("/api/subjects?level=高中").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(subjects.as_array().unwrap().contains(&json!("物理")));
+
+    let (status, _, _) = app.get("/api/subjects?level=大學").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _, rounds) = app.get("/api/rounds/current").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rounds["year"], YEAR);
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn teacher_lifecycle_is_versioned_and_recorded() {
+    let Some(app) = TestApp::start().await else { return };
+    let payload = teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區"));
+
+    let created = app.create_teacher(&payload, "token-a").await;
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(created["version"], 1);
+    assert!(created["display_id"].as_str().is_some_and(|display_id| display_id.starts_with("臺中市西屯區")));
+
+    // 列表只提供登入帳號自己的登記
+    let listed = |uri: &str, token: &str| {
+        let (name, value) = bearer(token);
+        Request::get(uri).header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.get("/api/teachers").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, mine) = app.send(listed("/api/teachers?google_id=a@example.com", "token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mine.as_array().unwrap().len(), 1);
+    let (status, _, _) = app.send(listed("/api/teachers?google_id=a@example.com", "token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 公開讀取時不含帳號資料
+    let uri = format!("/api/teachers/{}", id);
+    let (status, headers, public) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ETAG], "\"1\"");
+    assert_eq!(public["email"], "");
+    assert!(public["google_id"].is_null());
+    let (_, _, own) = app.send(listed(&uri, "token-a")).await;
+    assert_eq!(own["email"], "a@example.com");
+
+    let mut update = payload.clone();
+    update["target_districts"] = json!(["鼓山區"]);
+    let (status, _, _) = app.json("PUT", &uri, &update, &[(header::IF_MATCH, "\"1\"")]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 只有登記所屬的帳號可以修改或刪除
+    let owner = bearer("token-a");
+    let other = bearer("token-b");
+    let (status, _, _) = app.json("PUT", &uri, &update, &[(other.0.clone(), &other.1), (header::IF_MATCH, "\"1\"")]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, _) = app.json("PUT", &uri, &update, &[(owner.0.clone(), &owner.1)]).await;
+    assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+
+    let (status, headers, updated) = app.json("PUT", &uri, &update, &[(owner.0.clone(), &owner.1), (header::IF_MATCH, "\"1\"")]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ETAG], "\"2\"");
+    assert_eq!(updated["target_districts"], json!(["鼓山區"]));
+
+    // 以舊版本再次修改時返回目前內容
+    let (status, _, current) = app.json("PUT", &uri, &payload, &[(owner.0.clone(), &owner.1), (header::IF_MATCH, "\"1\"")]).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(current["version"], 2);
+
+    // 通知設定同樣只有本人可以讀取與修改
+    let preferences_uri = format!("{}/notification-preferences", uri);
+    let preferences = json!({ "email": false, "match_found": true, "match_accepted": true, "match_broken": false });
+    let (status, _, _) = app.get(&preferences_uri).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = app.json("PUT", &preferences_uri, &preferences, &[(other.0.clone(), &other.1)]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = app.json("PUT", &preferences_uri, &preferences, &[(owner.0.clone(), &owner.1)]).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, stored) = app.send(Request::get(&preferences_uri).header(&owner.0, &owner.1).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stored, preferences);
+
+    let delete = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::delete(&uri).header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.send(Request::delete(&uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = app.send(delete("token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = app.send(delete("token-a")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) = app.send(delete("token-a")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 異動紀錄只有登記所屬的帳號與管理者可以查看，操作者是登入的帳號
+    let history_of = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::get(format!("{}/history", uri)).header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.get(&format!("{}/history", uri)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = app.send(history_of("token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    app.login("token-admin").await;
+    let (status, _, _) = app.send(history_of("token-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, history) = app.send(history_of("token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = history.as_array().unwrap();
+    let actions: Vec<&str> = entries.iter().map(|entry| entry["action"].as_str().unwrap()).collect();
+    assert_eq!(actions.len(), 3);
+    for action in ["create", "update", "delete"] {
+        assert!(actions.contains(&action), "{:?}", actions);
+    }
+    assert!(entries.iter().all(|entry| entry["actor"] == "a@example.com"), "{}", history);
+
+    // 重新啟動時保留異動歷史，新登記不沿用已有歷史的 ID
+    db::init_db(&app.pool).await.expect("重新建立資料表失敗");
+    let (_, _, history) = app.send(history_of("token-a")).await;
+    assert_eq!(history.as_array().unwrap().len(), 3);
+    let recreated = app.create_teacher(&payload, "token-a").await;
+    assert!(recreated["id"].as_i64().unwrap() > id);
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn restarting_keeps_registrations_and_history() {
+    let Some(app) = TestApp::start().await else { return };
+    let created = app.create_teacher(&teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區")), "token-a").await;
+    app.login("token-admin").await;
+    let list = || {
+        let (name, value) = bearer("token-admin");
+        Request::get("/api/admin/teachers").header(name, value).body(Body::empty()).unwrap()
+    };
+    let (_, _, before) = app.send(list()).await;
+
+    // 伺服器重啟時再次執行 init_db，登記、歷史與帳號都要保留，測試資料也不會重複加入
+    db::init_db(&app.pool).await.expect("再次初始化失敗");
+
+    let (_, _, after) = app.send(list()).await;
+    assert_eq!(after, before);
+    let uri = format!("/api/teachers/{}", created["id"]);
+    let (status, _, kept) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(kept["version"], 1);
+
+    let history: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM teacher_history WHERE teacher_id = $1")
+        .bind(created["id"].as_i64().unwrap() as i32)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(history, 1);
+
+    // 新登記不會沿用既有的 ID
+    let next = app.create_teacher(&teacher("b@example.com", ("高雄市", "苓雅區"), ("臺中市", "西屯區")), "token-b").await;
+    assert!(next["id"].as_i64().unwrap() > created["id"].as_i64().unwrap());
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn owners_copy_registrations_into_the_next_round() {
+    let Some(app) = TestApp::start().await else { return };
+    let created = app.create_teacher(&teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區")), "token-a").await;
+    let uri = format!("/api/teachers/{}/next-round", created["id"]);
+    let next_round = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::post(&uri).header(name, value).body(Body::empty()).unwrap()
+    };
+
+    // 下一輪尚未開放時無法複製
+    app.login("token-admin").await;
+    let admin = bearer("token-admin");
+    let (status, _, round) = app.json("POST", "/api/admin/rounds", &json!({ "year": YEAR + 1 }), &[(admin.0.clone(), &admin.1)]).await;
+    assert_eq!(status, StatusCode::OK, "{}", round);
+    let (status, _, _) = app.send(next_round("token-a")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let round_uri = format!("/api/admin/rounds/{}", YEAR + 1);
+    let (status, _, round) = app.json("PUT", &round_uri, &json!({ "state": "open" }), &[(admin.0, &admin.1)]).await;
+    assert_eq!(status, StatusCode::OK, "{}", round);
+
+    let (status, _, _) = app.send(Request::post(&uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    app.login("token-b").await;
+    let (status, _, _) = app.send(next_round("token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 本人複製的登記沿用代號與帳號，異動紀錄的操作者是本人
+    let (status, _, copied) = app.send(next_round("token-a")).await;
+    assert_eq!(status, StatusCode::OK, "{}", copied);
+    assert_eq!(copied["year"], YEAR + 1);
+    assert_eq!(copied["display_id"], created["display_id"]);
+    assert_eq!(copied["user_id"], created["user_id"]);
+    assert_ne!(copied["id"], created["id"]);
+
+    let (name, value) = bearer("token-a");
+    let history = Request::get(format!("/api/teachers/{}/history", copied["id"])).header(name, value).body(Body::empty()).unwrap();
+    let (status, _, history) = app.send(history).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history[0]["actor"], "a@example.com");
+
+    // 下一輪已有相同科目的登記時不重複建立
+    let (status, _, _) = app.send(next_round("token-a")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn seeded_registrations_pass_validation() {
+    let Some(app) = TestApp::start().await else { return };
+
+    // 測試資料的科目都在各學制的科目目錄中，原樣送回必須成功。
+    // 測試資料沒有所屬帳號，以管理者的修改路由送回，驗證規則與本人修改相同
+    let (status, _, _) = app.json("POST", "/api/google-login", &json!({ "token": "token-admin" }), &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    let admin = bearer("token-admin");
+
+    let list = Request::get("/api/admin/teachers").header(&admin.0, &admin.1).body(Body::empty()).unwrap();
+    let (_, _, seeded) = app.send(list).await;
+    let seeded = seeded.as_array().unwrap();
+    assert!(!seeded.is_empty());
+    for teacher in seeded {
+        let uri = format!("/api/admin/teachers/{}", teacher["id"]);
+        let version = format!("\"{}\"", teacher["version"]);
+        let (status, _, body) = app.json("PUT", &uri, teacher, &[(admin.0.clone(), &admin.1), (header::IF_MATCH, &version)]).await;
+        assert_eq!(status, StatusCode::OK, "{}: {}", teacher["display_id"], body);
+    }
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn rejects_invalid_registrations_and_closed_rounds() {
+    let Some(app) = TestApp::start().await else { return };
+
+    // 新增登記需要登入，且只能替自己的帳號登記
+    let valid = teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區"));
+    let (status, _, _) = app.json("POST", "/api/teachers", &valid, &[]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    app.login("token-a").await;
+    let owner = bearer("token-a");
+    let other = bearer("token-b");
+    let (status, _, _) = app.json("POST", "/api/teachers", &valid, &[(other.0, &other.1)]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let mut invalid = valid.clone();
+    invalid["subject"] = json!("物理");
+    let (status, _, _) = app.json("POST", "/api/teachers", &invalid, &[(owner.0.clone(), &owner.1)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let mut other_year = valid.clone();
+    other_year["year"] = json!(YEAR + 1);
+    let (status, _, _) = app.json("POST", "/api/teachers", &other_year, &[(owner.0.clone(), &owner.1)]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 輪次截止後不接受新增
+    let admin = bearer("token-admin");
+    let (status, _, _) = app.json("POST", "/api/google-login", &json!({ "token": "token-admin" }), &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    let round_uri = format!("/api/admin/rounds/{}", YEAR);
+    let window = json!({
+        "state": "open",
+        "registration_opens_at": "2020-01-01T00:00:00Z",
+        "registration_closes_at": "2099-01-01T00:00:00Z",
+    });
+    let (status, _, round) = app.json("PUT", &round_uri, &window, &[(admin.0.clone(), &admin.1)]).await;
+    assert_eq!(status, StatusCode::OK, "{}", round);
+
+    // 只改狀態時保留原本的登記期間；只給截止時間時與原本的開始時間比較
+    let early_close = json!({ "state": "open", "registration_closes_at": "2019-01-01T00:00:00Z" });
+    let (status, _, _) = app.json("PUT", &round_uri, &early_close, &[(admin.0.clone(), &admin.1)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let closed = json!({ "state": "closed" });
+    let (status, _, round) = app.json("PUT", &round_uri, &closed, &[(admin.0, &admin.1)]).await;
+    assert_eq!(status, StatusCode::OK, "{}", round);
+    assert_eq!(round["registration_opens_at"], "2020-01-01T00:00:00Z");
+    assert_eq!(round["registration_closes_at"], "2099-01-01T00:00:00Z");
+
+    let (status, _, _) = app.json("POST", "/api/teachers", &valid, &[(owner.0, &owner.1)]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn matches_follow_registration_changes() {
+    let Some(app) = TestApp::start().await else { return };
+
+    // 測試資料包含一組互換與一個五人循環
+    let (first, matches) = app.matches().await;
+    assert_eq!(match_types(&matches), ["5_swap", "direct_swap"]);
+
+    let a = app.create_teacher(&teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區")), "token-a").await;
+    let b = app.create_teacher(&teacher("b@example.com", ("高雄市", "苓雅區"), ("臺中市", "西屯區")), "token-b").await;
+
+    // 同時查詢時計算只做一次，兩邊都拿到新的結果
+    let ((second, matches), (concurrent, concurrent_matches)) = tokio::join!(app.matches(), app.matches());
+    assert!(second > first);
+    assert_eq!(second, concurrent);
+    assert_eq!(match_types(&matches), ["5_swap", "direct_swap", "direct_swap"]);
+    assert_eq!(match_types(&concurrent_matches), match_types(&matches));
+
+    // 新循環會通知參與者；通知只能由所屬帳號讀取
+    let token_a = bearer("token-a");
+    let token_b = bearer("token-b");
+    let notifications_of = |token: &(header::HeaderName, String), uri: &str| {
+        Request::get(uri).header(&token.0, &token.1).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.get("/api/notifications").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = app.send(notifications_of(&token_b, "/api/notifications?google_id=a@example.com")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, notifications) = app.send(notifications_of(&token_a, "/api/notifications")).await;
+    assert_eq!(status, StatusCode::OK);
+    let found = notifications.as_array().unwrap().iter().find(|n| n["kind"] == "match_found").unwrap().clone();
+
+    let read_uri = format!("/api/notifications/{}/read", found["id"]);
+    let read = |token: &(header::HeaderName, String)| {
+        Request::post(&read_uri).header(&token.0, &token.1).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.send(read(&token_b)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, marked) = app.send(read(&token_a)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!marked["read_at"].is_null());
+
+    // 只能以自己的登記確認循環
+    let accept_uri = format!("/api/cycles/{}/accept", found["cycle_id"]);
+    let (status, _, _) = app.json("POST", &accept_uri, &json!({ "teacher_id": a["id"] }), &[]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = app.json("POST", &accept_uri, &json!({ "teacher_id": a["id"] }), &[(token_b.0.clone(), &token_b.1)]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, cycle) = app.json("POST", &accept_uri, &json!({ "teacher_id": a["id"] }), &[(token_a.0.clone(), &token_a.1)]).await;
+    assert_eq!(status, StatusCode::OK, "{}", cycle);
+    assert_eq!(cycle["accepted_teacher_ids"], json!([a["id"]]));
+
+    // 排序與打包參數
+    let (status, _, sorted) = app.get(&format!("/api/matches?year={}&sort=cycle_size&packing=max_teachers", YEAR)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sorted[0]["teachers"].as_array().unwrap().len(), 2);
+
+    // 刪除參與者後循環不再出現，其他參與者收到循環失效的通知
+    let uri = format!("/api/teachers/{}", b["id"]);
+    let (name, value) = bearer("token-b");
+    let (status, _, _) = app.send(Request::delete(&uri).header(name, value).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (third, matches) = app.matches().await;
+    assert!(third > second);
+    assert_eq!(match_types(&matches), ["5_swap", "direct_swap"]);
+
+    let (_, _, notifications) = app.send(notifications_of(&token_a, "/api/notifications")).await;
+    let kinds: Vec<&str> = notifications.as_array().unwrap().iter().map(|n| n["kind"].as_str().unwrap()).collect();
+    assert!(kinds.contains(&"match_broken"), "{:?}", kinds);
+    assert!(notifications.as_array().unwrap().iter().all(|n| n["teacher_id"] == a["id"]));
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn notification_preferences_decide_which_emails_are_queued() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let a = app.create_teacher(&teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區")), "token-a").await;
+    let preferences = json!({ "email": true, "match_found": false, "match_accepted": true, "match_broken": true });
+    let (name, value) = bearer("token-a");
+    let uri = format!("/api/teachers/{}/notification-preferences", a["id"]);
+    let (status, _, _) = app.json("PUT", &uri, &preferences, &[(name, &value)]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let b = app.create_teacher(&teacher("b@example.com", ("高雄市", "苓雅區"), ("臺中市", "西屯區")), "token-b").await;
+    app.matches().await;
+
+    // 兩人都收到站內通知，只有未關閉 match_found 的一方排入 Email 寄送
+    let email_status = |teacher: &Value| {
+        sqlx::query_scalar::<_, String>("SELECT email_status FROM notifications WHERE teacher_id = $1 AND kind = 'match_found'")
+            .bind(teacher["id"].as_i64().unwrap() as i32)
+            .fetch_one(&app.pool)
+    };
+    assert_eq!(email_status(&a).await.unwrap(), "skipped");
+    assert_eq!(email_status(&b).await.unwrap(), "pending");
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn registrations_link_to_the_signed_in_account() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let (status, _, _) = app.json("POST", "/api/google-login", &json!({ "token": "forged" }), &[]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 另一個 Google 帳號先以相同的 email 登入，登記仍只連結到新增它的帳號
+    let other = app.login("token-a2").await;
+    let login = app.login("token-a").await;
+    assert_eq!(login["user"]["role"], "user");
+    assert_ne!(login["user"]["id"], other["user"]["id"]);
+
+    let created = app.create_teacher(&teacher("a@example.com", ("臺中市", "西屯區"), ("高雄市", "苓雅區")), "token-a").await;
+    assert_eq!(created["user_id"], login["user"]["id"]);
+    let login = app.login("token-a").await;
+    assert_eq!(login["teachers"][0]["id"], created["id"]);
+
+    let (status, _, _) = app.get("/api/me").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let me = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::get("/api/me").header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, mine) = app.send(me("token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mine["user"]["email"], "a@example.com");
+    assert_eq!(mine["teachers"].as_array().unwrap().len(), 1);
+    assert_eq!(mine["teachers"][0]["user_id"], mine["user"]["id"]);
+
+    let (status, _, theirs) = app.send(me("token-a2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(theirs["teachers"], json!([]));
+
+    // 相同 email 的另一個 Google 帳號不能修改或刪除已連結到其他帳號的登記
+    let uri = format!("/api/teachers/{}", created["id"]);
+    let mut update = created.clone();
+    update["target_districts"] = json!(["鼓山區"]);
+    let impostor = bearer("token-a2");
+    let (status, _, _) = app.json("PUT", &uri, &update, &[(impostor.0.clone(), &impostor.1), (header::IF_MATCH, "\"1\"")]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = app.send(Request::delete(&uri).header(impostor.0, impostor.1).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 已登入但未曾呼叫 google-login 的帳號沒有資料
+    let (status, _, _) = app.send(me("token-b")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    app.stop().await;
+}
+
+#[tokio::test]
+async fn admin_routes_require_an_admin_account() {
+    let Some(app) = TestApp::start().await else { return };
+
+    for token in ["token-a", "token-admin"] {
+        let (status, _, _) = app.json("POST", "/api/google-login", &json!({ "token": token }), &[]).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let list = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::get("/api/admin/teachers?include_hidden=true").header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.get("/api/admin/teachers").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = app.send(list("token-a")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, all) = app.send(list("token-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    let seeded = all.as_array().unwrap().len();
+    assert!(seeded > 0);
+
+    // 隱藏一位五人循環的參與者：公開列表與配對結果都不再包含，管理列表仍有
+    let (_, matches) = app.matches().await;
+    let cycle = matches.iter().find(|m| m["match_type"] == "5_swap").unwrap();
+    let hidden_id = cycle["teachers"][0]["id"].as_i64().unwrap();
+
+    let admin = bearer("token-admin");
+    let hide = Request::post(format!("/api/admin/teachers/{}/hide", hidden_id))
+        .header(&admin.0, &admin.1)
+        .body(Body::empty())
+        .unwrap();
+    let (status, _, hidden) = app.send(hide).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(hidden["hidden"], true);
+
+    let (status, _, _) = app.get(&format!("/api/teachers/{}", hidden_id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, _, all) = app.send(list("token-admin")).await;
+    assert_eq!(all.as_array().unwrap().len(), seeded);
+
+    let (_, matches) = app.matches().await;
+    assert_eq!(match_types(&matches), ["direct_swap"]);
+
+    let unhide = Request::post(format!("/api/admin/teachers/{}/unhide", hidden_id))
+        .header(&admin.0, &admin.1)
+        .body(Body::empty())
+        .unwrap();
+    let (status, _, _) = app.send(unhide).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, matches) = app.matches().await;
+    assert_eq!(match_types(&matches), ["5_swap", "direct_swap"]);
+
+    // 帳號管理只有系統管理者可以使用
+    let users = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::get("/api/admin/users").header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.send(users("token-a")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, accounts) = app.send(users("token-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(accounts.as_array().unwrap().len(), 2);
+
+    // 只有登記所屬的帳號或管理者可以複製到下一輪
+    let next_round = |token: &str| {
+        let (name, value) = bearer(token);
+        Request::post(format!("/api/teachers/{}/next-round", hidden_id)).header(name, value).body(Body::empty()).unwrap()
+    };
+    let (status, _, _) = app.send(next_round("token-a")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = app.send(next_round("token-admin")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // CSV 匯出逐列串流：BOM、標題列，之後每筆登記一列
+    let export = Request::get(format!("/api/admin/export/teachers?year={}", YEAR))
+        .header(&admin.0, &admin.1)
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, csv) = app.send(export).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/csv; charset=utf-8");
+    let csv = csv.as_str().unwrap();
+    assert!(csv.starts_with("\u{feff}id,display_id,"));
+    assert_eq!(csv.matches("\u{feff}").count(), 1);
+    assert_eq!(csv.lines().count(), seeded + 1);
+
+    app.stop().await;
+}
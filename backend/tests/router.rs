@@ -0,0 +1,443 @@
+// 以記憶體中的登記與配對服務執行完整路由，不需要 Postgres 或 Google
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Request, StatusCode};
+use axum::Router;
+use circlematch_api::auth::{GoogleTokenInfo, StaticTokenVerifier};
+use circlematch_api::config::Config;
+use circlematch_api::model::{MatchCycle, Round, RoundState};
+use circlematch_api::repository::{InMemoryNotificationRepository, InMemoryTeacherRepository};
+use circlematch_api::routes;
+use circlematch_api::state::AppState;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+const YEAR: i32 = 114;
+
+fn round(state: RoundState) -> Round {
+    Round {
+        year: YEAR,
+        state,
+        registration_opens_at: None,
+        registration_closes_at: None,
+        created_at: None,
+    }
+}
+
+fn open_repository() -> Arc<InMemoryTeacherRepository> {
+    let repository = Arc::new(InMemoryTeacherRepository::new());
+    repository.put_round(round(RoundState::Open));
+    repository
+}
+
+fn app(repository: Arc<InMemoryTeacherRepository>) -> Router {
+    routes::router(AppState::in_memory(Config::default(), repository))
+}
+
+fn account(sub: &str, email: &str) -> GoogleTokenInfo {
+    GoogleTokenInfo {
+        sub: sub.to_string(),
+        email: email.to_string(),
+        name: email.split('@').next().unwrap_or_default().to_string(),
+        picture: String::new(),
+    }
+}
+
+// token-a 與 token-b 分別對應 a@example.com 與 b@example.com，token-admin 對應 SUPER_ADMIN_EMAILS 中的帳號
+fn authenticated_state(repository: Arc<InMemoryTeacherRepository>) -> AppState {
+    let verifier = StaticTokenVerifier::new()
+        .with_token("token-a", account("sub-a", "a@example.com"))
+        .with_token("token-b", account("sub-b", "b@example.com"))
+        .with_token("token-admin", account("sub-admin", "admin@example.com"));
+    let mut config = Config::default();
+    config.auth.super_admin_emails = vec!["admin@example.com".to_string()];
+    AppState::in_memory(config, repository).with_verifier(verifier)
+}
+
+fn authenticated_app(repository: Arc<InMemoryTeacherRepository>) -> Router {
+    routes::router(authenticated_state(repository))
+}
+
+fn with_token(mut request: Request<Body>, token: &str) -> Request<Body> {
+    request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+    request
+}
+
+fn teacher(google_id: &str, from: (&str, &str), to: (&str, &str)) -> Value {
+    json!({
+        "email": google_id,
+        "google_id": google_id,
+        "year": YEAR,
+        "school_level": "國小",
+        "subject": "一般",
+        "current_county": from.0,
+        "current_district": from.1,
+        "current_school": format!("{}國小", from.1),
+        "target_counties": [to.0],
+        "target_districts": [to.1],
+    })
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+    (status, headers, body)
+}
+
+// 以 token 對應的帳號登入後新增登記
+async fn create(app: &Router, payload: &Value, token: &str) -> (StatusCode, Value) {
+    let (status, _, _) = send(app, json_request("POST", "/api/google-login", &json!({"token": token}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, created) = send(app, with_token(json_request("POST", "/api/teachers", payload), token)).await;
+    (status, created)
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+fn json_request(method: &str, uri: &str, body: &Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serves_health_and_reference_data() {
+    let app = app(open_repository());
+
+    let (status, _, body) = send(&app, get("/healthz")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "ok");
+
+    let (status, _, body) = send(&app, get("/api/districts")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.as_array().is_some_and(|counties| !counties.is_empty()));
+
+    let (status, _, body) = send(&app, get("/api/subjects?level=國中")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.as_array().unwrap().contains(&json!("數學")));
+}
+
+#[tokio::test]
+async fn creates_updates_and_deletes_a_registration() {
+    let app = authenticated_app(open_repository());
+    let payload = teacher("a@example.com", ("臺北市", "大安區"), ("新北市", "板橋區"));
+
+    let (status, _, _) = send(&app, json_request("POST", "/api/teachers", &payload)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = create(&app, &payload, "token-b").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, created) = create(&app, &payload, "token-a").await;
+    assert_eq!(status, StatusCode::OK);
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(created["version"], 1);
+
+    // 登記以帳號的 users.id 連結
+    let (status, _, me) = send(&app, with_token(get("/api/me"), "token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["teachers"][0]["id"], created["id"]);
+    assert_eq!(created["user_id"], me["user"]["id"]);
+
+    // 列表只有登入帳號自己的登記，不能查詢其他帳號
+    let (status, _, _) = send(&app, get("/api/teachers")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, listed) = send(&app, with_token(get("/api/teachers"), "token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    let (status, _, _) = send(&app, with_token(get("/api/teachers?google_id=a@example.com"), "token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 公開讀取不含 email 與 google_id
+    let (status, headers, public) = send(&app, get(&format!("/api/teachers/{}", id))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ETAG], "\"1\"");
+    assert_eq!(public["email"], "");
+    assert!(public["google_id"].is_null());
+
+    // 未登入或不是本人都不能修改
+    let mut update = payload.clone();
+    update["target_districts"] = json!(["中和區"]);
+    let mut anonymous = json_request("PUT", &format!("/api/teachers/{}", id), &update);
+    anonymous.headers_mut().insert(header::IF_MATCH, "\"1\"".parse().unwrap());
+    let (status, _, _) = send(&app, anonymous).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let mut other = with_token(json_request("PUT", &format!("/api/teachers/{}", id), &update), "token-b");
+    other.headers_mut().insert(header::IF_MATCH, "\"1\"".parse().unwrap());
+    let (status, _, _) = send(&app, other).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 缺少 If-Match 或版本過期都不會寫入
+    let request = with_token(json_request("PUT", &format!("/api/teachers/{}", id), &update), "token-a");
+    let (status, _, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+
+    let mut stale = with_token(json_request("PUT", &format!("/api/teachers/{}", id), &update), "token-a");
+    stale.headers_mut().insert(header::IF_MATCH, "\"7\"".parse().unwrap());
+    let (status, headers, current) = send(&app, stale).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(headers[header::ETAG], "\"1\"");
+    assert_eq!(current["target_districts"], json!(["板橋區"]));
+
+    let mut fresh = with_token(json_request("PUT", &format!("/api/teachers/{}", id), &update), "token-a");
+    fresh.headers_mut().insert(header::IF_MATCH, "\"1\"".parse().unwrap());
+    let (status, headers, updated) = send(&app, fresh).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ETAG], "\"2\"");
+    assert_eq!(updated["target_districts"], json!(["中和區"]));
+
+    // 異動紀錄只有本人或管理者可以查看
+    let history = || get(&format!("/api/teachers/{}/history", id));
+    let (status, _, _) = send(&app, history()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = send(&app, with_token(history(), "token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, entries) = send(&app, with_token(history(), "token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(entries.as_array().unwrap().len(), 2);
+
+    let delete = || Request::delete(format!("/api/teachers/{}", id)).body(Body::empty()).unwrap();
+    let (status, _, _) = send(&app, with_token(delete(), "token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send(&app, with_token(delete(), "token-a")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, _) = send(&app, get(&format!("/api/teachers/{}", id))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn rejects_invalid_registrations_and_closed_rounds() {
+    let repository = open_repository();
+    let app = authenticated_app(repository.clone());
+
+    let mut invalid = teacher("a@example.com", ("臺北市", "大安區"), ("新北市", "板橋區"));
+    invalid["subject"] = json!("數學");
+    let (status, _) = create(&app, &invalid, "token-a").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    repository.put_round(round(RoundState::Closed));
+    let payload = teacher("a@example.com", ("臺北市", "大安區"), ("新北市", "板橋區"));
+    let (status, _) = create(&app, &payload, "token-a").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn matches_reflect_new_registrations() {
+    let app = authenticated_app(open_repository());
+
+    let (status, headers, matches) = send(&app, get("/api/matches")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(matches, json!([]));
+    let before: u64 = headers["x-match-generation"].to_str().unwrap().parse().unwrap();
+
+    for (token, payload) in [
+        ("token-a", teacher("a@example.com", ("臺北市", "大安區"), ("新北市", "板橋區"))),
+        ("token-b", teacher("b@example.com", ("新北市", "板橋區"), ("臺北市", "大安區"))),
+    ] {
+        let (status, _) = create(&app, &payload, token).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // 未指定年度時使用目前輪次
+    let (status, headers, matches) = send(&app, get("/api/matches")).await;
+    assert_eq!(status, StatusCode::OK);
+    let after: u64 = headers["x-match-generation"].to_str().unwrap().parse().unwrap();
+    assert!(after > before);
+    assert_eq!(headers["x-match-exhaustive"], "true");
+
+    let matches = matches.as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["match_type"], "direct_swap");
+    assert_eq!(matches[0]["teachers"].as_array().unwrap().len(), 2);
+
+    // 公開的配對結果不含帳號資料，參與者只看得到循環中其他人的 email
+    let teachers = matches[0]["teachers"].as_array().unwrap();
+    assert!(teachers.iter().all(|t| t["email"] == "" && t["google_id"].is_null()));
+    for (token, expected) in [("token-a", "b@example.com"), ("token-admin", "")] {
+        let (_, _, matches) = send(&app, with_token(get("/api/matches"), token)).await;
+        let teachers = matches[0]["teachers"].as_array().unwrap();
+        assert!(teachers.iter().any(|t| t["email"] == expected), "{}: {:?}", token, teachers);
+        assert!(teachers.iter().all(|t| t["google_id"].is_null()));
+    }
+}
+
+#[tokio::test]
+async fn authentication_uses_the_injected_verifier() {
+    let without_verifier = app(open_repository());
+    let me = |token: Option<&str>| {
+        let mut request = Request::get("/api/me");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let (status, _, _) = send(&without_verifier, me(None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = send(&without_verifier, me(Some("token"))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let with_verifier = authenticated_app(open_repository());
+
+    let (status, _, _) = send(&with_verifier, me(Some("forged"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = send(&with_verifier, get("/api/admin/users?token=forged")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn admin_routes_use_the_injected_account_and_round_services() {
+    let app = authenticated_app(open_repository());
+    let users = || get("/api/admin/users");
+
+    // 帳號在第一次登入時建立，之前沒有管理權限
+    let (status, _, _) = send(&app, with_token(users(), "token-admin")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, login) = send(&app, json_request("POST", "/api/google-login", &json!({"token": "token-admin"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(login["user"]["role"], "super_admin");
+
+    let (status, _, _) = send(&app, with_token(users(), "token-a")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, listed) = send(&app, with_token(users(), "token-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    let new_round = json!({"year": YEAR + 1});
+    let (status, _, created) = send(&app, with_token(json_request("POST", "/api/admin/rounds", &new_round), "token-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["state"], "draft");
+    let (status, _, _) = send(&app, with_token(json_request("POST", "/api/admin/rounds", &new_round), "token-admin")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let open = json!({"state": "open"});
+    let uri = format!("/api/admin/rounds/{}", YEAR + 1);
+    let (status, _, updated) = send(&app, with_token(json_request("PUT", &uri, &open), "token-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["state"], "open");
+
+    let (status, _, rounds) = send(&app, get("/api/rounds")).await;
+    assert_eq!(status, StatusCode::OK);
+    let years: Vec<i64> = rounds.as_array().unwrap().iter().map(|round| round["year"].as_i64().unwrap()).collect();
+    assert_eq!(years, [i64::from(YEAR + 1), i64::from(YEAR)]);
+
+    let announcement = json!({"body": "系統維護", "severity": "warning"});
+    let (status, _, _) = send(&app, with_token(json_request("POST", "/api/admin/announcements", &announcement), "token-admin")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _, hello) = send(&app, get("/api/hello")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(hello["text"], "系統維護");
+
+    let (status, _, ready) = send(&app, get("/readyz")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ready["database"], true);
+}
+
+#[tokio::test]
+async fn accepting_a_cycle_notifies_every_participant() {
+    let repository = open_repository();
+    let notifications = Arc::new(InMemoryNotificationRepository::new(repository.clone()));
+    let app = routes::router(AppState {
+        notifications: notifications.clone(),
+        ..authenticated_state(repository)
+    });
+
+    let mut ids = Vec::new();
+    for (token, payload) in [
+        ("token-a", teacher("a@example.com", ("臺北市", "大安區"), ("新北市", "板橋區"))),
+        ("token-b", teacher("b@example.com", ("新北市", "板橋區"), ("臺北市", "大安區"))),
+    ] {
+        let (status, created) = create(&app, &payload, token).await;
+        assert_eq!(status, StatusCode::OK);
+        ids.push(created["id"].as_i64().unwrap() as i32);
+    }
+    notifications.put_cycle(MatchCycle {
+        id: 1,
+        cycle_key: "test".to_string(),
+        year: YEAR,
+        school_level: "國小".to_string(),
+        teacher_ids: ids.clone(),
+        accepted_teacher_ids: Vec::new(),
+        status: "active".to_string(),
+        found_at: chrono::Utc::now(),
+        broken_at: None,
+        broken_reason: None,
+    });
+
+    let accept = |teacher_id: i32, token: &str| {
+        with_token(json_request("POST", "/api/cycles/1/accept", &json!({"teacher_id": teacher_id})), token)
+    };
+    let (status, _, _) = send(&app, accept(ids[0], "token-b")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, cycle) = send(&app, accept(ids[0], "token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cycle["status"], "active");
+    let (status, _, cycle) = send(&app, accept(ids[1], "token-b")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cycle["status"], "accepted");
+
+    let (status, _, listed) = send(&app, with_token(get("/api/notifications"), "token-a")).await;
+    assert_eq!(status, StatusCode::OK);
+    let listed = listed.as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["kind"], "match_accepted");
+    assert_eq!(listed[0]["teacher_id"], ids[0]);
+}
+
+#[tokio::test]
+async fn registration_changes_are_published_on_the_state_event_bus() {
+    let state = authenticated_state(open_repository());
+    let mut events = state.events.subscribe();
+    // 另一個狀態的匯流排不會收到這個狀態的事件
+    let other = authenticated_state(open_repository());
+    let mut other_events = other.events.subscribe();
+    let app = routes::router(state);
+
+    let (status, _) = create(&app, &teacher("a@example.com", ("臺北市", "大安區"), ("新北市", "板橋區")), "token-a").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let event = events.try_recv().expect("新增登記後應發布事件");
+    assert_eq!(event.name(), "teachers_changed");
+    assert!(other_events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn query_tokens_are_only_accepted_as_event_tickets() {
+    let app = authenticated_app(open_repository());
+    let (status, _, _) = send(&app, json_request("POST", "/api/google-login", &json!({"token": "token-a"}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 網址中的登入 token 不再被接受
+    let (status, _, _) = send(&app, get("/api/me?token=token-a")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let status = app.clone().oneshot(get("/api/events?token=token-a")).await.unwrap().status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _, _) = send(&app, Request::post("/api/events/ticket").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let request = with_token(Request::post("/api/events/ticket").body(Body::empty()).unwrap(), "token-a");
+    let (status, _, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let ticket = body["ticket"].as_str().unwrap().to_string();
+
+    // 票證只適用於 SSE，且只能使用一次
+    let (status, _, _) = send(&app, get(&format!("/api/me?ticket={}", ticket))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(get(&format!("/api/events?ticket={}", ticket))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+    drop(response);
+    let status = app.clone().oneshot(get(&format!("/api/events?ticket={}", ticket))).await.unwrap().status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,81 @@
+// 學校名錄的解析：內建 JSON 與教育部名錄 CSV 匯出檔
+use circlematch_api::db::{load_school_directory, parse_school_directory};
+use circlematch_api::model::Teacher;
+use circlematch_api::validation::validate_teacher;
+use serde_json::json;
+
+#[test]
+fn parses_the_bundled_sample_directory() {
+    // 範例名錄不含學校代碼，不能被當成正式代碼核對
+    let schools = parse_school_directory(include_str!("../data/sample_schools.json"), false).unwrap();
+    assert!(!schools.is_empty());
+    assert!(schools.iter().all(|school| school.code.is_none()));
+}
+
+#[test]
+fn parses_the_moe_csv_export() {
+    let csv = "\u{feff}代碼,學校名稱,公/私立,縣市名稱,地址,電話,網址
+353601,市立大安國小,公立,[63]臺北市,[106]臺北市大安區新生南路三段100號,(02)00000000,
+014569,縣立竹東國中,公立,[10]新竹縣,[310]新竹縣竹東鎮中興路一段1號,(03)0000000,
+0001,國立臺灣大學,公立,[63]臺北市,[106]臺北市大安區羅斯福路四段1號,(02)00000000,
+";
+    let schools = parse_school_directory(csv, true).unwrap();
+    assert_eq!(schools.len(), 2);
+
+    assert_eq!(schools[0].code.as_deref(), Some("353601"));
+    assert_eq!((schools[0].county.as_str(), schools[0].district.as_str()), ("臺北市", "大安區"));
+    assert_eq!(schools[0].level, "國小");
+    assert_eq!((schools[1].county.as_str(), schools[1].district.as_str()), ("新竹縣", "竹東鎮"));
+    assert_eq!(schools[1].level, "國中");
+}
+
+#[test]
+fn rejects_csv_without_required_columns() {
+    let error = parse_school_directory("代碼,學校名稱\n1,某國小\n", true).unwrap_err();
+    assert!(error.contains("縣市名稱"), "{}", error);
+}
+
+#[test]
+fn recognises_combined_and_senior_secondary_schools() {
+    let csv = "代碼,學校名稱,公/私立,縣市名稱,地址
+064752,市立博屋瑪國民中小學,公立,[66]臺中市,[423]臺中市和平區東關路三段1號
+193301,市立臺中第一高級中等學校,公立,[66]臺中市,[404]臺中市北區育才街2號
+";
+    let schools = parse_school_directory(csv, true).unwrap();
+    let levels: Vec<(&str, &str)> = schools.iter()
+        .map(|school| (school.code.as_deref().unwrap(), school.level.as_str()))
+        .collect();
+    assert_eq!(levels, [("064752", "國小"), ("064752", "國中"), ("193301", "高中")]);
+}
+
+#[test]
+fn the_sample_directory_does_not_reject_school_codes() {
+    // 測試環境未設定 SCHOOL_DIRECTORY_FILE，使用不含代碼的範例名錄，無法核對的代碼照常接受
+    let teacher: Teacher = serde_json::from_value(json!({
+        "email": "a@example.com",
+        "year": 114,
+        "subject": "一般",
+        "current_county": "臺北市",
+        "current_district": "大安區",
+        "current_school": "大安國小",
+        "school_code": "313601",
+        "target_counties": ["新北市"],
+        "target_districts": ["板橋區"],
+    }))
+    .unwrap();
+    assert_eq!(validate_teacher(&teacher), Ok(()));
+}
+
+#[test]
+fn rejects_unusable_directory_files() {
+    let missing = std::env::temp_dir().join("circlematch-missing-schools.csv");
+    let error = load_school_directory(&missing).unwrap_err();
+    assert!(error.contains("無法讀取"), "{}", error);
+
+    // 只有大專校院的名錄沒有任何可用的學校
+    let empty = std::env::temp_dir().join(format!("circlematch-schools-{}.csv", std::process::id()));
+    std::fs::write(&empty, "代碼,學校名稱,縣市名稱,地址\n0001,國立臺灣大學,[63]臺北市,[106]臺北市大安區羅斯福路四段1號\n").unwrap();
+    let error = load_school_directory(&empty).unwrap_err();
+    std::fs::remove_file(&empty).unwrap();
+    assert!(error.contains("沒有任何可用的學校"), "{}", error);
+}
@@ -1,23 +1,54 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { Alert } from 'antd';
 import { InfoCircleOutlined } from '@ant-design/icons';
+import ApiService from '../services/ApiService';
+import { Announcement } from '../types';
+
+// 公告的重要程度對應的橫幅樣式
+const ALERT_TYPES: Record<Announcement['severity'], 'info' | 'warning' | 'error'> = {
+  info: 'info',
+  warning: 'warning',
+  critical: 'error',
+};
 
 const AnnouncementBanner: React.FC = () => {
   const [isVisible, setIsVisible] = useState(true);
-  
-  if (!isVisible) return null;
+  const [announcements, setAnnouncements] = useState<Announcement[]>([]);
+  const [dismissed, setDismissed] = useState<number[]>([]);
+
+  useEffect(() => {
+    ApiService.getAnnouncements().then(setAnnouncements);
+  }, []);
   
   return (
-    <Alert
-      message="請提供正確資料，本平台僅協助配對，不查核資訊真實性。錯誤資料將影響其他教師的配對機會，謝謝合作。"
-      type="warning"
-      showIcon
-      icon={<InfoCircleOutlined />}
-      closable
-      onClose={() => setIsVisible(false)}
-      style={{ marginBottom: 20 }}
-    />
+    <>
+      {announcements
+        .filter((announcement) => !dismissed.includes(announcement.id))
+        .map((announcement) => (
+          <Alert
+            key={announcement.id}
+            message={announcement.title || announcement.body}
+            description={announcement.title ? announcement.body : undefined}
+            type={ALERT_TYPES[announcement.severity]}
+            showIcon
+            closable={announcement.severity !== 'critical'}
+            onClose={() => setDismissed([...dismissed, announcement.id])}
+            style={{ marginBottom: 12 }}
+          />
+        ))}
+      {isVisible && (
+        <Alert
+          message="請提供正確資料，本平台僅協助配對，不查核資訊真實性。錯誤資料將影響其他教師的配對機會，謝謝合作。"
+          type="warning"
+          showIcon
+          icon={<InfoCircleOutlined />}
+          closable
+          onClose={() => setIsVisible(false)}
+          style={{ marginBottom: 20 }}
+        />
+      )}
+    </>
   );
 };
 
-export default AnnouncementBanner;
\ No newline at end of file
+export default AnnouncementBanner;
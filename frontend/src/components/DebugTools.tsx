@@ -59,7 +59,7 @@ const DebugTools: React.FC<DebugToolsProps> = ({
     },
     {
       key: 'debugLogin',
-      label: '模擬登入',
+      label: '重新載入帳號',
       icon: <UserOutlined />,
       onClick: onDebugLogin,
       disabled: !isDebugAuthenticated
@@ -50,7 +50,7 @@ const MatchCard: React.FC<MatchCardProps> = ({
                 >
                   <div className={styles.teacherInfo}>
                     <Text strong={isCurrentUser}>
-                      {teacher.name || teacher.email}
+                      {teacher.name || teacher.display_id || teacher.email}
                       {isCurrentUser && <Tag color="blue" className={styles.userTag}>您</Tag>}
                     </Text>
                     <div className={styles.schoolInfo}>
@@ -18,17 +18,18 @@ const ProtectedRoute: React.FC<ProtectedRouteProps> = ({ children }) => {
     // Check if we have auth token in localStorage but no userInfo
     const checkAuth = async () => {
       const token = localStorage.getItem('auth_token');
-      const googleId = localStorage.getItem('google_id');
       
-      if (token && googleId && !userVM.userInfo) {
+      if (token && !userVM.userInfo) {
         try {
-          // Get user data based on stored Google ID
-          await userVM.debugLogin(googleId);
+          // 以保存的 token 取得目前登入的帳號，token 失效時需重新登入
+          if (!await userVM.restoreSession()) {
+            localStorage.removeItem('auth_token');
+            localStorage.removeItem('user_info');
+          }
           setCheckingAuth(false);
         } catch (error) {
           console.error('Failed to restore auth:', error);
           localStorage.removeItem('auth_token');
-          localStorage.removeItem('google_id');
           localStorage.removeItem('user_info');
           setCheckingAuth(false);
         }
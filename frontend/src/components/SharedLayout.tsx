@@ -37,7 +37,6 @@ const SharedLayout: React.FC<SharedLayoutProps> = ({
     } else {
       // Clear localStorage
       localStorage.removeItem('auth_token');
-      localStorage.removeItem('google_id');
       localStorage.removeItem('user_info');
       navigate('/login');
     }
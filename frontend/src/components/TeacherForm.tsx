@@ -151,7 +151,6 @@ const TeacherForm: React.FC<TeacherFormProps> = ({
       subject: values.subject,
       target_counties: values.targets?.map((t: any) => t.county) || [],
       target_districts: values.targets?.map((t: any) => t.district) || [],
-      google_id: initialData?.google_id,
       year: currentYear,
     };
     
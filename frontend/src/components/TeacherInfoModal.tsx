@@ -88,7 +88,11 @@ const TeacherInfoModal: React.FC<TeacherInfoModalProps> = ({
         <Space direction="vertical" size="small" style={{ width: '100%' }}>
           <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center' }}>
             <Text strong>聯絡Email：</Text>
-            <Text copyable={{ tooltips: ['複製', '已複製'] }}>{email}</Text>
+            {email ? (
+              <Text copyable={{ tooltips: ['複製', '已複製'] }}>{email}</Text>
+            ) : (
+              <Text type="secondary">僅提供給同一循環的參與者</Text>
+            )}
           </div>
           
           {teacherInfo?.subject && (
@@ -123,6 +127,7 @@ const TeacherInfoModal: React.FC<TeacherInfoModalProps> = ({
             icon={<MailOutlined />} 
             block 
             onClick={sendEmail}
+            disabled={!email}
             style={{ marginBottom: 12 }}
           >
             寄送Email
@@ -132,6 +137,7 @@ const TeacherInfoModal: React.FC<TeacherInfoModalProps> = ({
             icon={<CopyOutlined />} 
             block
             onClick={copyEmail}
+            disabled={!email}
           >
             複製Email
           </Button>
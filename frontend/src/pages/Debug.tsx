@@ -1,5 +1,5 @@
 import React, { useState, useEffect } from 'react';
-import { Card, Typography, Input, Button, Space, Alert, message, Tabs, Divider, Switch, Row, Col, List } from 'antd';
+import { Card, Typography, Button, Space, Alert, message, Tabs, Divider, Switch, Row, Col, List, Spin } from 'antd';
 import { useNavigate } from 'react-router-dom';
 import { BugOutlined, EyeOutlined, UserOutlined, SettingOutlined, LockOutlined } from '@ant-design/icons';
 import { useMatchViewModel } from '../viewmodels/MatchViewModel';
@@ -8,7 +8,6 @@ import SharedLayout from '../components/SharedLayout';
 import ApiService from '../services/ApiService';
 
 const { Title, Text, Paragraph } = Typography;
-const { Password } = Input;
 const { TabPane } = Tabs;
 
 const Debug: React.FC = () => {
@@ -16,26 +15,26 @@ const Debug: React.FC = () => {
   const userVM = useUserViewModel();
   const matchVM = useMatchViewModel(userVM.currentTeacher, userVM.allTeachers);
   
-  const [password, setPassword] = useState('');
   const [isAuthenticated, setIsAuthenticated] = useState(false);
-  const [error, setError] = useState('');
   const [activeTab, setActiveTab] = useState('1');
-  const [debugGoogleId, setDebugGoogleId] = useState('debug-user-123');
   const [userViewMode, setUserViewMode] = useState(false);
   const [checkingAuth, setCheckingAuth] = useState(true);
   
-  // Read debug password from environment variable
-  // Note: Make sure VITE_DEBUG_AUTH is in a .env file in the root directory
-  const DEBUG_PASSWORD = import.meta.env.VITE_DEBUG_AUTH || '';
-  
+  // 只有管理者帳號（縣市管理者或系統管理者）可以使用 Debug 頁面，權限由後端的 /api/me 判斷
   useEffect(() => {
-    const storedAuth = localStorage.getItem('debug_authenticated');
-    if (storedAuth === 'true') {
-      setIsAuthenticated(true);
-      // Enable debug mode in the MatchViewModel
-      matchVM.enableDebugMode();
-    }
-    setCheckingAuth(false);
+    ApiService.getMe()
+      .then((me) => {
+        const isAdmin = !!me && me.user.role !== 'user';
+        setIsAuthenticated(isAdmin);
+        if (isAdmin) {
+          localStorage.setItem('debug_authenticated', 'true');
+          // Enable debug mode in the MatchViewModel
+          matchVM.enableDebugMode();
+        } else {
+          localStorage.removeItem('debug_authenticated');
+        }
+      })
+      .finally(() => setCheckingAuth(false));
   }, []);
   
   // Set user view mode based on MatchViewModel
@@ -43,19 +42,6 @@ const Debug: React.FC = () => {
     setUserViewMode(matchVM.userView);
   }, [matchVM.userView]);
   
-  // Handle password submission
-  const handleAuthenticate = () => {
-    if (password === DEBUG_PASSWORD) {
-      setIsAuthenticated(true);
-      matchVM.enableDebugMode();
-      // Store authentication in localStorage
-      localStorage.setItem('debug_authenticated', 'true');
-      message.success('Debug mode activated');
-    } else {
-      setError('Incorrect password');
-    }
-  };
-  
   // Handle debug actions
   const handleViewAllMatches = async () => {
     try {
@@ -71,15 +57,14 @@ const Debug: React.FC = () => {
   
   const handleDebugLogin = async () => {
     try {
-      const result = await userVM.debugLogin(debugGoogleId);
-      // Store authentication data in localStorage
-      localStorage.setItem('auth_token', 'debug-token');
-      localStorage.setItem('google_id', debugGoogleId);
-      if (result?.userInfo) {
-        localStorage.setItem('user_info', JSON.stringify(result.userInfo));
+      // 以目前登入的 token 重新載入帳號與登記
+      const result = await userVM.restoreSession();
+      if (!result) {
+        message.error('Session expired, please sign in again');
+        return;
       }
       
-      message.success('Debug login successful');
+      message.success('Session reloaded');
       
       if (result?.teachers && result.teachers.length > 0) {
         navigate('/matches');
@@ -129,26 +114,20 @@ const Debug: React.FC = () => {
           }}
         >
           <Space direction="vertical" size="middle" style={{ width: '100%' }}>
-            <Alert
-              message="Restricted Area"
-              description="This area is for developers only. Please enter the debug password to continue."
-              type="warning"
-              showIcon
-              icon={<LockOutlined />}
-            />
-            
-            {error && <Alert message={error} type="error" showIcon />}
-            
-            <Password
-              placeholder="Enter debug password"
-              value={password}
-              onChange={(e) => setPassword(e.target.value)}
-              onPressEnter={handleAuthenticate}
-              size="large"
-            />
+            {checkingAuth ? (
+              <div style={{ textAlign: 'center' }}><Spin /></div>
+            ) : (
+              <Alert
+                message="Restricted Area"
+                description="此頁面僅限管理者帳號使用，請以具有管理權限的 Google 帳號登入。"
+                type="warning"
+                showIcon
+                icon={<LockOutlined />}
+              />
+            )}
             
-            <Button type="primary" onClick={handleAuthenticate} block size="large">
-              Authenticate
+            <Button type="primary" onClick={() => navigate('/login')} block size="large" disabled={checkingAuth}>
+              Sign in
             </Button>
             
             <Button onClick={() => navigate('/')} block>
@@ -228,14 +207,6 @@ const Debug: React.FC = () => {
                 <Divider orientation="left">User Controls</Divider>
                 
                 <Row gutter={[16, 16]}>
-                  <Col xs={24} sm={12}>
-                    <Input
-                      addonBefore="Google ID"
-                      value={debugGoogleId}
-                      onChange={(e) => setDebugGoogleId(e.target.value)}
-                      placeholder="Enter a debug Google ID"
-                    />
-                  </Col>
                   <Col xs={24} sm={12}>
                     <Button 
                       type="primary" 
@@ -243,7 +214,7 @@ const Debug: React.FC = () => {
                       onClick={handleDebugLogin}
                       block
                     >
-                      Debug Login
+                      Reload Session
                     </Button>
                   </Col>
                 </Row>
@@ -287,8 +258,8 @@ const Debug: React.FC = () => {
                         <Text>{userVM.userInfo.email}</Text>
                       </List.Item>
                       <List.Item>
-                        <Text strong>Google ID: </Text>
-                        <Text>{userVM.userInfo.google_id}</Text>
+                        <Text strong>Account ID: </Text>
+                        <Text>{userVM.userInfo.id}</Text>
                       </List.Item>
                     </>
                   )}
@@ -20,7 +20,6 @@ const Home: React.FC = () => {
       
       // Store authentication data in localStorage
       localStorage.setItem('auth_token', credentialResponse.credential);
-      localStorage.setItem('google_id', result?.userInfo.google_id || '');
       localStorage.setItem('user_info', JSON.stringify(result?.userInfo));
       
       if (result?.teachers?.length > 0) {
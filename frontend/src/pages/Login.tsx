@@ -19,7 +19,6 @@ const Login: React.FC = () => {
       
       // Store authentication data in localStorage
       localStorage.setItem('auth_token', credentialResponse.credential);
-      localStorage.setItem('google_id', result?.userInfo.google_id || '');
       localStorage.setItem('user_info', JSON.stringify(result?.userInfo));
       
       if (result?.teachers?.length > 0) {
@@ -68,7 +68,6 @@ const MatchResults: React.FC = () => {
     userVM.logout();
     // Clear local storage
     localStorage.removeItem('auth_token');
-    localStorage.removeItem('google_id');
     localStorage.removeItem('user_info');
     navigate('/login');
   };
@@ -85,8 +84,7 @@ const MatchResults: React.FC = () => {
   };
   
   const handleDebugLogin = () => {
-    const debugGoogleId = 'debug-user-123';
-    userVM.debugLogin(debugGoogleId);
+    userVM.restoreSession();
   };
 
   const handleExitDebugMode = () => {
@@ -27,7 +27,7 @@ const NewTeacherForm: React.FC = () => {
   
   // Handle create teacher submission
   const handleCreateTeacher = async (teacher: Teacher) => {
-    if (!userVM.userInfo?.google_id) {
+    if (!userVM.userInfo) {
       setError('登入狀態已過期，請重新登入');
       return;
     }
@@ -36,10 +36,9 @@ const NewTeacherForm: React.FC = () => {
     setError('');
     
     try {
-      // Create teacher with Google ID
+      // 登記由伺服器連結到登入的帳號
       const createdTeacher = await ApiService.createTeacher({
         ...teacher,
-        google_id: userVM.userInfo.google_id,
         year: currentYear
       });
       
@@ -62,7 +61,6 @@ const NewTeacherForm: React.FC = () => {
     userVM.logout();
     // Clear local storage
     localStorage.removeItem('auth_token');
-    localStorage.removeItem('google_id');
     localStorage.removeItem('user_info');
     navigate('/login');
   };
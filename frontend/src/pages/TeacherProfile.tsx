@@ -30,17 +30,11 @@ const TeacherProfile: React.FC = () => {
     // Check if we have auth token in localStorage but no userInfo
     const checkAuth = async () => {
       const token = localStorage.getItem('auth_token');
-      const googleId = localStorage.getItem('google_id');
       
-      if (token && googleId) {
+      if (token) {
         try {
           // Get all user teacher data
-          const teachers = await ApiService.getTeachersByGoogleId(googleId);
-          userVM.setAllTeachers(teachers);
-          
-          if (teachers.length > 0) {
-            userVM.setCurrentTeacher(teachers[0]);
-          }
+          await userVM.restoreSession();
         } catch (error) {
           console.error('Failed to load teacher data:', error);
         }
@@ -57,7 +51,7 @@ const TeacherProfile: React.FC = () => {
   
   // Handle create teacher submission
   const handleCreateTeacher = async (teacher: Teacher) => {
-    if (!userVM.userInfo?.google_id) {
+    if (!userVM.userInfo) {
       setError('登入狀態已過期，請重新登入');
       return;
     }
@@ -66,10 +60,9 @@ const TeacherProfile: React.FC = () => {
     setError('');
     
     try {
-      // Create teacher with Google ID
+      // 登記由伺服器連結到登入的帳號
       const createdTeacher = await ApiService.createTeacher({
         ...teacher,
-        google_id: userVM.userInfo.google_id,
         year: currentYear
       });
       
@@ -97,7 +90,6 @@ const TeacherProfile: React.FC = () => {
     userVM.logout();
     // Clear local storage
     localStorage.removeItem('auth_token');
-    localStorage.removeItem('google_id');
     localStorage.removeItem('user_info');
     navigate('/login');
   };
@@ -110,8 +102,7 @@ const TeacherProfile: React.FC = () => {
   };
   
   const handleDebugLogin = () => {
-    const debugGoogleId = 'debug-user-123';
-    userVM.debugLogin(debugGoogleId);
+    userVM.restoreSession();
   };
 
   const handleExitDebugMode = () => {
@@ -1,8 +1,23 @@
-import { Teacher, UserInfo, UserResponse, MatchResult } from '../types';
+import { Teacher, UserInfo, UserResponse, MatchResult, Round, MeResponse, Announcement, Account } from '../types';
 
 // Base API URL from environment variables
 const API_BASE_URL = import.meta.env.VITE_API_URL || '';
 
+// 需要登入的 API 以 Authorization 標頭帶入 Google ID token
+const authHeaders = (): Record<string, string> => {
+  const token = localStorage.getItem('auth_token');
+  return token ? { 'Authorization': `Bearer ${token}` } : {};
+};
+
+// 由 users 表的帳號取得前端顯示用的使用者資料
+export const toUserInfo = (user: Account): UserInfo => ({
+  id: user.id,
+  google_sub: user.google_sub,
+  name: user.name || '未知使用者',
+  picture: user.picture || '',
+  email: user.email,
+});
+
 // Generic error handling
 const handleApiError = (error: any, defaultMessage: string = '操作失敗，請稍後再試') => {
   console.error('API Error:', error);
@@ -33,12 +48,7 @@ export class ApiService {
       
       // Format the response to meet our UserResponse interface
       return {
-        userInfo: {
-          name: data.name || '未知使用者',
-          picture: data.picture || '',
-          email: data.email || '',
-          google_id: data.google_id || '',
-        },
+        userInfo: toUserInfo(data.user),
         teacher: data.teacher || null,
         teachers: data.teachers || [],
       };
@@ -48,31 +58,13 @@ export class ApiService {
   }
 
   // Teacher-related endpoints
-  static async getTeachersByGoogleId(googleId: string): Promise<Teacher[]> {
-    try {
-      const response = await fetch(
-        `${API_BASE_URL}/api/teachers?google_id=${encodeURIComponent(googleId)}`,
-        { method: 'GET' }
-      );
-      
-      if (!response.ok) {
-        throw new Error(`獲取教師資料失敗: ${response.status}`);
-      }
-      
-      const data = await response.json();
-      return data;
-    } catch (error) {
-      console.error("Error fetching teachers:", error);
-      throw new Error(handleApiError(error, '獲取教師資料失敗，請稍後再試'));
-    }
-  }
-
   static async createTeacher(teacher: Teacher): Promise<Teacher> {
     try {
       const response = await fetch(`${API_BASE_URL}/api/teachers`, {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
+          ...authHeaders(),
         },
         body: JSON.stringify(teacher),
       });
@@ -94,11 +86,18 @@ export class ApiService {
       const response = await fetch(`${API_BASE_URL}/api/teachers/${teacherId}`, {
         method: 'PUT',
         headers: {
+          ...authHeaders(),
           'Content-Type': 'application/json',
+          // 沒有版本號的舊資料不檢查版本
+          'If-Match': teacher.version !== undefined ? `"${teacher.version}"` : '*',
         },
         body: JSON.stringify(teacher),
       });
 
+      if (response.status === 412) {
+        throw new Error('這筆資料已在其他地方被修改，請重新整理後再編輯');
+      }
+
       if (!response.ok) {
         const errorText = await response.text();
         throw new Error(`後端錯誤: ${response.status} ${response.statusText} - ${errorText}`);
@@ -115,6 +114,7 @@ export class ApiService {
     try {
       const response = await fetch(`${API_BASE_URL}/api/teachers/${teacherId}`, {
         method: 'DELETE',
+        headers: authHeaders(),
       });
 
       if (!response.ok) {
@@ -129,7 +129,10 @@ export class ApiService {
   // Match-related endpoints
   static async getMatches(): Promise<MatchResult[]> {
     try {
-      const response = await fetch(`${API_BASE_URL}/api/matches`);
+      // 登入時帶上 token，才看得到自己參與的循環中其他人的 email
+      const response = await fetch(`${API_BASE_URL}/api/matches`, {
+        headers: authHeaders(),
+      });
       
       if (!response.ok) {
         throw new Error(`配對結果獲取失敗: ${response.status}`);
@@ -157,9 +160,10 @@ export class ApiService {
   }
 
   // Subject-related endpoints
-  static async getSubjects(): Promise<string[]> {
+  static async getSubjects(level?: string): Promise<string[]> {
     try {
-      const response = await fetch(`${API_BASE_URL}/api/subjects`);
+      const query = level ? `?level=${encodeURIComponent(level)}` : '';
+      const response = await fetch(`${API_BASE_URL}/api/subjects${query}`);
       
       if (!response.ok) {
         throw new Error(`Failed to fetch subjects: ${response.status}`);
@@ -172,6 +176,117 @@ export class ApiService {
       return ["一般", "英文", "體育", "音樂", "美術", "資訊", "特教", "行政"];
     }
   }
+
+  // 目前輪次，沒有進行中的輪次時返回 null
+  static async getCurrentRound(): Promise<Round | null> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/api/rounds/current`);
+      
+      if (!response.ok) {
+        return null;
+      }
+      
+      return await response.json();
+    } catch (error) {
+      console.error('Error fetching current round:', error);
+      return null;
+    }
+  }
+
+  // 目前顯示中的公告，讀取失敗時不顯示
+  static async getAnnouncements(): Promise<Announcement[]> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/api/announcements`);
+
+      if (!response.ok) {
+        return [];
+      }
+
+      return await response.json();
+    } catch (error) {
+      console.error('Error fetching announcements:', error);
+      return [];
+    }
+  }
+
+  // 目前登入的帳號（含權限），未登入或 token 失效時返回 null
+  static async getMe(): Promise<MeResponse | null> {
+    if (!localStorage.getItem('auth_token')) {
+      return null;
+    }
+
+    try {
+      const response = await fetch(`${API_BASE_URL}/api/me`, {
+        headers: authHeaders(),
+      });
+
+      if (!response.ok) {
+        return null;
+      }
+
+      return await response.json();
+    } catch (error) {
+      console.error('Error fetching account:', error);
+      return null;
+    }
+  }
+
+  // 即時配對事件（SSE）。EventSource 無法設定標頭，先以登入 token 換取一次性的短期票證再連線，
+  // 連線中斷時重新換取票證；返回的函式用來停止接收
+  static subscribeMatchEvents(onEvent: (type: string, data: any) => void): () => void {
+    let source: EventSource | null = null;
+    let closed = false;
+    let retry: ReturnType<typeof setTimeout> | undefined;
+
+    const reconnect = () => {
+      if (!closed) {
+        retry = setTimeout(connect, 5000);
+      }
+    };
+
+    const connect = async () => {
+      try {
+        const response = await fetch(`${API_BASE_URL}/api/events/ticket`, {
+          method: 'POST',
+          headers: authHeaders(),
+        });
+        // 登入已失效時不再重試，重新登入後頁面會重新訂閱
+        if (response.status === 401) {
+          return;
+        }
+        if (!response.ok) {
+          throw new Error(`取得事件票證失敗: ${response.status}`);
+        }
+
+        const { ticket } = await response.json();
+        if (closed) {
+          return;
+        }
+        source = new EventSource(`${API_BASE_URL}/api/events?ticket=${encodeURIComponent(ticket)}`);
+        for (const type of ['cycle_found', 'cycle_broken']) {
+          source.addEventListener(type, (event) => {
+            onEvent(type, JSON.parse((event as MessageEvent).data));
+          });
+        }
+        // 票證只能使用一次，EventSource 自動重連會被拒絕，因此關閉後換取新票證
+        source.onerror = () => {
+          source?.close();
+          source = null;
+          reconnect();
+        };
+      } catch (error) {
+        console.error('Error subscribing to match events:', error);
+        reconnect();
+      }
+    };
+
+    connect();
+    return () => {
+      closed = true;
+      clearTimeout(retry);
+      source?.close();
+    };
+  }
 }
 
 export default ApiService;
\ No newline at end of file
@@ -1,9 +1,11 @@
 // User related types
+// 登入的帳號（users 表），以 id 識別；email 只是聯絡資料，可能由其他 Google 帳號沿用
 export interface UserInfo {
+    id: number;
+    google_sub: string;
     name: string;
     picture: string;
     email: string;
-    google_id: string;
   }
   
   // Teacher types
@@ -14,12 +16,26 @@ export interface UserInfo {
     current_county: string;
     current_district: string;
     current_school: string;
+    school_code?: string;  // 學校名錄代碼
     target_counties: string[];
     target_districts: string[];
     subject: string;
     display_id?: string;
     google_id?: string;
     year: number;
+    school_level?: string;  // 國小/國中/高中，未提供時後端視為國小
+    version?: number;  // 更新時以 If-Match 帶回，用來偵測同時編輯
+    updated_at?: string;
+    user_id?: number;  // 所屬帳號，由伺服器在登入或新增時連結
+  }
+  
+  // 學校名錄
+  export interface School {
+    code?: string;  // 教育部學校代碼，內建的範例名錄沒有代碼
+    name: string;
+    county: string;
+    district: string;
+    level: string;
   }
   
   // Match types
@@ -27,9 +43,59 @@ export interface UserInfo {
     id: number | string;
     match_type: "direct" | "triangle" | "cycle" | string;
     teachers: Teacher[];
+    moves?: CycleMove[];
+    total_distance_km?: number | null;
     createdAt?: string;
   }
   
+  // 循環中的一次調動，teachers[i] 調往 teachers[i + 1] 的位置
+  export interface CycleMove {
+    teacher_id?: number;
+    from_county: string;
+    from_district: string;
+    to_county: string;
+    to_district: string;
+    distance_km?: number | null;
+  }
+  
+  // 調動輪次（民國年度）
+  export interface Round {
+    year: number;
+    state: "draft" | "open" | "closed" | "archived";
+    registration_opens_at?: string | null;
+    registration_closes_at?: string | null;
+    created_at?: string;
+  }
+  
+  // 帳號權限：縣市管理者只能管理 admin_county 的登記
+  export type Role = "user" | "county_admin" | "super_admin";
+
+  export interface Account {
+    id: number;
+    google_sub: string;
+    email: string;
+    name?: string | null;
+    picture?: string | null;
+    role: Role;
+    admin_county?: string | null;
+  }
+
+  // /api/me 的回應
+  export interface MeResponse {
+    user: Account;
+    teachers: Teacher[];
+  }
+  
+  // 網站公告
+  export interface Announcement {
+    id: number;
+    title?: string | null;
+    body: string;
+    severity: "info" | "warning" | "critical";
+    publish_at: string;
+    expire_at?: string | null;
+  }
+  
   // 用戶資料響應，包含多個教師記錄
   export interface UserResponse {
     userInfo: UserInfo;
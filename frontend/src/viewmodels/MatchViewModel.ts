@@ -19,6 +19,17 @@ export const useMatchViewModel = (currentTeacher: Teacher | null, allTeachers: T
     }
   }, []);
   
+  // 有與自己相關的循環出現或失效時重新取得配對結果
+  useEffect(() => {
+    if (!localStorage.getItem('auth_token')) {
+      return;
+    }
+
+    return ApiService.subscribeMatchEvents(() => {
+      fetchMatches();
+    });
+  }, []);
+
   // Get current Taiwanese year
   const currentYear = new Date().getFullYear() - 1911;
 
@@ -47,19 +58,14 @@ export const useMatchViewModel = (currentTeacher: Teacher | null, allTeachers: T
   };
 
   // Create a new teacher
-  const createTeacher = async (teacher: Teacher, googleId: string | undefined) => {
-    if (!googleId) {
-      throw new Error("Google ID 未設定，請重新登入");
-    }
-
+  const createTeacher = async (teacher: Teacher) => {
     setLoading(true);
     setError("");
     
     try {
-      // Ensure we include google_id and current year
+      // 登記由伺服器連結到登入的帳號，這裡只補上目前年度
       const teacherWithMetadata = {
         ...teacher,
-        google_id: googleId,
         year: currentYear,
       };
   
@@ -1,6 +1,6 @@
 import { useState, useEffect } from 'react';
 import { Teacher, UserInfo, UserResponse } from '../types';
-import ApiService from '../services/ApiService';
+import ApiService, { toUserInfo } from '../services/ApiService';
 
 export const useUserViewModel = () => {
   const [userInfo, setUserInfo] = useState<UserInfo | null>(null);
@@ -16,32 +16,14 @@ export const useUserViewModel = () => {
     const storedUserInfo = localStorage.getItem('user_info');
     if (storedUserInfo) {
       try {
-        const parsedUserInfo = JSON.parse(storedUserInfo);
-        setUserInfo(parsedUserInfo);
-        
-        // Fetch teacher data for this user
-        const fetchTeachersData = async () => {
-          if (parsedUserInfo.google_id) {
-            try {
-              const teachers = await ApiService.getTeachersByGoogleId(parsedUserInfo.google_id);
-              
-              if (teachers && teachers.length > 0) {
-                setAllTeachers(teachers);
-                setCurrentTeacher(teachers[0]);
-              } else {
-                console.warn("No teachers found for user");
-              }
-            } catch (err) {
-              console.error("Failed to restore teacher data:", err);
-            }
-          }
-        };
-        
-        fetchTeachersData();
+        setUserInfo(JSON.parse(storedUserInfo));
       } catch (err) {
         console.error("Failed to parse stored user info:", err);
       }
     }
+
+    // 帳號與登記以伺服器的 /api/me 為準
+    restoreSession().catch((err) => console.error("Failed to restore teacher data:", err));
   }, []);
   
   const handleGoogleLoginSuccess = async (credentialResponse: any) => {
@@ -87,7 +69,6 @@ export const useUserViewModel = () => {
     
     // Clear localStorage
     localStorage.removeItem('auth_token');
-    localStorage.removeItem('google_id');
     localStorage.removeItem('user_info');
   };
 
@@ -180,49 +161,26 @@ export const useUserViewModel = () => {
     }
   };
 
-  // Debug login (for development/testing)
-  const debugLogin = async (googleId: string) => {
-    if (!googleId) return null;
-    
-    const mockUserInfo = {
-      name: "Debug User",
-      picture: "",
-      email: googleId,
-      google_id: googleId,
-    };
-    
-    setUserInfo(mockUserInfo);
+  // 以保存的 token 向 /api/me 取得目前的帳號與登記，token 失效時返回 null
+  const restoreSession = async (): Promise<UserResponse | null> => {
     setLoading(true);
 
     try {
-      // Fetch teacher data for this Google ID
-      const teachers = await ApiService.getTeachersByGoogleId(googleId);
-      
-      setAllTeachers(teachers);
-      
-      if (teachers.length > 0) {
-        // Set first teacher as current
-        setCurrentTeacher(teachers[0]);
-        return { 
-          userInfo: mockUserInfo, 
-          teacher: teachers[0],
-          teachers: teachers
-        };
+      const me = await ApiService.getMe();
+      if (!me) {
+        return null;
       }
-      
-      // No teacher data
-      return { 
-        userInfo: mockUserInfo, 
-        teacher: null,
-        teachers: []
-      };
-    } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : "獲取教師資料失敗，請稍後再試";
-      setError(errorMessage);
-      return { 
-        userInfo: mockUserInfo, 
-        teacher: null,
-        teachers: []
+
+      const restoredUserInfo = toUserInfo(me.user);
+      setUserInfo(restoredUserInfo);
+      localStorage.setItem('user_info', JSON.stringify(restoredUserInfo));
+      setAllTeachers(me.teachers);
+      setCurrentTeacher(me.teachers.length > 0 ? me.teachers[0] : null);
+
+      return {
+        userInfo: restoredUserInfo,
+        teacher: me.teachers[0] || null,
+        teachers: me.teachers
       };
     } finally {
       setLoading(false);
@@ -247,6 +205,6 @@ export const useUserViewModel = () => {
     updateTeacher,
     deleteTeacher,
     logout,
-    debugLogin
+    restoreSession
   };
 };
\ No newline at end of file
@@ -3,7 +3,6 @@
 interface ImportMetaEnv {
     readonly VITE_API_URL: string
     readonly VITE_GOOGLE_CLIENT_ID: string
-    readonly VITE_DEBUG_AUTH: string
     // more env variables...
   }
   